@@ -0,0 +1,58 @@
+-- Initial schema for the UHI gateway storage layer.
+--
+-- Every entity is stored as a JSONB document alongside the columns that the
+-- Storage trait filters on, so the models can evolve without a migration for
+-- every new optional field.
+
+CREATE TABLE IF NOT EXISTS providers (
+    id          TEXT PRIMARY KEY,
+    data        JSONB NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS catalogs (
+    provider_id TEXT PRIMARY KEY REFERENCES providers (id) ON DELETE CASCADE,
+    data        JSONB NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS orders (
+    id          TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    customer_id TEXT NOT NULL,
+    data        JSONB NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_orders_provider_id ON orders (provider_id);
+CREATE INDEX IF NOT EXISTS idx_orders_customer_id ON orders (customer_id);
+
+CREATE TABLE IF NOT EXISTS fulfillments (
+    id          TEXT PRIMARY KEY,
+    provider_id TEXT NOT NULL,
+    data        JSONB NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_fulfillments_provider_id ON fulfillments (provider_id);
+
+CREATE TABLE IF NOT EXISTS subscribers (
+    id          TEXT PRIMARY KEY,
+    type_field  TEXT NOT NULL,
+    domain      TEXT NOT NULL,
+    data        JSONB NOT NULL,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_subscribers_type_domain ON subscribers (type_field, domain);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    transaction_id TEXT PRIMARY KEY,
+    data           JSONB NOT NULL,
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
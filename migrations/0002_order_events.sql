@@ -0,0 +1,11 @@
+-- Append-only history of changes to orders and their fulfillments. Events
+-- are listed in insertion order, which `seq` preserves.
+
+CREATE TABLE IF NOT EXISTS order_events (
+    seq         BIGSERIAL PRIMARY KEY,
+    order_id    TEXT NOT NULL,
+    data        JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_events_order_id ON order_events (order_id, seq);
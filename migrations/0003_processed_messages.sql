@@ -0,0 +1,12 @@
+-- Messages claimed for processing, used to deduplicate retried protocol
+-- messages. A record deduplicates replays of its message until expires_at,
+-- after which the key may be claimed again.
+
+CREATE TABLE IF NOT EXISTS processed_messages (
+    subscriber_id TEXT NOT NULL,
+    message_id    TEXT NOT NULL,
+    action        TEXT NOT NULL,
+    data          JSONB NOT NULL,
+    expires_at    TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (subscriber_id, message_id, action)
+);
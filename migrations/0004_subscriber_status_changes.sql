@@ -0,0 +1,12 @@
+-- Append-only audit trail of network participant status changes, listed in
+-- insertion order, which `seq` preserves.
+
+CREATE TABLE IF NOT EXISTS subscriber_status_changes (
+    seq           BIGSERIAL PRIMARY KEY,
+    subscriber_id TEXT NOT NULL,
+    data          JSONB NOT NULL,
+    changed_at    TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_subscriber_status_changes_subscriber_id
+    ON subscriber_status_changes (subscriber_id, seq);
@@ -0,0 +1,21 @@
+-- Ratings given for orders, one per rated entity and order, and the running
+-- totals of every rated entity. Totals are incremented in place so that
+-- concurrent ratings of the same entity are all counted.
+
+CREATE TABLE IF NOT EXISTS order_ratings (
+    order_id    TEXT NOT NULL,
+    category    TEXT NOT NULL,
+    entity_id   TEXT NOT NULL,
+    data        JSONB NOT NULL,
+    rated_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (order_id, category, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS rating_aggregates (
+    category    TEXT NOT NULL,
+    entity_id   TEXT NOT NULL,
+    count       BIGINT NOT NULL,
+    total       BIGINT NOT NULL,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (category, entity_id)
+);
@@ -0,0 +1,8 @@
+-- Latest tracking information of each fulfillment, replaced on every
+-- tracking update from the provider.
+
+CREATE TABLE IF NOT EXISTS fulfillment_tracking (
+    fulfillment_id TEXT PRIMARY KEY,
+    data           JSONB NOT NULL,
+    updated_at     TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,11 @@
+-- Catalogs returned by HSPs for network searches, one row per provider and
+-- search message so that concurrent responses never overwrite each other.
+
+CREATE TABLE IF NOT EXISTS search_responses (
+    transaction_id TEXT NOT NULL,
+    message_id     TEXT NOT NULL,
+    provider_id    TEXT NOT NULL,
+    data           JSONB NOT NULL,
+    received_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (transaction_id, message_id, provider_id)
+);
@@ -0,0 +1,360 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use blake2::{Blake2b512, Digest};
+use std::fmt;
+use thiserror::Error;
+
+/// Header carrying the sender's signature
+pub const AUTHORIZATION: &str = "Authorization";
+
+/// Header carrying the signature of a gateway relaying the request
+pub const GATEWAY_AUTHORIZATION: &str = "X-Gateway-Authorization";
+
+/// Signature algorithm supported for request signing
+pub const ED25519: &str = "ed25519";
+
+/// Headers covered by signatures produced by the gateway
+pub const DEFAULT_SIGNED_HEADERS: [&str; 3] = ["(created)", "(expires)", "digest"];
+
+/// Errors raised while parsing or verifying signature headers
+#[derive(Debug, Error, PartialEq)]
+pub enum AuthError {
+    #[error("Missing {0} header")]
+    MissingHeader(String),
+
+    #[error("Malformed signature header: {0}")]
+    MalformedHeader(String),
+
+    #[error("Unsupported signature algorithm: {0}")]
+    UnsupportedAlgorithm(String),
+
+    #[error("Signature has expired")]
+    Expired,
+
+    #[error("Signature is not valid yet")]
+    NotYetValid,
+
+    #[error("Unknown subscriber: {0}")]
+    UnknownSubscriber(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Not a gateway: {0}")]
+    NotGateway(String),
+
+    #[error("Invalid signing key: {0}")]
+    InvalidKey(String),
+}
+
+impl AuthError {
+    /// Protocol error code for this error
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthError::MissingHeader(_) => "MISSING_SIGNATURE",
+            AuthError::MalformedHeader(_) => "MALFORMED_SIGNATURE",
+            AuthError::UnsupportedAlgorithm(_) => "UNSUPPORTED_ALGORITHM",
+            AuthError::Expired => "SIGNATURE_EXPIRED",
+            AuthError::NotYetValid => "SIGNATURE_NOT_YET_VALID",
+            AuthError::UnknownSubscriber(_) => "UNKNOWN_SUBSCRIBER",
+            AuthError::InvalidSignature(_) => "INVALID_SIGNATURE",
+            AuthError::NotGateway(_) => "NOT_GATEWAY",
+            AuthError::InvalidKey(_) => "INVALID_KEY",
+        }
+    }
+}
+
+/// Parsed `Signature` authorization header.
+///
+/// The header has the form
+/// `Signature keyId="{subscriber_id}|{unique_key_id}|{algorithm}",algorithm="ed25519",created="..",expires="..",headers="(created) (expires) digest",signature=".."`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureHeader {
+    /// Subscriber that signed the request
+    pub subscriber_id: String,
+
+    /// Registry key used for signing
+    pub unique_key_id: String,
+
+    /// Signature algorithm
+    pub algorithm: String,
+
+    /// Unix timestamp when the signature was created
+    pub created: i64,
+
+    /// Unix timestamp when the signature expires
+    pub expires: i64,
+
+    /// Headers covered by the signature, in signing order
+    pub headers: Vec<String>,
+
+    /// Base64 encoded signature
+    pub signature: String,
+}
+
+impl SignatureHeader {
+    /// Parse a signature header value
+    pub fn parse(value: &str) -> Result<Self, AuthError> {
+        let params = value
+            .trim()
+            .strip_prefix("Signature ")
+            .ok_or_else(|| AuthError::MalformedHeader("expected 'Signature' scheme".to_string()))?;
+
+        let mut key_id = None;
+        let mut algorithm = None;
+        let mut created = None;
+        let mut expires = None;
+        let mut headers = None;
+        let mut signature = None;
+
+        for param in params.split(',') {
+            let (name, value) = param.trim().split_once('=').ok_or_else(|| {
+                AuthError::MalformedHeader(format!("invalid parameter '{}'", param))
+            })?;
+            let value = value.trim().trim_matches('"').to_string();
+
+            match name.trim() {
+                "keyId" => key_id = Some(value),
+                "algorithm" => algorithm = Some(value),
+                "created" => created = Some(parse_timestamp("created", &value)?),
+                "expires" => expires = Some(parse_timestamp("expires", &value)?),
+                "headers" => headers = Some(value),
+                "signature" => signature = Some(value),
+                // Unknown parameters are ignored for forward compatibility
+                _ => {}
+            }
+        }
+
+        let key_id = key_id.ok_or_else(|| missing_param("keyId"))?;
+        let mut key_parts = key_id.split('|');
+        let (subscriber_id, unique_key_id, key_algorithm) = match (
+            key_parts.next(),
+            key_parts.next(),
+            key_parts.next(),
+            key_parts.next(),
+        ) {
+            (Some(subscriber), Some(key), Some(algorithm), None)
+                if !subscriber.is_empty() && !key.is_empty() =>
+            {
+                (
+                    subscriber.to_string(),
+                    key.to_string(),
+                    algorithm.to_string(),
+                )
+            }
+            _ => {
+                return Err(AuthError::MalformedHeader(format!(
+                    "keyId '{}' must be subscriber_id|unique_key_id|algorithm",
+                    key_id
+                )))
+            }
+        };
+
+        let algorithm = algorithm.unwrap_or_else(|| key_algorithm.clone());
+        if algorithm != key_algorithm {
+            return Err(AuthError::MalformedHeader(format!(
+                "algorithm '{}' does not match keyId algorithm '{}'",
+                algorithm, key_algorithm
+            )));
+        }
+
+        let headers = headers
+            .map(|h| h.split_whitespace().map(str::to_lowercase).collect())
+            .unwrap_or_else(|| {
+                DEFAULT_SIGNED_HEADERS
+                    .iter()
+                    .map(|h| h.to_string())
+                    .collect()
+            });
+
+        Ok(Self {
+            subscriber_id,
+            unique_key_id,
+            algorithm,
+            created: created.ok_or_else(|| missing_param("created"))?,
+            expires: expires.ok_or_else(|| missing_param("expires"))?,
+            headers,
+            signature: signature.ok_or_else(|| missing_param("signature"))?,
+        })
+    }
+
+    /// Key ID in `subscriber_id|unique_key_id|algorithm` form
+    pub fn key_id(&self) -> String {
+        format!(
+            "{}|{}|{}",
+            self.subscriber_id, self.unique_key_id, self.algorithm
+        )
+    }
+
+    /// Check the validity window against `now`, allowing `skew` seconds of
+    /// clock drift
+    pub fn check_validity(&self, now: i64, skew: i64) -> Result<(), AuthError> {
+        if self.created > now + skew {
+            return Err(AuthError::NotYetValid);
+        }
+        if self.expires + skew < now {
+            return Err(AuthError::Expired);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SignatureHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Signature keyId=\"{}\",algorithm=\"{}\",created=\"{}\",expires=\"{}\",headers=\"{}\",signature=\"{}\"",
+            self.key_id(),
+            self.algorithm,
+            self.created,
+            self.expires,
+            self.headers.join(" "),
+            self.signature
+        )
+    }
+}
+
+/// Compute the `BLAKE-512=` digest of a request body
+pub fn body_digest(body: &[u8]) -> String {
+    format!("BLAKE-512={}", BASE64.encode(Blake2b512::digest(body)))
+}
+
+/// Build the signing string for the given covered headers.
+///
+/// `(created)`, `(expires)` and `digest` are derived from the signature
+/// parameters and the body; any other header is read through `header_value`.
+pub fn signing_string<F>(
+    headers: &[String],
+    created: i64,
+    expires: i64,
+    body: &[u8],
+    header_value: F,
+) -> Result<String, AuthError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut lines = Vec::with_capacity(headers.len());
+
+    for header in headers {
+        let line = match header.as_str() {
+            "(created)" => format!("(created): {}", created),
+            "(expires)" => format!("(expires): {}", expires),
+            "digest" => format!("digest: {}", body_digest(body)),
+            name => {
+                let value = header_value(name).ok_or_else(|| {
+                    AuthError::MalformedHeader(format!("signed header '{}' is missing", name))
+                })?;
+                format!("{}: {}", name, value)
+            }
+        };
+        lines.push(line);
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn parse_timestamp(name: &str, value: &str) -> Result<i64, AuthError> {
+    value
+        .parse()
+        .map_err(|_| AuthError::MalformedHeader(format!("{} must be a unix timestamp", name)))
+}
+
+fn missing_param(name: &str) -> AuthError {
+    AuthError::MalformedHeader(format!("missing {} parameter", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "Signature keyId=\"hsp.example.com|key-1|ed25519\",algorithm=\"ed25519\",created=\"1641287875\",expires=\"1641291475\",headers=\"(created) (expires) digest\",signature=\"c2lnbmF0dXJl\"";
+
+    #[test]
+    fn test_parse_signature_header() {
+        let header = SignatureHeader::parse(HEADER).unwrap();
+
+        assert_eq!(header.subscriber_id, "hsp.example.com");
+        assert_eq!(header.unique_key_id, "key-1");
+        assert_eq!(header.algorithm, ED25519);
+        assert_eq!(header.created, 1641287875);
+        assert_eq!(header.expires, 1641291475);
+        assert_eq!(header.headers, DEFAULT_SIGNED_HEADERS);
+        assert_eq!(header.signature, "c2lnbmF0dXJl");
+    }
+
+    #[test]
+    fn test_signature_header_round_trip() {
+        let header = SignatureHeader::parse(HEADER).unwrap();
+        assert_eq!(header.to_string(), HEADER);
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_headers() {
+        assert!(matches!(
+            SignatureHeader::parse("Bearer token"),
+            Err(AuthError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            SignatureHeader::parse(&HEADER.replace("|key-1|ed25519", "")),
+            Err(AuthError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            SignatureHeader::parse(&HEADER.replace("created=\"1641287875\",", "")),
+            Err(AuthError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            SignatureHeader::parse(&HEADER.replace("algorithm=\"ed25519\"", "algorithm=\"rsa\"")),
+            Err(AuthError::MalformedHeader(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_validity() {
+        let header = SignatureHeader::parse(HEADER).unwrap();
+
+        assert!(header.check_validity(1641288000, 0).is_ok());
+        assert_eq!(
+            header.check_validity(1641291500, 0),
+            Err(AuthError::Expired)
+        );
+        assert!(header.check_validity(1641291500, 60).is_ok());
+        assert_eq!(
+            header.check_validity(1641287000, 0),
+            Err(AuthError::NotYetValid)
+        );
+    }
+
+    #[test]
+    fn test_signing_string() {
+        let headers: Vec<String> = DEFAULT_SIGNED_HEADERS
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+        let signing_string = signing_string(&headers, 10, 20, b"{}", |_| None).unwrap();
+
+        assert_eq!(
+            signing_string,
+            format!(
+                "(created): 10\n(expires): 20\ndigest: {}",
+                body_digest(b"{}")
+            )
+        );
+        assert!(body_digest(b"{}").starts_with("BLAKE-512="));
+        assert_ne!(body_digest(b"{}"), body_digest(b"{ }"));
+    }
+
+    #[test]
+    fn test_signing_string_requires_covered_headers() {
+        let headers = vec!["(created)".to_string(), "content-type".to_string()];
+
+        let result = signing_string(&headers, 10, 20, b"", |_| None);
+        assert!(matches!(result, Err(AuthError::MalformedHeader(_))));
+
+        let result = signing_string(&headers, 10, 20, b"", |_| {
+            Some("application/json".to_string())
+        });
+        assert_eq!(
+            result.unwrap(),
+            "(created): 10\ncontent-type: application/json"
+        );
+    }
+}
@@ -0,0 +1,610 @@
+use super::header::{
+    signing_string, AuthError, SignatureHeader, AUTHORIZATION, DEFAULT_SIGNED_HEADERS,
+    GATEWAY_AUTHORIZATION,
+};
+use crate::config::{AppConfig, AuthConfig};
+use crate::models::ack::{AckResponse, ProtocolError, CORE_ERROR};
+use crate::models::network_registry::{
+    ParticipantStatus, ParticipantType, SignatureAlgorithm, Subscriber,
+};
+use crate::services::{NetworkRegistryService, ServiceError};
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::HeaderMap;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use chrono::Utc;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Subscriber whose signature was verified for the current request.
+///
+/// Inserted into the request extensions by [`SignatureAuth`]; handlers can
+/// read it with `web::ReqData<AuthenticatedSubscriber>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticatedSubscriber {
+    /// Subscriber that signed the `Authorization` header
+    pub subscriber_id: String,
+
+    /// Registry key used for the signature
+    pub unique_key_id: String,
+
+    /// URL registered for the subscriber in the network registry
+    pub subscriber_url: String,
+
+    /// Gateway that signed the `X-Gateway-Authorization` header, if any
+    pub gateway_id: Option<String>,
+}
+
+/// Participants that may sign protocol requests and countersign as gateways
+const PROTOCOL_SIGNERS: &[ParticipantStatus] = &[ParticipantStatus::Active];
+
+/// Participants that may sign requests completing their own onboarding
+const ONBOARDING_SIGNERS: &[ParticipantStatus] =
+    &[ParticipantStatus::Subscribed, ParticipantStatus::Active];
+
+/// Middleware verifying the `Authorization` and `X-Gateway-Authorization`
+/// signature headers of protocol requests against the network registry.
+///
+/// Settings are read from the `[auth]` section of the application config;
+/// requests failing verification are rejected with a NACK.
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureAuth {
+    /// Statuses of the participants whose signatures are accepted
+    signers: &'static [ParticipantStatus],
+}
+
+impl SignatureAuth {
+    /// Accept signatures of ACTIVE participants only, as on protocol endpoints
+    pub fn new() -> Self {
+        Self {
+            signers: PROTOCOL_SIGNERS,
+        }
+    }
+
+    /// Also accept signatures of SUBSCRIBED participants, which register
+    /// keys and verify their domain before an admin activates them
+    pub fn onboarding() -> Self {
+        Self {
+            signers: ONBOARDING_SIGNERS,
+        }
+    }
+}
+
+impl Default for SignatureAuth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SignatureAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = SignatureAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SignatureAuthMiddleware {
+            service: Rc::new(service),
+            signers: self.signers,
+        }))
+    }
+}
+
+pub struct SignatureAuthMiddleware<S> {
+    service: Rc<S>,
+    signers: &'static [ParticipantStatus],
+}
+
+impl<S, B> Service<ServiceRequest> for SignatureAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let signers = self.signers;
+
+        Box::pin(async move {
+            let (config, realm) = match req.app_data::<web::Data<AppConfig>>() {
+                Some(app_config) => (
+                    app_config.auth.clone(),
+                    app_config.gateway.subscriber_id.clone(),
+                ),
+                None => (AuthConfig::default(), String::new()),
+            };
+
+            if !config.enabled {
+                let response = service.call(req).await?;
+                return Ok(response.map_into_left_body());
+            }
+
+            // Buffer the body for the digest and hand it back to the handler
+            let body = req.extract::<web::Bytes>().await?;
+            req.set_payload(Payload::from(body.clone()));
+
+            let registry = req.app_data::<web::Data<NetworkRegistryService>>().cloned();
+            let outcome = match registry {
+                Some(registry) => {
+                    verify_request(
+                        req.headers(),
+                        &body,
+                        &registry,
+                        signers,
+                        config.clock_skew_secs,
+                    )
+                    .await
+                }
+                None => Err(AuthError::UnknownSubscriber(
+                    "network registry is not available".to_string(),
+                )),
+            };
+
+            match outcome {
+                Ok(subscriber) => {
+                    req.extensions_mut().insert(subscriber);
+                    let response = service.call(req).await?;
+                    Ok(response.map_into_left_body())
+                }
+                Err(err) => {
+                    tracing::warn!("Rejected request to {}: {}", req.path(), err);
+                    let response = unauthorized(&realm, err);
+                    Ok(req.into_response(response).map_into_right_body())
+                }
+            }
+        })
+    }
+}
+
+/// Verify the signature headers of a request, accepting senders with one of
+/// the `signers` statuses
+async fn verify_request(
+    headers: &HeaderMap,
+    body: &[u8],
+    registry: &NetworkRegistryService,
+    signers: &[ParticipantStatus],
+    clock_skew_secs: i64,
+) -> Result<AuthenticatedSubscriber, AuthError> {
+    let authorization = header_str(headers, AUTHORIZATION)?
+        .ok_or_else(|| AuthError::MissingHeader(AUTHORIZATION.to_string()))?;
+    let (header, signer) = verify_header(
+        authorization,
+        headers,
+        body,
+        registry,
+        signers,
+        clock_skew_secs,
+    )
+    .await?;
+
+    // A relaying gateway countersigns the request with its own key
+    let gateway_id = match header_str(headers, GATEWAY_AUTHORIZATION)? {
+        Some(value) => {
+            let (_, gateway) = verify_header(
+                value,
+                headers,
+                body,
+                registry,
+                PROTOCOL_SIGNERS,
+                clock_skew_secs,
+            )
+            .await?;
+            if gateway.type_field != ParticipantType::Gateway.code() {
+                return Err(AuthError::NotGateway(format!(
+                    "{} is registered as {} and cannot sign {}",
+                    gateway.id, gateway.type_field, GATEWAY_AUTHORIZATION
+                )));
+            }
+            Some(gateway.id)
+        }
+        None => None,
+    };
+
+    Ok(AuthenticatedSubscriber {
+        subscriber_id: header.subscriber_id,
+        unique_key_id: header.unique_key_id,
+        subscriber_url: signer.url,
+        gateway_id,
+    })
+}
+
+/// Verify a single signature header value, returning it with the signer's
+/// registry entry
+async fn verify_header(
+    value: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+    registry: &NetworkRegistryService,
+    signers: &[ParticipantStatus],
+    clock_skew_secs: i64,
+) -> Result<(SignatureHeader, Subscriber), AuthError> {
+    let header = SignatureHeader::parse(value)?;
+    let algorithm = header
+        .algorithm
+        .parse::<SignatureAlgorithm>()
+        .map_err(|_| AuthError::UnsupportedAlgorithm(header.algorithm.clone()))?;
+
+    header.check_validity(Utc::now().timestamp(), clock_skew_secs)?;
+
+    if !DEFAULT_SIGNED_HEADERS
+        .iter()
+        .all(|required| header.headers.iter().any(|h| h == required))
+    {
+        return Err(AuthError::MalformedHeader(format!(
+            "signature must cover {}",
+            DEFAULT_SIGNED_HEADERS.join(" ")
+        )));
+    }
+
+    let message = signing_string(
+        &header.headers,
+        header.created,
+        header.expires,
+        body,
+        |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        },
+    )?;
+
+    let subscriber = registry
+        .authenticate(
+            &header.subscriber_id,
+            &header.unique_key_id,
+            algorithm,
+            &header.signature,
+            message.as_bytes(),
+            signers,
+        )
+        .await
+        .map_err(|err| match err {
+            ServiceError::NotFound(_) => AuthError::UnknownSubscriber(header.subscriber_id.clone()),
+            other => AuthError::InvalidSignature(other.to_string()),
+        })?;
+
+    Ok((header, subscriber))
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Result<Option<&'a str>, AuthError> {
+    headers
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .map_err(|_| AuthError::MalformedHeader(format!("{} is not valid ASCII", name)))
+        })
+        .transpose()
+}
+
+/// Build the NACK returned for requests failing authentication
+fn unauthorized(realm: &str, err: AuthError) -> HttpResponse {
+    let error = ProtocolError::new(CORE_ERROR, err.code(), err.to_string());
+
+    HttpResponse::Unauthorized()
+        .insert_header((
+            "WWW-Authenticate",
+            format!(
+                "Signature realm=\"{}\",headers=\"{}\"",
+                realm,
+                DEFAULT_SIGNED_HEADERS.join(" ")
+            ),
+        ))
+        .json(AckResponse::nack(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::header::ED25519;
+    use crate::models::ack::AckStatus;
+    use crate::models::network_registry::Subscriber;
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::Storage;
+    use actix_web::{test as actix_test, App};
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    use ring::signature::Ed25519KeyPair;
+    use std::sync::Arc;
+
+    const BODY: &str = r#"{"context":{"action":"search"},"message":{}}"#;
+
+    /// Registered subscriber together with its signing key
+    struct TestSigner {
+        subscriber_id: String,
+        key_pair: Ed25519KeyPair,
+    }
+
+    impl TestSigner {
+        fn sign(&self, body: &str, created: i64, expires: i64) -> String {
+            let headers: Vec<String> = DEFAULT_SIGNED_HEADERS
+                .iter()
+                .map(|h| h.to_string())
+                .collect();
+            let message =
+                signing_string(&headers, created, expires, body.as_bytes(), |_| None).unwrap();
+
+            SignatureHeader {
+                subscriber_id: self.subscriber_id.clone(),
+                unique_key_id: "key-1".to_string(),
+                algorithm: ED25519.to_string(),
+                created,
+                expires,
+                headers,
+                signature: BASE64.encode(self.key_pair.sign(message.as_bytes()).as_ref()),
+            }
+            .to_string()
+        }
+
+        fn sign_now(&self, body: &str) -> String {
+            let now = Utc::now().timestamp();
+            self.sign(body, now, now + 300)
+        }
+    }
+
+    async fn register_signer(storage: &Arc<dyn Storage>, subscriber_id: &str) -> TestSigner {
+        register_participant(storage, subscriber_id, ParticipantType::Eua).await
+    }
+
+    async fn register_participant(
+        storage: &Arc<dyn Storage>,
+        subscriber_id: &str,
+        participant_type: ParticipantType,
+    ) -> TestSigner {
+        let (public_key, private_key) = NetworkRegistryService::generate_test_keypair().unwrap();
+        let pkcs8 = BASE64.decode(private_key).unwrap();
+
+        storage
+            .register_subscriber(Subscriber {
+                id: subscriber_id.to_string(),
+                type_field: participant_type.code().to_string(),
+                domain: "nic2004:85111".to_string(),
+                city: None,
+                country: None,
+                url: format!("https://{}/api/v1", subscriber_id),
+                status: ParticipantStatus::Active,
+                public_key,
+                encryption_public_key: None,
+                keys: Vec::new(),
+                domain_verification: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        TestSigner {
+            subscriber_id: subscriber_id.to_string(),
+            key_pair: Ed25519KeyPair::from_pkcs8(&pkcs8).unwrap(),
+        }
+    }
+
+    async fn echo_signer(
+        body: web::Bytes,
+        signer: web::ReqData<AuthenticatedSubscriber>,
+    ) -> HttpResponse {
+        assert_eq!(body, BODY);
+        HttpResponse::Ok().json(signer.into_inner().subscriber_id)
+    }
+
+    async fn send(
+        storage: Arc<dyn Storage>,
+        headers: Vec<(&'static str, String)>,
+        body: &str,
+    ) -> actix_web::dev::ServiceResponse {
+        send_with(SignatureAuth::new(), storage, headers, body).await
+    }
+
+    async fn send_with(
+        auth: SignatureAuth,
+        storage: Arc<dyn Storage>,
+        headers: Vec<(&'static str, String)>,
+        body: &str,
+    ) -> actix_web::dev::ServiceResponse {
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(
+                    NetworkRegistryService::new(storage).unwrap(),
+                ))
+                .service(
+                    web::scope("")
+                        .wrap(auth)
+                        .route("/search", web::post().to(echo_signer)),
+                ),
+        )
+        .await;
+
+        let mut request = actix_test::TestRequest::post()
+            .uri("/search")
+            .insert_header(("Content-Type", "application/json"))
+            .set_payload(body.to_string());
+        for header in headers {
+            request = request.insert_header(header);
+        }
+
+        actix_test::call_service(&app, request.to_request()).await
+    }
+
+    async fn assert_rejected(response: actix_web::dev::ServiceResponse, code: &str) {
+        assert_eq!(response.status(), 401);
+        assert!(response.headers().contains_key("WWW-Authenticate"));
+
+        let body: AckResponse = actix_test::read_body_json(response).await;
+        assert_eq!(body.message.ack.status, AckStatus::Nack);
+        assert_eq!(body.error.unwrap().code, code);
+    }
+
+    #[actix_rt::test]
+    async fn test_accepts_valid_signature() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+
+        let response = send(storage, vec![(AUTHORIZATION, signer.sign_now(BODY))], BODY).await;
+
+        assert_eq!(response.status(), 200);
+        let subscriber: String = actix_test::read_body_json(response).await;
+        assert_eq!(subscriber, "eua.example.com");
+    }
+
+    #[actix_rt::test]
+    async fn test_rejects_missing_signature() {
+        let response = send(MemoryStorage::empty(), Vec::new(), BODY).await;
+        assert_rejected(response, "MISSING_SIGNATURE").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_rejects_tampered_body() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+
+        let authorization = signer.sign_now(r#"{"context":{"action":"select"},"message":{}}"#);
+        let response = send(storage, vec![(AUTHORIZATION, authorization)], BODY).await;
+
+        assert_rejected(response, "INVALID_SIGNATURE").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_rejects_expired_signature() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+
+        let now = Utc::now().timestamp();
+        let authorization = signer.sign(BODY, now - 600, now - 300);
+        let response = send(storage, vec![(AUTHORIZATION, authorization)], BODY).await;
+
+        assert_rejected(response, "SIGNATURE_EXPIRED").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_rejects_unknown_subscriber() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+
+        let unknown = TestSigner {
+            subscriber_id: "unknown.example.com".to_string(),
+            key_pair: signer.key_pair,
+        };
+        let response = send(storage, vec![(AUTHORIZATION, unknown.sign_now(BODY))], BODY).await;
+
+        assert_rejected(response, "UNKNOWN_SUBSCRIBER").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_rejects_malformed_header() {
+        let response = send(
+            MemoryStorage::empty(),
+            vec![(AUTHORIZATION, "Signature keyId=\"broken\"".to_string())],
+            BODY,
+        )
+        .await;
+        assert_rejected(response, "MALFORMED_SIGNATURE").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_verifies_gateway_signature() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+        let gateway =
+            register_participant(&storage, "gateway.example.com", ParticipantType::Gateway).await;
+
+        let response = send(
+            storage.clone(),
+            vec![
+                (AUTHORIZATION, signer.sign_now(BODY)),
+                (GATEWAY_AUTHORIZATION, gateway.sign_now(BODY)),
+            ],
+            BODY,
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+
+        // A gateway signature over a different body is rejected
+        let response = send(
+            storage.clone(),
+            vec![
+                (AUTHORIZATION, signer.sign_now(BODY)),
+                (GATEWAY_AUTHORIZATION, gateway.sign_now("{}")),
+            ],
+            BODY,
+        )
+        .await;
+        assert_rejected(response, "INVALID_SIGNATURE").await;
+
+        // Only gateways may countersign, not the sender itself
+        let response = send(
+            storage,
+            vec![
+                (AUTHORIZATION, signer.sign_now(BODY)),
+                (GATEWAY_AUTHORIZATION, signer.sign_now(BODY)),
+            ],
+            BODY,
+        )
+        .await;
+        assert_rejected(response, "NOT_GATEWAY").await;
+    }
+
+    #[actix_rt::test]
+    async fn test_subscribed_signers_only_on_onboarding_endpoints() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = register_signer(&storage, "eua.example.com").await;
+        let subscriber = storage.get_subscriber("eua.example.com").await.unwrap();
+        storage
+            .update_subscriber(Subscriber {
+                status: ParticipantStatus::Subscribed,
+                ..subscriber
+            })
+            .await
+            .unwrap();
+
+        let response = send(
+            storage.clone(),
+            vec![(AUTHORIZATION, signer.sign_now(BODY))],
+            BODY,
+        )
+        .await;
+        assert_rejected(response, "INVALID_SIGNATURE").await;
+
+        let response = send_with(
+            SignatureAuth::onboarding(),
+            storage.clone(),
+            vec![(AUTHORIZATION, signer.sign_now(BODY))],
+            BODY,
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+
+        // Gateways countersigning onboarding requests must still be ACTIVE
+        let gateway =
+            register_participant(&storage, "gateway.example.com", ParticipantType::Gateway).await;
+        let subscriber = storage.get_subscriber("gateway.example.com").await.unwrap();
+        storage
+            .update_subscriber(Subscriber {
+                status: ParticipantStatus::Subscribed,
+                ..subscriber
+            })
+            .await
+            .unwrap();
+        let response = send_with(
+            SignatureAuth::onboarding(),
+            storage,
+            vec![
+                (AUTHORIZATION, signer.sign_now(BODY)),
+                (GATEWAY_AUTHORIZATION, gateway.sign_now(BODY)),
+            ],
+            BODY,
+        )
+        .await;
+        assert_rejected(response, "INVALID_SIGNATURE").await;
+    }
+}
@@ -0,0 +1,7 @@
+pub mod header;
+pub mod keys;
+pub mod middleware;
+pub mod signer;
+
+pub use middleware::{AuthenticatedSubscriber, SignatureAuth};
+pub use signer::RequestSigner;
@@ -0,0 +1,332 @@
+use super::header::{
+    signing_string, AuthError, SignatureHeader, AUTHORIZATION, DEFAULT_SIGNED_HEADERS, ED25519,
+    GATEWAY_AUTHORIZATION,
+};
+use crate::config::GatewayConfig;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::Utc;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::RequestBuilder;
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+
+/// Signs requests originated by the gateway with its registry key
+pub struct RequestSigner {
+    /// Subscriber ID of the gateway
+    subscriber_id: String,
+    /// Registry key ID of the signing key
+    unique_key_id: String,
+    /// Ed25519 signing key
+    key_pair: Ed25519KeyPair,
+    /// Validity of produced signatures (in seconds)
+    ttl_secs: i64,
+}
+
+impl RequestSigner {
+    /// Create a signer from an Ed25519 key pair
+    pub fn new(
+        subscriber_id: String,
+        unique_key_id: String,
+        key_pair: Ed25519KeyPair,
+        ttl_secs: i64,
+    ) -> Self {
+        Self {
+            subscriber_id,
+            unique_key_id,
+            key_pair,
+            ttl_secs,
+        }
+    }
+
+    /// Load the gateway's signing key from `signing_private_key`, or from the
+    /// file at `signing_private_key_path`.
+    ///
+    /// When neither is configured, an ephemeral key is only generated if
+    /// `allow_ephemeral_signing_key` is set; its public key must be registered
+    /// before peers can verify our signatures. Otherwise loading fails.
+    pub fn from_config(config: &GatewayConfig) -> Result<Self, AuthError> {
+        let encoded = match (
+            &config.signing_private_key,
+            &config.signing_private_key_path,
+        ) {
+            (Some(key), _) if !key.is_empty() => Some(key.clone()),
+            (_, Some(path)) if !path.is_empty() => {
+                Some(std::fs::read_to_string(path).map_err(|e| {
+                    AuthError::InvalidKey(format!("failed to read {}: {}", path, e))
+                })?)
+            }
+            _ => None,
+        };
+
+        let key_pair = match encoded {
+            Some(encoded) => decode_private_key(&encoded)?,
+            None if !config.allow_ephemeral_signing_key => {
+                return Err(AuthError::InvalidKey(
+                    "no signing key configured; set gateway.signing_private_key or \
+                     gateway.signing_private_key_path"
+                        .to_string(),
+                ))
+            }
+            None => {
+                let key_pair = generate_key_pair()?;
+                tracing::warn!(
+                    "No signing key configured, generated ephemeral key with public key {}",
+                    BASE64.encode(key_pair.public_key().as_ref())
+                );
+                key_pair
+            }
+        };
+
+        Ok(Self::new(
+            config.subscriber_id.clone(),
+            config.unique_key_id.clone(),
+            key_pair,
+            config.signature_ttl_secs,
+        ))
+    }
+
+    /// Base64 encoded public key to register with the network registry
+    pub fn public_key(&self) -> String {
+        BASE64.encode(self.key_pair.public_key().as_ref())
+    }
+
+    /// Sign a request body, returning the signature header
+    pub fn sign(&self, body: &[u8]) -> Result<SignatureHeader, AuthError> {
+        let created = Utc::now().timestamp();
+        let expires = created + self.ttl_secs;
+        let headers: Vec<String> = DEFAULT_SIGNED_HEADERS
+            .iter()
+            .map(|h| h.to_string())
+            .collect();
+
+        let message = signing_string(&headers, created, expires, body, |_| None)?;
+        let signature = self.key_pair.sign(message.as_bytes());
+
+        Ok(SignatureHeader {
+            subscriber_id: self.subscriber_id.clone(),
+            unique_key_id: self.unique_key_id.clone(),
+            algorithm: ED25519.to_string(),
+            created,
+            expires,
+            headers,
+            signature: BASE64.encode(signature.as_ref()),
+        })
+    }
+
+    /// Attach a signed JSON body to an outgoing request.
+    ///
+    /// The gateway signs both as the sender (`Authorization`) and as the
+    /// relaying gateway (`X-Gateway-Authorization`).
+    pub fn sign_request(
+        &self,
+        request: RequestBuilder,
+        body: Vec<u8>,
+    ) -> Result<RequestBuilder, AuthError> {
+        let header = self.sign(&body)?.to_string();
+
+        Ok(request
+            .header(CONTENT_TYPE, "application/json")
+            .header(AUTHORIZATION, header.clone())
+            .header(GATEWAY_AUTHORIZATION, header)
+            .body(body))
+    }
+}
+
+/// Decode a base64 Ed25519 private key given as PKCS#8, a 32 byte seed or a
+/// 64 byte seed + public key pair
+fn decode_private_key(encoded: &str) -> Result<Ed25519KeyPair, AuthError> {
+    let bytes = BASE64
+        .decode(encoded.trim())
+        .map_err(|_| AuthError::InvalidKey("private key is not valid base64".to_string()))?;
+
+    let key_pair = match bytes.len() {
+        32 => Ed25519KeyPair::from_seed_unchecked(&bytes),
+        64 => Ed25519KeyPair::from_seed_and_public_key(&bytes[..32], &bytes[32..]),
+        _ => Ed25519KeyPair::from_pkcs8_maybe_unchecked(&bytes),
+    };
+
+    key_pair.map_err(|e| AuthError::InvalidKey(format!("not an Ed25519 private key: {}", e)))
+}
+
+fn generate_key_pair() -> Result<Ed25519KeyPair, AuthError> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map_err(|_| AuthError::InvalidKey("failed to generate key pair".to_string()))?;
+    Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+        .map_err(|_| AuthError::InvalidKey("failed to parse generated key pair".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{AuthenticatedSubscriber, SignatureAuth};
+    use crate::models::network_registry::{ParticipantStatus, Subscriber};
+    use crate::services::NetworkRegistryService;
+    use crate::storage::memory::MemoryStorage;
+    use crate::storage::Storage;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    fn gateway_config(private_key: Option<String>) -> GatewayConfig {
+        GatewayConfig {
+            subscriber_id: "gateway.example.com".to_string(),
+            signing_private_key: private_key,
+            allow_ephemeral_signing_key: true,
+            ..GatewayConfig::default()
+        }
+    }
+
+    async fn echo_signer(signer: web::ReqData<AuthenticatedSubscriber>) -> HttpResponse {
+        let signer = signer.into_inner();
+        HttpResponse::Ok().json((signer.subscriber_id, signer.gateway_id))
+    }
+
+    // Start a local participant verifying requests with our middleware
+    fn start_participant(storage: Arc<dyn Storage>) -> String {
+        let registry = web::Data::new(NetworkRegistryService::new(storage).unwrap());
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = HttpServer::new(move || {
+            App::new().app_data(registry.clone()).service(
+                web::scope("")
+                    .wrap(SignatureAuth::new())
+                    .route("/on_search", web::post().to(echo_signer)),
+            )
+        })
+        .listen(listener)
+        .unwrap()
+        .workers(1)
+        .run();
+        actix_rt::spawn(server);
+
+        format!("http://127.0.0.1:{}/on_search", port)
+    }
+
+    async fn register_gateway(storage: &Arc<dyn Storage>, signer: &RequestSigner) {
+        storage
+            .register_subscriber(Subscriber {
+                id: "gateway.example.com".to_string(),
+                type_field: "GATEWAY".to_string(),
+                domain: "nic2004:85111".to_string(),
+                city: None,
+                country: None,
+                url: "https://gateway.example.com/api/v1".to_string(),
+                status: ParticipantStatus::Active,
+                public_key: signer.public_key(),
+                encryption_public_key: None,
+                keys: Vec::new(),
+                domain_verification: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn test_loads_configured_keys() {
+        let (public_key, private_key) = NetworkRegistryService::generate_test_keypair().unwrap();
+
+        // PKCS#8 document
+        let signer = RequestSigner::from_config(&gateway_config(Some(private_key))).unwrap();
+        assert_eq!(signer.public_key(), public_key);
+
+        // Raw 32 byte seed
+        let seed = BASE64.encode([7u8; 32]);
+        let from_seed = RequestSigner::from_config(&gateway_config(Some(seed.clone()))).unwrap();
+        let again = RequestSigner::from_config(&gateway_config(Some(seed))).unwrap();
+        assert_eq!(from_seed.public_key(), again.public_key());
+    }
+
+    #[test]
+    fn test_loads_key_from_file() {
+        let (public_key, private_key) = NetworkRegistryService::generate_test_keypair().unwrap();
+        let path = std::env::temp_dir().join(format!("uhi-signing-key-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, format!("{}\n", private_key)).unwrap();
+
+        let config = GatewayConfig {
+            signing_private_key_path: Some(path.to_string_lossy().to_string()),
+            ..gateway_config(None)
+        };
+        let signer = RequestSigner::from_config(&config).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(signer.public_key(), public_key);
+    }
+
+    #[test]
+    fn test_rejects_invalid_keys() {
+        let result = RequestSigner::from_config(&gateway_config(Some("not base64!".to_string())));
+        assert!(matches!(result, Err(AuthError::InvalidKey(_))));
+
+        let result = RequestSigner::from_config(&gateway_config(Some(BASE64.encode([1u8; 10]))));
+        assert!(matches!(result, Err(AuthError::InvalidKey(_))));
+    }
+
+    #[test]
+    fn test_requires_key_unless_ephemeral_keys_are_allowed() {
+        let config = GatewayConfig {
+            allow_ephemeral_signing_key: false,
+            ..gateway_config(None)
+        };
+        let result = RequestSigner::from_config(&config);
+        assert!(matches!(result, Err(AuthError::InvalidKey(_))));
+
+        assert!(RequestSigner::from_config(&gateway_config(None)).is_ok());
+    }
+
+    #[test]
+    fn test_sign_produces_parsable_header() {
+        let signer = RequestSigner::from_config(&gateway_config(None)).unwrap();
+        let header = signer.sign(b"{}").unwrap();
+
+        assert_eq!(
+            header.key_id(),
+            "gateway.example.com|uhi-gateway-key-1|ed25519"
+        );
+        assert_eq!(header.expires - header.created, 300);
+        assert_eq!(SignatureHeader::parse(&header.to_string()).unwrap(), header);
+    }
+
+    #[actix_rt::test]
+    async fn test_signed_request_passes_verification() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let signer = RequestSigner::from_config(&gateway_config(None)).unwrap();
+        register_gateway(&storage, &signer).await;
+        let url = start_participant(storage);
+
+        let client = reqwest::Client::new();
+        let body = br#"{"context":{"action":"on_search"},"message":{}}"#.to_vec();
+        let response = signer
+            .sign_request(client.post(&url), body)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 200);
+        let (subscriber_id, gateway_id): (String, Option<String>) = response.json().await.unwrap();
+        assert_eq!(subscriber_id, "gateway.example.com");
+        assert_eq!(gateway_id.as_deref(), Some("gateway.example.com"));
+    }
+
+    #[actix_rt::test]
+    async fn test_unregistered_key_fails_verification() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let registered = RequestSigner::from_config(&gateway_config(None)).unwrap();
+        register_gateway(&storage, &registered).await;
+        let url = start_participant(storage);
+
+        // Same subscriber ID, different key
+        let signer = RequestSigner::from_config(&gateway_config(None)).unwrap();
+        let response = signer
+            .sign_request(reqwest::Client::new().post(&url), b"{}".to_vec())
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), 401);
+    }
+}
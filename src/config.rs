@@ -1,5 +1,7 @@
+use crate::models::fulfillment::FulfillmentState;
 use config::{Config, ConfigError, Environment, File};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 use std::env;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -9,8 +11,21 @@ pub struct ServerConfig {
     pub workers: usize,
 }
 
+/// Storage backend used to persist gateway data
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// In-memory storage, lost on restart
+    #[default]
+    Memory,
+    /// PostgreSQL storage using the configured connection pool
+    Postgres,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
+    #[serde(default)]
+    pub backend: StorageBackend,
     pub url: String,
     pub max_connections: u32,
     pub min_connections: u32,
@@ -22,11 +37,341 @@ pub struct LoggingConfig {
     pub format: String,
 }
 
+/// Identity the gateway uses when it acts as a network participant
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct GatewayConfig {
+    pub subscriber_id: String,
+    pub subscriber_url: String,
+    /// Registry key ID of the signing key
+    pub unique_key_id: String,
+    /// Base64 encoded Ed25519 private key (PKCS#8 or raw seed)
+    pub signing_private_key: Option<String>,
+    /// File containing the base64 encoded private key, used when
+    /// `signing_private_key` is not set
+    pub signing_private_key_path: Option<String>,
+    /// Validity of outgoing signatures (in seconds)
+    pub signature_ttl_secs: i64,
+    /// Generate a throwaway signing key when none is configured; for local
+    /// development and tests only
+    pub allow_ephemeral_signing_key: bool,
+}
+
+impl Default for GatewayConfig {
+    fn default() -> Self {
+        Self {
+            subscriber_id: "uhi-gateway".to_string(),
+            subscriber_url: "http://127.0.0.1:8080/api/v1".to_string(),
+            unique_key_id: "uhi-gateway-key-1".to_string(),
+            signing_private_key: None,
+            signing_private_key_path: None,
+            signature_ttl_secs: 300,
+            allow_ephemeral_signing_key: false,
+        }
+    }
+}
+
+/// Delivery settings for asynchronous on_* callbacks
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct CallbackConfig {
+    /// Timeout for a single delivery attempt (in seconds)
+    pub timeout_secs: u64,
+    /// Number of retries after the first failed attempt
+    pub max_retries: u32,
+    /// Base delay between retries, doubled on every attempt (in milliseconds)
+    pub retry_backoff_ms: u64,
+    /// Upper bound of the delay between retries (in milliseconds)
+    pub max_retry_backoff_ms: u64,
+}
+
+impl Default for CallbackConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 10,
+            max_retries: 3,
+            retry_backoff_ms: 500,
+            max_retry_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// Verification of signed protocol requests
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthConfig {
+    /// Whether protocol requests must carry valid signatures
+    pub enabled: bool,
+    /// Tolerated clock drift when checking created/expires (in seconds)
+    pub clock_skew_secs: i64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            clock_skew_secs: 30,
+        }
+    }
+}
+
+/// Deduplication of retried protocol messages
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    /// Whether replayed messages are answered from their recorded response
+    pub enabled: bool,
+    /// How long a message is deduplicated after it was first received (in seconds)
+    pub window_secs: i64,
+    /// How long a message being processed holds its claim (in seconds); a
+    /// replay after the lease has lapsed claims the message again
+    pub processing_lease_secs: i64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 86400,
+            processing_lease_secs: 60,
+        }
+    }
+}
+
+/// Network registry administration
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RegistryConfig {
+    /// Subscribers allowed to change participant statuses when signatures
+    /// are verified
+    pub admins: Vec<String>,
+    /// Participants registered as ACTIVE at startup, such as the first
+    /// registry admins, who have no admin to activate them
+    #[serde(default)]
+    pub seed: Vec<SeedSubscriber>,
+}
+
+/// Participant registered by configuration instead of subscribing
+#[derive(Debug, Deserialize, Clone)]
+pub struct SeedSubscriber {
+    pub subscriber_id: String,
+    /// Participant type (EUA, HSP, GATEWAY)
+    #[serde(rename = "type")]
+    pub type_field: String,
+    pub domain: String,
+    pub url: String,
+    /// Base64 encoded Ed25519 public key the participant signs with
+    pub signing_public_key: String,
+}
+
+/// Protocol values accepted in request contexts
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProtocolConfig {
+    /// Supported core version of the UHI protocol
+    pub core_version: String,
+    /// Domains served by the gateway
+    pub domains: Vec<String>,
+    /// City codes served by the gateway; empty accepts any `std:` code
+    #[serde(default)]
+    pub cities: Vec<String>,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            core_version: "0.7.1".to_string(),
+            domains: vec!["nic2004:85111".to_string()],
+            cities: Vec::new(),
+        }
+    }
+}
+
+/// Fee charged for cancelling within some time of the fulfillment start
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CancellationFee {
+    /// The fee applies to cancellations less than this many hours before
+    /// the fulfillment starts
+    pub within_hours: i64,
+    /// Fee as a percentage of the quoted price
+    pub percent: f64,
+}
+
+/// Rules for cancelling orders with a provider
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct CancellationPolicy {
+    /// Cancellations are refused less than this many minutes before the
+    /// fulfillment starts
+    pub cutoff_mins: i64,
+    /// Fee tiers; the highest applicable fee is charged
+    pub fees: Vec<CancellationFee>,
+}
+
+/// Cancellation policies, with per-provider overrides
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CancellationConfig {
+    /// Policy for providers without an override
+    pub default: CancellationPolicy,
+    /// Policies keyed by provider ID
+    pub providers: HashMap<String, CancellationPolicy>,
+}
+
+impl CancellationConfig {
+    /// Policy that applies to orders with `provider_id`
+    pub fn policy_for(&self, provider_id: &str) -> &CancellationPolicy {
+        self.providers.get(provider_id).unwrap_or(&self.default)
+    }
+}
+
+/// States a fulfillment may move to from one state
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct StateTransitions {
+    /// State the fulfillment is in
+    pub from: FulfillmentState,
+    /// Allowed next states; empty for terminal states
+    #[serde(default)]
+    pub to: Vec<FulfillmentState>,
+}
+
+/// Fulfillment lifecycles, with per-type transition tables.
+///
+/// Tables are built in for teleconsultation, home-visit and lab-pickup
+/// fulfillments; configured tables replace the built-in table of their type.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+pub struct FulfillmentConfig {
+    /// Transitions for fulfillment types without their own table
+    pub default: Vec<StateTransitions>,
+    /// Transition tables keyed by fulfillment type
+    #[serde(deserialize_with = "with_builtin_types")]
+    pub types: HashMap<String, Vec<StateTransitions>>,
+}
+
+impl Default for FulfillmentConfig {
+    fn default() -> Self {
+        Self {
+            default: teleconsultation_transitions(),
+            types: builtin_types(),
+        }
+    }
+}
+
+impl FulfillmentConfig {
+    /// Transition table that applies to fulfillments of `fulfillment_type`
+    pub fn transitions_for(&self, fulfillment_type: &str) -> &[StateTransitions] {
+        self.types
+            .get(&fulfillment_type.to_lowercase())
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Build a transition table from `(from, to)` pairs
+fn transition_table(entries: &[(FulfillmentState, &[FulfillmentState])]) -> Vec<StateTransitions> {
+    entries
+        .iter()
+        .map(|(from, to)| StateTransitions {
+            from: *from,
+            to: to.to_vec(),
+        })
+        .collect()
+}
+
+/// Virtual consultations, where the patient joins a waiting room
+fn teleconsultation_transitions() -> Vec<StateTransitions> {
+    use FulfillmentState::*;
+
+    transition_table(&[
+        (
+            Scheduled,
+            &[Waiting, InProgress, Cancelled, NoShow, Rescheduled],
+        ),
+        (Waiting, &[InProgress, Cancelled, NoShow]),
+        (InProgress, &[Completed, Cancelled]),
+        (NoShow, &[Rescheduled]),
+        (Rescheduled, &[Scheduled]),
+        (Completed, &[]),
+        (Cancelled, &[]),
+    ])
+}
+
+/// Visits by a doctor or nurse, who travels to the patient
+fn home_visit_transitions() -> Vec<StateTransitions> {
+    use FulfillmentState::*;
+
+    transition_table(&[
+        (Scheduled, &[EnRoute, Cancelled, Rescheduled]),
+        (EnRoute, &[InProgress, Cancelled, NoShow]),
+        (InProgress, &[Completed, Cancelled]),
+        (NoShow, &[Rescheduled]),
+        (Rescheduled, &[Scheduled]),
+        (Completed, &[]),
+        (Cancelled, &[]),
+    ])
+}
+
+/// Sample collection, completed once the sample reaches the lab
+fn lab_pickup_transitions() -> Vec<StateTransitions> {
+    use FulfillmentState::*;
+
+    transition_table(&[
+        (Scheduled, &[EnRoute, Cancelled, Rescheduled]),
+        (EnRoute, &[SampleCollected, Cancelled, NoShow]),
+        (SampleCollected, &[Completed]),
+        (NoShow, &[Rescheduled]),
+        (Rescheduled, &[Scheduled]),
+        (Completed, &[]),
+        (Cancelled, &[]),
+    ])
+}
+
+fn builtin_types() -> HashMap<String, Vec<StateTransitions>> {
+    HashMap::from([
+        (
+            "teleconsultation".to_string(),
+            teleconsultation_transitions(),
+        ),
+        ("home-visit".to_string(), home_visit_transitions()),
+        ("lab-pickup".to_string(), lab_pickup_transitions()),
+    ])
+}
+
+/// Deserialize configured transition tables on top of the built-in ones
+fn with_builtin_types<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, Vec<StateTransitions>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let configured = HashMap::<String, Vec<StateTransitions>>::deserialize(deserializer)?;
+    let mut types = builtin_types();
+    types.extend(
+        configured
+            .into_iter()
+            .map(|(fulfillment_type, table)| (fulfillment_type.to_lowercase(), table)),
+    );
+    Ok(types)
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub gateway: GatewayConfig,
+    #[serde(default)]
+    pub callback: CallbackConfig,
+    #[serde(default)]
+    pub protocol: ProtocolConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub registry: RegistryConfig,
+    #[serde(default)]
+    pub cancellation: CancellationConfig,
+    #[serde(default)]
+    pub fulfillment: FulfillmentConfig,
 }
 
 impl AppConfig {
@@ -1,6 +1,7 @@
 use actix_web::{HttpResponse, ResponseError};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use crate::models::ack::{ProtocolError, CORE_ERROR, DOMAIN_ERROR, POLICY_ERROR};
 use crate::services::error::ServiceError;
 
 #[derive(Debug, Error)]
@@ -17,6 +18,9 @@ pub enum AppError {
     #[error("Not found: {0}")]
     NotFoundError(String),
 
+    #[error("Business rule violation: {0}")]
+    BusinessRuleError(String),
+
     #[error("Authentication error: {0}")]
     AuthError(String),
 
@@ -37,13 +41,18 @@ pub struct ErrorResponse {
     pub code: String,
 }
 
-impl ResponseError for AppError {
-    fn error_response(&self) -> HttpResponse {
-        let (status_code, error_code) = match self {
+impl AppError {
+    /// HTTP status and error code reported for this error
+    fn status_and_code(&self) -> (actix_web::http::StatusCode, &'static str) {
+        match self {
             AppError::ValidationError(_) => {
                 (actix_web::http::StatusCode::BAD_REQUEST, "INVALID_INPUT")
             }
             AppError::NotFoundError(_) => (actix_web::http::StatusCode::NOT_FOUND, "NOT_FOUND"),
+            AppError::BusinessRuleError(_) => (
+                actix_web::http::StatusCode::UNPROCESSABLE_ENTITY,
+                "BUSINESS_RULE_VIOLATION",
+            ),
             AppError::AuthError(_) => (actix_web::http::StatusCode::UNAUTHORIZED, "UNAUTHORIZED"),
             AppError::ForbiddenError(_) => (actix_web::http::StatusCode::FORBIDDEN, "FORBIDDEN"),
             AppError::DatabaseError(_) => (
@@ -62,7 +71,13 @@ impl ResponseError for AppError {
                 actix_web::http::StatusCode::BAD_GATEWAY,
                 "EXTERNAL_SERVICE_ERROR",
             ),
-        };
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn error_response(&self) -> HttpResponse {
+        let (status_code, error_code) = self.status_and_code();
 
         let error_response = ErrorResponse {
             error: format!("{:?}", status_code),
@@ -80,9 +95,30 @@ impl From<ServiceError> for AppError {
             ServiceError::NotFound(msg) => AppError::NotFoundError(msg),
             ServiceError::Validation(msg) => AppError::ValidationError(msg),
             ServiceError::ExternalService(msg) => AppError::ExternalServiceError(msg),
-            ServiceError::BusinessLogic(msg) => AppError::InternalError(msg),
+            ServiceError::BusinessLogic(msg) => AppError::BusinessRuleError(msg),
+            ServiceError::Forbidden(msg) => AppError::ForbiddenError(msg),
             ServiceError::Internal(msg) => AppError::InternalError(msg),
             ServiceError::Storage(storage_err) => AppError::InternalError(storage_err.to_string()),
         }
     }
 }
+
+impl From<AppError> for ProtocolError {
+    fn from(err: AppError) -> Self {
+        let (_, code) = err.status_and_code();
+        let error_type = match &err {
+            AppError::ValidationError(_) | AppError::NotFoundError(_) => DOMAIN_ERROR,
+            AppError::BusinessRuleError(_) | AppError::ForbiddenError(_) => POLICY_ERROR,
+            _ => CORE_ERROR,
+        };
+
+        ProtocolError::new(error_type, code, err.to_string())
+    }
+}
+
+/// Protocol errors are reported with the same codes as API errors
+impl From<ServiceError> for ProtocolError {
+    fn from(err: ServiceError) -> Self {
+        ProtocolError::from(AppError::from(err))
+    }
+}
@@ -0,0 +1,392 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::ProtocolConfig;
+use crate::models::ack::{AckResponse, ProtocolError, CONTEXT_ERROR, JSON_SCHEMA_ERROR};
+use crate::models::context::Context;
+use crate::services::callback::CallbackDispatcher;
+use crate::services::ServiceError;
+use actix_web::error::{InternalError, JsonPayloadError};
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Serialize;
+use std::future::Future;
+
+/// Acknowledge a protocol request
+pub fn ack() -> HttpResponse {
+    HttpResponse::Ok().json(AckResponse::ack())
+}
+
+/// Reject a protocol request; no callback will follow
+pub fn nack(error: ProtocolError) -> HttpResponse {
+    HttpResponse::BadRequest().json(AckResponse::nack(error))
+}
+
+/// Acknowledge a request and process it in the background, delivering the
+/// outcome as an `action` callback to the consumer_uri of `context`
+pub fn respond_async<T, F>(
+    dispatcher: web::Data<CallbackDispatcher>,
+    context: Context,
+    action: &'static str,
+    task: F,
+) -> HttpResponse
+where
+    T: Serialize + 'static,
+    F: Future<Output = Result<T, ServiceError>> + 'static,
+{
+    actix_web::rt::spawn(async move {
+        let outcome = task.await;
+        if let Err(err) = &outcome {
+            tracing::error!(
+                "Processing for transaction {} failed: {}",
+                context.transaction_id,
+                err
+            );
+        }
+
+        // Delivery failures are retried and dead-lettered by the dispatcher
+        let _ = dispatcher.send(&context, action, outcome).await;
+    });
+
+    ack()
+}
+
+/// Acknowledge a callback once it has been processed, or NACK it with the
+/// processing error
+pub fn respond_sync<T>(outcome: Result<T, ServiceError>) -> HttpResponse {
+    match outcome {
+        Ok(_) => ack(),
+        Err(err) => {
+            tracing::error!("Callback processing failed: {}", err);
+            nack(ProtocolError::from(err))
+        }
+    }
+}
+
+/// Validate a request context against the route it was received on and the
+/// protocol values supported by the gateway
+pub fn validate_context(
+    context: &Context,
+    action: &str,
+    config: &ProtocolConfig,
+) -> Result<(), ProtocolError> {
+    if context.action != action {
+        return Err(context_error(
+            "ACTION_MISMATCH",
+            "context.action",
+            format!(
+                "Action '{}' does not match the '{}' endpoint",
+                context.action, action
+            ),
+        ));
+    }
+
+    if context.core_version != config.core_version {
+        return Err(context_error(
+            "UNSUPPORTED_CORE_VERSION",
+            "context.core_version",
+            format!(
+                "Unsupported core version '{}', expected '{}'",
+                context.core_version, config.core_version
+            ),
+        ));
+    }
+
+    if !config.domains.contains(&context.domain) {
+        return Err(context_error(
+            "UNSUPPORTED_DOMAIN",
+            "context.domain",
+            format!("Unsupported domain '{}'", context.domain),
+        ));
+    }
+
+    if !is_valid_city(&context.city, config) {
+        return Err(context_error(
+            "INVALID_CITY",
+            "context.city",
+            format!("Unsupported city code '{}'", context.city),
+        ));
+    }
+
+    if context.country.is_empty() {
+        return Err(context_error(
+            "MISSING_FIELD",
+            "context.country",
+            "Country is required".to_string(),
+        ));
+    }
+
+    if context.transaction_id.is_empty() || context.message_id.is_empty() {
+        return Err(context_error(
+            "MISSING_FIELD",
+            "context.transaction_id",
+            "Transaction and message IDs are required".to_string(),
+        ));
+    }
+
+    if !is_http_url(&context.consumer_uri) {
+        return Err(context_error(
+            "INVALID_CONSUMER_URI",
+            "context.consumer_uri",
+            format!("Invalid consumer_uri: {}", context.consumer_uri),
+        ));
+    }
+
+    // Callbacks must identify the provider sending them
+    if action.starts_with("on_") {
+        if context.provider_id.as_deref().unwrap_or("").is_empty() {
+            return Err(context_error(
+                "MISSING_FIELD",
+                "context.provider_id",
+                "Provider ID is required for callbacks".to_string(),
+            ));
+        }
+
+        if !context.provider_uri.as_deref().is_some_and(is_http_url) {
+            return Err(context_error(
+                "INVALID_PROVIDER_URI",
+                "context.provider_uri",
+                "A valid provider_uri is required for callbacks".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that a message was signed by its sender: the provider named in the
+/// context for callbacks, the consumer for requests. Requests must also name
+/// a `consumer_uri` on the host registered for the consumer, since their
+/// callbacks are delivered there.
+///
+/// `signer` is absent when signature verification is disabled, in which
+/// case the context is trusted as is.
+pub fn verify_sender(
+    context: &Context,
+    signer: Option<&AuthenticatedSubscriber>,
+) -> Result<(), ProtocolError> {
+    let Some(signer) = signer else {
+        return Ok(());
+    };
+
+    let (role, path, sender_id) = if context.action.starts_with("on_") {
+        let provider_id = context.provider_id.as_deref().unwrap_or("");
+        ("Provider", "context.provider_id", provider_id)
+    } else {
+        let consumer_id = context.consumer_id.as_str();
+        ("Consumer", "context.consumer_id", consumer_id)
+    };
+    if sender_id != signer.subscriber_id {
+        return Err(context_error(
+            "SENDER_MISMATCH",
+            path,
+            format!(
+                "{} '{}' does not match signing subscriber '{}'",
+                role, sender_id, signer.subscriber_id
+            ),
+        ));
+    }
+
+    if !context.action.starts_with("on_")
+        && url_host(&context.consumer_uri) != url_host(&signer.subscriber_url)
+    {
+        return Err(context_error(
+            "CONSUMER_URI_MISMATCH",
+            "context.consumer_uri",
+            format!(
+                "consumer_uri {} is not on the host registered for '{}'",
+                context.consumer_uri, signer.subscriber_id
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Turn JSON extraction failures into NACKs with a schema error
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let error = ProtocolError::new(JSON_SCHEMA_ERROR, "INVALID_ENVELOPE", err.to_string());
+    InternalError::from_response(err, nack(error)).into()
+}
+
+fn context_error(code: &str, path: &str, message: String) -> ProtocolError {
+    ProtocolError {
+        path: Some(path.to_string()),
+        ..ProtocolError::new(CONTEXT_ERROR, code, message)
+    }
+}
+
+fn is_valid_city(city: &str, config: &ProtocolConfig) -> bool {
+    if !config.cities.is_empty() {
+        return config.cities.iter().any(|c| c == city);
+    }
+
+    city.strip_prefix("std:")
+        .is_some_and(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn url_host(uri: &str) -> Option<String> {
+    url::Url::parse(uri)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+}
+
+fn is_http_url(uri: &str) -> bool {
+    matches!(url::Url::parse(uri), Ok(url) if url.scheme() == "http" || url.scheme() == "https")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::context::Envelope;
+    use crate::models::order::OrderStatusRequest;
+    use actix_web::{test as actix_test, App};
+
+    fn create_context(action: &str) -> Context {
+        Context::new(
+            "nic2004:85111".to_string(),
+            "IND".to_string(),
+            "std:080".to_string(),
+            action.to_string(),
+            "0.7.1".to_string(),
+            "eua-1".to_string(),
+            "https://eua.example.com/api/v1".to_string(),
+        )
+    }
+
+    fn error_code(result: Result<(), ProtocolError>) -> String {
+        let error = result.unwrap_err();
+        assert_eq!(error.error_type, CONTEXT_ERROR);
+        error.code
+    }
+
+    #[test]
+    fn test_validate_context_accepts_valid_request() {
+        let config = ProtocolConfig::default();
+        assert!(validate_context(&create_context("search"), "search", &config).is_ok());
+    }
+
+    #[test]
+    fn test_validate_context_rejects_action_mismatch() {
+        let config = ProtocolConfig::default();
+        let result = validate_context(&create_context("select"), "search", &config);
+        assert_eq!(error_code(result), "ACTION_MISMATCH");
+    }
+
+    #[test]
+    fn test_validate_context_rejects_unsupported_values() {
+        let config = ProtocolConfig::default();
+
+        let mut context = create_context("search");
+        context.core_version = "0.9.0".to_string();
+        assert_eq!(
+            error_code(validate_context(&context, "search", &config)),
+            "UNSUPPORTED_CORE_VERSION"
+        );
+
+        let mut context = create_context("search");
+        context.domain = "nic2004:52110".to_string();
+        assert_eq!(
+            error_code(validate_context(&context, "search", &config)),
+            "UNSUPPORTED_DOMAIN"
+        );
+
+        let mut context = create_context("search");
+        context.city = "Bangalore".to_string();
+        assert_eq!(
+            error_code(validate_context(&context, "search", &config)),
+            "INVALID_CITY"
+        );
+
+        let mut context = create_context("search");
+        context.consumer_uri = "eua.example.com".to_string();
+        assert_eq!(
+            error_code(validate_context(&context, "search", &config)),
+            "INVALID_CONSUMER_URI"
+        );
+    }
+
+    #[test]
+    fn test_validate_context_restricts_configured_cities() {
+        let config = ProtocolConfig {
+            cities: vec!["std:011".to_string()],
+            ..ProtocolConfig::default()
+        };
+        let result = validate_context(&create_context("search"), "search", &config);
+        assert_eq!(error_code(result), "INVALID_CITY");
+    }
+
+    #[test]
+    fn test_validate_context_requires_provider_on_callbacks() {
+        let config = ProtocolConfig::default();
+
+        let mut context = create_context("on_search");
+        assert_eq!(
+            error_code(validate_context(&context, "on_search", &config)),
+            "MISSING_FIELD"
+        );
+
+        context.provider_id = Some("hsp-1".to_string());
+        context.provider_uri = Some("https://hsp.example.com".to_string());
+        assert!(validate_context(&context, "on_search", &config).is_ok());
+    }
+
+    #[test]
+    fn test_verify_sender_matches_signing_subscriber() {
+        let mut context = create_context("on_search");
+        context.provider_id = Some("hsp-1".to_string());
+        let signer = |subscriber_id: &str| AuthenticatedSubscriber {
+            subscriber_id: subscriber_id.to_string(),
+            unique_key_id: "key-1".to_string(),
+            subscriber_url: "https://eua.example.com/callbacks".to_string(),
+            gateway_id: None,
+        };
+
+        assert!(verify_sender(&context, Some(&signer("hsp-1"))).is_ok());
+        assert!(verify_sender(&context, None).is_ok());
+        assert_eq!(
+            error_code(verify_sender(&context, Some(&signer("hsp-2")))),
+            "SENDER_MISMATCH"
+        );
+
+        let mut context = create_context("confirm");
+        context.provider_id = Some("hsp-1".to_string());
+        assert!(verify_sender(&context, Some(&signer("eua-1"))).is_ok());
+        assert_eq!(
+            error_code(verify_sender(&context, Some(&signer("hsp-1")))),
+            "SENDER_MISMATCH"
+        );
+
+        // Callbacks of requests only go to the consumer's registered host
+        context.consumer_uri = "http://169.254.169.254/latest".to_string();
+        assert_eq!(
+            error_code(verify_sender(&context, Some(&signer("eua-1")))),
+            "CONSUMER_URI_MISMATCH"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_malformed_envelope_is_nacked() {
+        async fn echo(payload: web::Json<Envelope<OrderStatusRequest>>) -> HttpResponse {
+            let _ = payload;
+            ack()
+        }
+
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+                .route("/status", web::post().to(echo)),
+        )
+        .await;
+
+        let request = actix_test::TestRequest::post()
+            .uri("/status")
+            .set_json(serde_json::json!({"context": {"action": "status"}, "message": {}}))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), 400);
+
+        let body: AckResponse = actix_test::read_body_json(response).await;
+        assert_eq!(body.message.ack.status, crate::models::ack::AckStatus::Nack);
+        let error = body.error.unwrap();
+        assert_eq!(error.error_type, JSON_SCHEMA_ERROR);
+        assert_eq!(error.code, "INVALID_ENVELOPE");
+    }
+}
@@ -0,0 +1,71 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::history::EventOrigin;
+use crate::models::order::{OrderCancelRequest, OrderCancelResponse};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::OrderService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::instrument;
+
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn cancel(
+    payload: web::Json<Envelope<OrderCancelRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received cancel request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "cancel", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+
+    // Cancel the order in the background and deliver it via on_cancel
+    Ok(respond_async(dispatcher, context, "on_cancel", async move {
+        let order = service
+            .cancel(
+                &message.order_id,
+                message.cancellation_reason_id,
+                message.descriptor,
+                &origin,
+            )
+            .await?;
+        Ok(OrderCancelResponse { order })
+    }))
+}
+
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_cancel(
+    payload: web::Json<Envelope<OrderCancelResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received on_cancel request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_cancel", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let order_id = message.order.id.clone();
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_cancel(&order_id, &provider_id, message.order, &origin)
+            .await,
+    ))
+}
@@ -1,21 +1,66 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::history::EventOrigin;
+use crate::models::order::{OrderConfirmRequest, OrderConfirmResponse};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::OrderService;
 use actix_web::{web, HttpResponse, Result};
-use serde_json::Value;
 use tracing::instrument;
 
-#[instrument(skip(payload))]
-pub async fn confirm(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn confirm(
+    payload: web::Json<Envelope<OrderConfirmRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received confirm request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Confirm endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "confirm", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+
+    // Confirm the order in the background and deliver it via on_confirm
+    Ok(respond_async(dispatcher, context, "on_confirm", async move {
+        let order = service
+            .confirm(&message.order_id, message.payment, &origin)
+            .await?;
+        Ok(OrderConfirmResponse { order })
+    }))
 }
 
-#[instrument(skip(payload))]
-pub async fn on_confirm(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_confirm(
+    payload: web::Json<Envelope<OrderConfirmResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received on_confirm request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "On_Confirm endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_confirm", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let order_id = message.order.id.clone();
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_confirm(&order_id, &provider_id, message.order, &origin)
+            .await,
+    ))
 }
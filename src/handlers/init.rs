@@ -1,21 +1,83 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::history::EventOrigin;
+use crate::models::order::{Order, OrderInitRequest, OrderInitResponse, OrderState};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::OrderService;
 use actix_web::{web, HttpResponse, Result};
-use serde_json::Value;
+use chrono::Utc;
 use tracing::instrument;
+use uuid::Uuid;
 
-#[instrument(skip(payload))]
-pub async fn init(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn init(
+    payload: web::Json<Envelope<OrderInitRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received init request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Init endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "init", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+
+    // Create the draft order in the background and deliver it, quoted, via on_init
+    Ok(respond_async(dispatcher, context, "on_init", async move {
+        let order = Order {
+            id: Uuid::new_v4().to_string(),
+            consumer_id: String::new(),
+            provider: message.provider,
+            items: message.items,
+            billing: message.billing,
+            fulfillment: message.fulfillment,
+            quote: None,
+            payment: None,
+            state: OrderState::Initialized,
+            transitions: Vec::new(),
+            cancellation: None,
+            changes: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let order = service.init(order, &origin).await?;
+        let order = service.quote(&order.id, &origin).await?;
+        Ok(OrderInitResponse { order })
+    }))
 }
 
-#[instrument(skip(payload))]
-pub async fn on_init(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_init(
+    payload: web::Json<Envelope<OrderInitResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received on_init request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "On_Init endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_init", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let order_id = message.order.id.clone();
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_init(&order_id, &provider_id, message.order, &origin)
+            .await,
+    ))
 }
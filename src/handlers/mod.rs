@@ -1,6 +1,13 @@
+pub mod ack;
+pub mod cancel;
 pub mod confirm;
 pub mod init;
 pub mod network_registry;
+pub mod rating;
 pub mod search;
 pub mod select;
 pub mod status;
+pub mod support;
+pub mod timeline;
+pub mod track;
+pub mod update;
@@ -1,7 +1,12 @@
 use actix_web::{web, HttpResponse, Result};
 use tracing::instrument;
 
-use crate::models::network_registry::{LookupRequest, LookupResponse};
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::models::network_registry::{
+    AddKeyRequest, LookupRequest, LookupResponse, ParticipantAction, SignatureAlgorithm,
+    StatusChangeRequest, SubscribeRequest, VerifyDomainRequest,
+};
 use crate::services::NetworkRegistryService;
 use crate::errors::AppError;
 
@@ -19,6 +24,183 @@ pub async fn lookup(
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Handle subscribe requests from participants joining the network.
+///
+/// The participant is SUBSCRIBED only after answering the encrypted
+/// challenge sent to its `/on_subscribe` endpoint.
+#[instrument(skip(service, payload))]
+pub async fn subscribe(
+    service: web::Data<NetworkRegistryService>,
+    payload: web::Json<SubscribeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let request = payload.into_inner();
+    tracing::info!("Received subscribe request from {}", request.subscriber_id);
+
+    let response = service.subscribe(request).await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Register a new key for a subscriber, optionally retiring an old one.
+///
+/// When signatures are verified, only the subscriber itself may manage its
+/// keys.
+#[instrument(skip(signer, service, payload))]
+pub async fn add_key(
+    path: web::Path<String>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<NetworkRegistryService>,
+    payload: web::Json<AddKeyRequest>,
+) -> Result<HttpResponse, AppError> {
+    let subscriber_id = path.into_inner();
+    tracing::info!("Received key registration for subscriber {}", subscriber_id);
+
+    if let Some(signer) = signer {
+        if signer.subscriber_id != subscriber_id {
+            return Err(AppError::ForbiddenError(format!(
+                "Subscriber {} cannot manage the keys of {}",
+                signer.subscriber_id, subscriber_id
+            )));
+        }
+    }
+
+    let subscriber = service
+        .add_key(&subscriber_id, payload.into_inner())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(subscriber))
+}
+
+/// Issue a request ID for a subscriber to sign and publish on its domain.
+///
+/// When signatures are verified, only the subscriber itself may verify its
+/// domain.
+#[instrument(skip(signer, service))]
+pub async fn issue_domain_verification(
+    path: web::Path<String>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<NetworkRegistryService>,
+) -> Result<HttpResponse, AppError> {
+    let subscriber_id = path.into_inner();
+    tracing::info!(
+        "Received domain verification request for subscriber {}",
+        subscriber_id
+    );
+
+    authorize_subscriber(signer, &subscriber_id)?;
+    let verification = service.issue_domain_verification(&subscriber_id).await?;
+
+    Ok(HttpResponse::Ok().json(verification))
+}
+
+/// Check the signed request ID a subscriber published on its domain
+#[instrument(skip(signer, service, payload))]
+pub async fn verify_domain(
+    path: web::Path<String>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<NetworkRegistryService>,
+    payload: web::Json<VerifyDomainRequest>,
+) -> Result<HttpResponse, AppError> {
+    let subscriber_id = path.into_inner();
+    tracing::info!(
+        "Received {} domain verification for subscriber {}",
+        payload.method,
+        subscriber_id
+    );
+
+    authorize_subscriber(signer, &subscriber_id)?;
+    let subscriber = service
+        .verify_domain(&subscriber_id, payload.method)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(subscriber))
+}
+
+/// Check the signer of a domain verification request is the subscriber itself
+fn authorize_subscriber(
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    subscriber_id: &str,
+) -> Result<(), AppError> {
+    if let Some(signer) = signer {
+        if signer.subscriber_id != subscriber_id {
+            return Err(AppError::ForbiddenError(format!(
+                "Subscriber {} cannot verify the domain of {}",
+                signer.subscriber_id, subscriber_id
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Activate, suspend, reinstate or deregister a participant.
+///
+/// When signatures are verified, only the registry admins configured under
+/// `[registry]` may change participant statuses.
+#[instrument(skip(signer, config, service, payload))]
+pub async fn change_status(
+    path: web::Path<(String, ParticipantAction)>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    config: Option<web::Data<AppConfig>>,
+    service: web::Data<NetworkRegistryService>,
+    payload: web::Json<StatusChangeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (subscriber_id, action) = path.into_inner();
+    tracing::info!(
+        "Received {} request for subscriber {}",
+        action.code(),
+        subscriber_id
+    );
+
+    let admin = authorize_admin(signer, config)?;
+    let admin = admin.as_deref();
+    let reason = &payload.reason;
+    let subscriber = match action {
+        ParticipantAction::Activate => service.activate(&subscriber_id, reason, admin).await?,
+        ParticipantAction::Suspend => service.suspend(&subscriber_id, reason, admin).await?,
+        ParticipantAction::Reinstate => service.reinstate(&subscriber_id, reason, admin).await?,
+        ParticipantAction::Deregister => service.deregister(&subscriber_id, reason, admin).await?,
+    };
+
+    Ok(HttpResponse::Ok().json(subscriber))
+}
+
+/// Return the audit trail of a participant's status changes
+#[instrument(skip(signer, config, service))]
+pub async fn status_history(
+    path: web::Path<String>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    config: Option<web::Data<AppConfig>>,
+    service: web::Data<NetworkRegistryService>,
+) -> Result<HttpResponse, AppError> {
+    let subscriber_id = path.into_inner();
+    authorize_admin(signer, config)?;
+
+    let history = service.status_history(&subscriber_id).await?;
+
+    Ok(HttpResponse::Ok().json(history))
+}
+
+/// Check the signer of an admin request is a registry admin, returning its
+/// subscriber ID when signatures are verified
+fn authorize_admin(
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    config: Option<web::Data<AppConfig>>,
+) -> Result<Option<String>, AppError> {
+    let Some(signer) = signer else {
+        return Ok(None);
+    };
+
+    let is_admin =
+        config.is_some_and(|config| config.registry.admins.contains(&signer.subscriber_id));
+    if !is_admin {
+        return Err(AppError::ForbiddenError(format!(
+            "Subscriber {} is not a registry admin",
+            signer.subscriber_id
+        )));
+    }
+    Ok(Some(signer.into_inner().subscriber_id))
+}
+
 /// Handle signature validation requests
 #[instrument(skip(service, payload))]
 pub async fn validate_signature(
@@ -33,6 +215,8 @@ pub async fn validate_signature(
     let is_valid = service
         .validate_signature(
             &request.subscriber_id,
+            request.unique_key_id.as_deref(),
+            request.algorithm,
             &request.signature,
             request.message.as_bytes(),
         )
@@ -46,6 +230,12 @@ pub async fn validate_signature(
 pub struct ValidateSignatureRequest {
     /// Subscriber ID whose public key will be used
     pub subscriber_id: String,
+    /// Key to validate with; the current signing key when absent
+    #[serde(default)]
+    pub unique_key_id: Option<String>,
+    /// Algorithm of the signature; the key's algorithm when absent
+    #[serde(default)]
+    pub algorithm: Option<SignatureAlgorithm>,
     /// Signature to validate
     pub signature: String,
     /// Original message that was signed
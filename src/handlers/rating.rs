@@ -0,0 +1,78 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::errors::AppError;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::history::EventOrigin;
+use crate::models::rating::{RatingCategory, RatingRequest, RatingResponse};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::RatingService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::instrument;
+
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn rating(
+    payload: web::Json<Envelope<RatingRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<RatingService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received rating request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "rating", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let rater = EventOrigin::from_context(&context, signer.as_deref()).subscriber_id;
+
+    // Record the ratings in the background and deliver the aggregates via on_rating
+    let RatingRequest { order_id, ratings } = message;
+    Ok(respond_async(
+        dispatcher,
+        context,
+        "on_rating",
+        async move {
+            let ratings = service.rate(&order_id, ratings, &rater).await?;
+            Ok(RatingResponse { order_id, ratings })
+        },
+    ))
+}
+
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_rating(
+    payload: web::Json<Envelope<RatingResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<RatingService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received on_rating request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_rating", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(service.on_rating(&provider_id, &message).await))
+}
+
+/// Return the aggregated rating of an entity, e.g. `/ratings/PROVIDER/{id}`
+#[instrument(skip(service))]
+pub async fn aggregate_rating(
+    path: web::Path<(RatingCategory, String)>,
+    service: web::Data<RatingService>,
+) -> Result<HttpResponse, AppError> {
+    let (category, id) = path.into_inner();
+    tracing::info!("Received rating request for {} {}", category.code(), id);
+
+    let aggregate = service.aggregate(category, &id).await?;
+    Ok(HttpResponse::Ok().json(aggregate))
+}
@@ -1,73 +1,59 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{ack, nack, respond_sync, validate_context, verify_sender};
 use crate::models::catalog::{SearchRequest, SearchResponse};
-use crate::services::{SearchService, ServiceError};
+use crate::models::context::Envelope;
+use crate::services::SearchService;
 use actix_web::{web, Error, HttpResponse, Result};
-use serde_json::Value;
 use tracing::instrument;
 
-#[instrument(skip(payload, service))]
+#[instrument(skip(payload, signer, service, config))]
 pub async fn search(
-    payload: web::Json<SearchRequest>,
+    payload: web::Json<Envelope<SearchRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
     service: web::Data<SearchService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse, Error> {
     tracing::info!("Received search request");
 
-    // Call the service layer to process the search request
-    match service.search(payload.into_inner()).await {
-        Ok(response) => {
-            tracing::info!("Search completed successfully");
-            Ok(HttpResponse::Ok().json(response))
-        }
-        Err(err) => {
-            tracing::error!("Search error: {}", err);
-            match err {
-                ServiceError::NotFound(msg) => {
-                    Ok(HttpResponse::NotFound().json(serde_json::json!({
-                        "error": msg
-                    })))
-                }
-                ServiceError::Validation(msg) => {
-                    Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                        "error": msg
-                    })))
-                }
-                _ => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Internal server error"
-                }))),
-            }
-        }
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "search", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
     }
+
+    // Fan the search out to HSPs in the background; their results are
+    // relayed to the EUA via on_search
+    actix_web::rt::spawn(async move {
+        if let Err(err) = service.search_network(context, message).await {
+            tracing::error!("Failed to relay search results: {}", err);
+        }
+    });
+
+    Ok(ack())
 }
 
-#[instrument(skip(payload, service))]
+#[instrument(skip(payload, signer, service, config))]
 pub async fn on_search(
-    payload: web::Json<SearchResponse>,
+    payload: web::Json<Envelope<SearchResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
     service: web::Data<SearchService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse, Error> {
     tracing::info!("Received on_search request");
 
-    // Extract provider_id from the payload or request context
-    // For now, we'll use a placeholder provider ID
-    let provider_id = "provider-123"; // In a real implementation, this would come from the request
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_search", &config.protocol) {
+        return Ok(nack(error));
+    }
 
-    match service.on_search(provider_id, payload.into_inner()).await {
-        Ok(_) => {
-            tracing::info!("On_search processed successfully");
-            Ok(HttpResponse::Ok().json(serde_json::json!({
-                "status": "Success"
-            })))
-        }
-        Err(err) => {
-            tracing::error!("On_search error: {}", err);
-            match err {
-                ServiceError::Validation(msg) => {
-                    Ok(HttpResponse::BadRequest().json(serde_json::json!({
-                        "error": msg
-                    })))
-                }
-                _ => Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                    "error": "Internal server error"
-                }))),
-            }
-        }
+    // Only the signing HSP may answer for itself
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
     }
+
+    // The provider and transaction are identified by the callback context
+    Ok(respond_sync(service.on_search(&context, message).await))
 }
@@ -1,21 +1,60 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::catalog::{SelectRequest, SelectResponse};
+use crate::models::context::Envelope;
+use crate::services::callback::CallbackDispatcher;
+use crate::services::CatalogService;
 use actix_web::{web, HttpResponse, Result};
-use serde_json::Value;
 use tracing::instrument;
 
-#[instrument(skip(payload))]
-pub async fn select(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn select(
+    payload: web::Json<Envelope<SelectRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<CatalogService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received select request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "Select endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "select", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    // Quote the selected items in the background and deliver via on_select
+    Ok(respond_async(dispatcher, context, "on_select", async move {
+        service.select_items(&message).await
+    }))
 }
 
-#[instrument(skip(payload))]
-pub async fn on_select(payload: web::Json<Value>) -> Result<HttpResponse> {
-    // Placeholder implementation
+#[instrument(skip(payload, signer, config))]
+pub async fn on_select(
+    payload: web::Json<Envelope<SelectResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
     tracing::info!("Received on_select request");
-    Ok(HttpResponse::Ok().json(serde_json::json!({
-        "message": "On_Select endpoint - Placeholder response"
-    })))
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_select", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    tracing::info!(
+        "Received quotation of {} {} for {} items from provider {} (valid for {})",
+        message.quote.price.value,
+        message.quote.price.currency,
+        message.items.len(),
+        message.provider_id,
+        message.quote.ttl
+    );
+    Ok(respond_sync(Ok(())))
 }
@@ -1,64 +1,70 @@
 use actix_web::{web, HttpResponse, Result};
-use serde_json::Value;
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::history::EventOrigin;
 use crate::models::order::{OrderStatus, OrderStatusRequest, OrderStatusResponse};
+use crate::services::callback::CallbackDispatcher;
 use crate::services::order::OrderService;
 use tracing::instrument;
 
-#[instrument(skip(payload, service))]
+#[instrument(skip(payload, signer, service, dispatcher, config))]
 pub async fn status(
-    payload: web::Json<OrderStatusRequest>,
+    payload: web::Json<Envelope<OrderStatusRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
     service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    tracing::info!("Received status request for order {}", payload.order_id);
-    
-    match service.status(&payload.order_id).await {
-        Ok(status) => {
-            // Get the order with updated status
-            match service.get_order(&payload.order_id).await {
-                Ok(order) => {
-                    let response = OrderStatusResponse { order };
-                    Ok(HttpResponse::Ok().json(response))
-                }
-                Err(err) => {
-                    tracing::error!("Failed to get order: {}", err);
-                    Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": format!("Failed to get order: {}", err)
-                    })))
-                }
-            }
-        }
-        Err(err) => {
-            tracing::error!("Failed to get order status: {}", err);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to get order status: {}", err)
-            })))
-        }
+    let Envelope { context, message, .. } = payload.into_inner();
+    tracing::info!("Received status request for order {}", message.order_id);
+
+    if let Err(error) = validate_context(&context, "status", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
     }
+
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+
+    // Refresh the order status in the background and deliver it via on_status
+    Ok(respond_async(dispatcher, context, "on_status", async move {
+        service.status(&message.order_id, &origin).await?;
+        let order = service.get_order(&message.order_id).await?;
+        Ok(OrderStatusResponse { order })
+    }))
 }
 
-#[instrument(skip(payload, service))]
+#[instrument(skip(payload, signer, service, config))]
 pub async fn on_status(
-    payload: web::Json<OrderStatusResponse>,
+    payload: web::Json<Envelope<OrderStatusResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
     service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
 ) -> Result<HttpResponse> {
-    tracing::info!("Received on_status request for order {}", payload.order.id);
-    
+    let Envelope { context, message, .. } = payload.into_inner();
+    tracing::info!("Received on_status request for order {}", message.order.id);
+
+    if let Err(error) = validate_context(&context, "on_status", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
     // Create status object from the order
     let status = OrderStatus {
-        state: payload.order.state.clone(),
-        updated_at: payload.order.updated_at,
+        state: message.order.state,
+        updated_at: message.order.updated_at,
     };
-    
-    match service.on_status(&payload.order.id, status).await {
-        Ok(updated_order) => {
-            let response = OrderStatusResponse { order: updated_order };
-            Ok(HttpResponse::Ok().json(response))
-        }
-        Err(err) => {
-            tracing::error!("Failed to process status update: {}", err);
-            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
-                "error": format!("Failed to process status update: {}", err)
-            })))
-        }
-    }
+
+    let origin = EventOrigin::from_context(&context, signer.as_deref());
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_status(&message.order.id, &provider_id, status, &origin)
+            .await,
+    ))
 }
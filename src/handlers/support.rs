@@ -0,0 +1,62 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::support::{SupportRequest, SupportResponse};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::OrderService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::instrument;
+
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn support(
+    payload: web::Json<Envelope<SupportRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received support request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "support", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    // Look up the provider's support contact in the background and deliver it via on_support
+    Ok(respond_async(dispatcher, context, "on_support", async move {
+        let contact = service.support(&message.order_id).await?;
+        Ok(SupportResponse {
+            order_id: message.order_id,
+            contact,
+        })
+    }))
+}
+
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_support(
+    payload: web::Json<Envelope<SupportResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received on_support request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_support", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_support(&message.order_id, &provider_id, &message.contact)
+            .await,
+    ))
+}
@@ -0,0 +1,31 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::errors::AppError;
+use crate::services::OrderService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::instrument;
+
+/// Return the chronological history of an order.
+///
+/// When signatures are verified, only the order's provider and subscribers
+/// that took part in the order may read it.
+#[instrument(skip(signer, service))]
+pub async fn timeline(
+    path: web::Path<String>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+) -> Result<HttpResponse, AppError> {
+    let order_id = path.into_inner();
+    tracing::info!("Received timeline request for order {}", order_id);
+
+    let timeline = service.timeline(&order_id).await?;
+    if let Some(signer) = signer {
+        if !timeline.involves(&signer.subscriber_id) {
+            return Err(AppError::ForbiddenError(format!(
+                "Subscriber {} is not a party to order {}",
+                signer.subscriber_id, order_id
+            )));
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(timeline))
+}
@@ -0,0 +1,62 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::config::AppConfig;
+use crate::handlers::ack::{nack, respond_async, respond_sync, validate_context, verify_sender};
+use crate::models::context::Envelope;
+use crate::models::tracking::{TrackRequest, TrackResponse};
+use crate::services::callback::CallbackDispatcher;
+use crate::services::OrderService;
+use actix_web::{web, HttpResponse, Result};
+use tracing::instrument;
+
+#[instrument(skip(payload, signer, service, dispatcher, config))]
+pub async fn track(
+    payload: web::Json<Envelope<TrackRequest>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    dispatcher: web::Data<CallbackDispatcher>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received track request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "track", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    // Look up the fulfillment's tracking in the background and deliver it via on_track
+    Ok(respond_async(dispatcher, context, "on_track", async move {
+        let tracking = service.track(&message.order_id).await?;
+        Ok(TrackResponse {
+            order_id: message.order_id,
+            tracking,
+        })
+    }))
+}
+
+#[instrument(skip(payload, signer, service, config))]
+pub async fn on_track(
+    payload: web::Json<Envelope<TrackResponse>>,
+    signer: Option<web::ReqData<AuthenticatedSubscriber>>,
+    service: web::Data<OrderService>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse> {
+    tracing::info!("Received on_track request");
+
+    let Envelope { context, message, .. } = payload.into_inner();
+    if let Err(error) = validate_context(&context, "on_track", &config.protocol) {
+        return Ok(nack(error));
+    }
+    if let Err(error) = verify_sender(&context, signer.as_deref()) {
+        return Ok(nack(error));
+    }
+
+    let provider_id = context.provider_id.unwrap_or_default();
+    Ok(respond_sync(
+        service
+            .on_track(&message.order_id, &provider_id, message.tracking)
+            .await,
+    ))
+}
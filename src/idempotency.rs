@@ -0,0 +1,284 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::handlers::ack::nack;
+use crate::models::ack::{AckResponse, ProtocolError, CONTEXT_ERROR};
+use crate::models::context::Context;
+use crate::models::history::EventOrigin;
+use crate::models::idempotency::RecordedResponse;
+use crate::services::idempotency::{Claim, IdempotencyService};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use serde::Deserialize;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Middleware answering replayed protocol messages with the response
+/// recorded for their first delivery, without processing them again.
+///
+/// Messages are identified by their sender, message ID and action. The sender
+/// is the subscriber that signed the request, or the consumer (provider for
+/// callbacks) named in the context when signatures are not verified. Only
+/// successful responses are recorded; rejected messages are released so
+/// that they can be corrected and sent again.
+#[derive(Default)]
+pub struct Idempotency;
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = IdempotencyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let idempotency = req
+                .app_data::<web::Data<IdempotencyService>>()
+                .filter(|idempotency| idempotency.is_enabled())
+                .cloned();
+            let Some(idempotency) = idempotency else {
+                let response = service.call(req).await?;
+                return Ok(response.map_into_boxed_body());
+            };
+
+            // Buffer the body to identify the message and hand it back to the handler
+            let body = req.extract::<web::Bytes>().await?;
+            req.set_payload(Payload::from(body.clone()));
+
+            let Some(origin) = message_origin(&req, &body) else {
+                let response = service.call(req).await?;
+                return Ok(response.map_into_boxed_body());
+            };
+
+            match idempotency.claim(&origin).await {
+                Ok(Claim::New) => {}
+                Ok(Claim::Replay(recorded)) => {
+                    tracing::info!(
+                        "Replaying response to {} message {} from {}",
+                        origin.action,
+                        origin.message_id,
+                        origin.subscriber_id
+                    );
+                    return Ok(req.into_response(replay(recorded)));
+                }
+                Ok(Claim::InProgress) => {
+                    let error = ProtocolError::new(
+                        CONTEXT_ERROR,
+                        "DUPLICATE_MESSAGE",
+                        format!("Message {} is still being processed", origin.message_id),
+                    );
+                    let response = HttpResponse::Conflict().json(AckResponse::nack(error));
+                    return Ok(req.into_response(response));
+                }
+                Err(err) => {
+                    tracing::error!("Failed to claim message {}: {}", origin.message_id, err);
+                    return Ok(req.into_response(nack(ProtocolError::from(err))));
+                }
+            }
+
+            match service.call(req).await {
+                Ok(response) => record(&idempotency, &origin, response).await,
+                Err(err) => {
+                    release(&idempotency, &origin).await;
+                    Err(err)
+                }
+            }
+        })
+    }
+}
+
+/// Context of a protocol message; the rest of the payload is left to the handler
+#[derive(Deserialize)]
+struct MessageContext {
+    context: Context,
+}
+
+/// Identify the message carried by a request, if the body has a context
+fn message_origin(req: &ServiceRequest, body: &[u8]) -> Option<EventOrigin> {
+    let MessageContext { context } = serde_json::from_slice(body).ok()?;
+    if context.message_id.is_empty() {
+        return None;
+    }
+
+    let extensions = req.extensions();
+    let signer = extensions.get::<AuthenticatedSubscriber>();
+    Some(EventOrigin::from_context(&context, signer))
+}
+
+/// Record a successful JSON response for replays, releasing the message otherwise
+async fn record<B>(
+    idempotency: &IdempotencyService,
+    origin: &EventOrigin,
+    response: ServiceResponse<B>,
+) -> Result<ServiceResponse<BoxBody>, Error>
+where
+    B: MessageBody + 'static,
+{
+    if !response.status().is_success() {
+        release(idempotency, origin).await;
+        return Ok(response.map_into_boxed_body());
+    }
+
+    let (req, res) = response.into_parts();
+    let status = res.status();
+    let (res, body) = res.into_parts();
+    let body = actix_web::body::to_bytes(body).await.map_err(|err| {
+        let err: Box<dyn std::error::Error> = err.into();
+        actix_web::error::ErrorInternalServerError(err.to_string())
+    })?;
+
+    match serde_json::from_slice(&body) {
+        Ok(body) => {
+            let recorded = RecordedResponse {
+                status: status.as_u16(),
+                body,
+            };
+            if let Err(err) = idempotency.complete(origin, recorded).await {
+                tracing::error!(
+                    "Failed to record response to message {}: {}",
+                    origin.message_id,
+                    err
+                );
+            }
+        }
+        Err(_) => release(idempotency, origin).await,
+    }
+
+    let res = res.set_body(body).map_into_boxed_body();
+    Ok(ServiceResponse::new(req, res))
+}
+
+/// Release a message that was not processed successfully
+async fn release(idempotency: &IdempotencyService, origin: &EventOrigin) {
+    if let Err(err) = idempotency.release(origin).await {
+        tracing::error!("Failed to release message {}: {}", origin.message_id, err);
+    }
+}
+
+/// Rebuild a recorded response
+fn replay(recorded: RecordedResponse) -> HttpResponse {
+    let status = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::OK);
+    HttpResponse::build(status).json(recorded.body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::ack::ack;
+    use crate::storage::memory::MemoryStorage;
+    use actix_web::{test as actix_test, App};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Count processed messages, rejecting them when `?reject` is set
+    async fn process(
+        req: actix_web::HttpRequest,
+        _payload: web::Json<Value>,
+        processed: web::Data<AtomicUsize>,
+    ) -> HttpResponse {
+        processed.fetch_add(1, Ordering::SeqCst);
+        if req.query_string() == "reject" {
+            return nack(ProtocolError::new(
+                CONTEXT_ERROR,
+                "INVALID",
+                "Rejected".to_string(),
+            ));
+        }
+        ack()
+    }
+
+    fn envelope(consumer_id: &str, action: &str, message_id: &str) -> Value {
+        let mut context = Context::new(
+            "nic2004:85111".to_string(),
+            "IND".to_string(),
+            "std:080".to_string(),
+            action.to_string(),
+            "0.7.1".to_string(),
+            consumer_id.to_string(),
+            "https://eua.example.com/api/v1".to_string(),
+        );
+        context.message_id = message_id.to_string();
+        json!({"context": context, "message": {}})
+    }
+
+    #[actix_rt::test]
+    async fn test_replayed_messages_are_not_processed_again() {
+        let processed = web::Data::new(AtomicUsize::new(0));
+        let idempotency = web::Data::new(IdempotencyService::new(Arc::new(MemoryStorage::new())));
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(processed.clone())
+                .app_data(idempotency)
+                .service(
+                    web::scope("")
+                        .wrap(Idempotency)
+                        .route("/{action}", web::post().to(process)),
+                ),
+        )
+        .await;
+
+        let send = |uri: &str, body: Value| {
+            actix_test::TestRequest::post()
+                .uri(uri)
+                .set_json(body)
+                .to_request()
+        };
+
+        let first =
+            actix_test::call_service(&app, send("/init", envelope("eua-1", "init", "m-1"))).await;
+        assert_eq!(first.status(), 200);
+        let replay =
+            actix_test::call_service(&app, send("/init", envelope("eua-1", "init", "m-1"))).await;
+        assert_eq!(replay.status(), 200);
+        let body: AckResponse = actix_test::read_body_json(replay).await;
+        assert_eq!(body.message.ack.status, crate::models::ack::AckStatus::Ack);
+        assert_eq!(processed.load(Ordering::SeqCst), 1);
+
+        // Other senders and actions are processed
+        actix_test::call_service(&app, send("/init", envelope("eua-2", "init", "m-1"))).await;
+        actix_test::call_service(&app, send("/confirm", envelope("eua-1", "confirm", "m-1"))).await;
+        assert_eq!(processed.load(Ordering::SeqCst), 3);
+
+        // Rejected messages can be sent again
+        for _ in 0..2 {
+            let response = actix_test::call_service(
+                &app,
+                send("/status?reject", envelope("eua-1", "status", "m-2")),
+            )
+            .await;
+            assert_eq!(response.status(), 400);
+        }
+        assert_eq!(processed.load(Ordering::SeqCst), 5);
+    }
+}
@@ -1,6 +1,8 @@
+mod auth;
 mod config;
 mod errors;
 mod handlers;
+mod idempotency;
 mod logging;
 mod models;
 mod routes;
@@ -11,13 +13,16 @@ use actix_web::{middleware, web, App, HttpServer};
 use dotenv::dotenv;
 use tracing_actix_web::TracingLogger;
 
-use crate::config::AppConfig;
+use crate::auth::RequestSigner;
+use crate::config::{AppConfig, StorageBackend};
 use crate::routes::configure_routes;
 use crate::services::{
-    CatalogService, FulfillmentService, NetworkRegistryService, OrderService, ProviderService,
-    SearchService,
+    CallbackDispatcher, CatalogService, FulfillmentService, IdempotencyService, NetworkRegistryService, OrderService,
+    ProviderService, RatingService, SearchService,
 };
 use crate::storage::memory::MemoryStorage;
+use crate::storage::postgres::PostgresStorage;
+use crate::storage::Storage;
 use std::sync::Arc;
 
 #[actix_web::main]
@@ -38,15 +43,75 @@ async fn main() -> std::io::Result<()> {
     );
 
     // Initialize storage (wrapped in Arc for thread-safe reference counting)
-    let storage = Arc::new(MemoryStorage::new());
+    let storage: Arc<dyn Storage> = match config.database.backend {
+        StorageBackend::Memory => {
+            tracing::info!("Using in-memory storage");
+            Arc::new(MemoryStorage::new())
+        }
+        StorageBackend::Postgres => {
+            tracing::info!("Using PostgreSQL storage");
+            Arc::new(
+                PostgresStorage::connect(&config.database)
+                    .await
+                    .expect("Failed to initialize PostgreSQL storage"),
+            )
+        }
+    };
 
     // Initialize services with storage dependency
-    let search_service = web::Data::new(SearchService::new(storage.clone()));
     let catalog_service = web::Data::new(CatalogService::new(storage.clone()));
-    let order_service = web::Data::new(OrderService::new(storage.clone()));
-    let fulfillment_service = web::Data::new(FulfillmentService::new(storage.clone()));
+    let order_service = web::Data::new(OrderService::with_config(
+        storage.clone(),
+        config.cancellation.clone(),
+        config.fulfillment.clone(),
+    ));
+    let fulfillment_service = web::Data::new(FulfillmentService::with_config(
+        storage.clone(),
+        config.fulfillment.clone(),
+    ));
     let provider_service = web::Data::new(ProviderService::new(storage.clone()));
-    let network_registry_service = web::Data::new(NetworkRegistryService::new(storage.clone()));
+    let network_registry_service = web::Data::new(
+        NetworkRegistryService::new(storage.clone())
+            .expect("Failed to create network registry service"),
+    );
+    network_registry_service
+        .seed(&config.registry.seed)
+        .await
+        .expect("Failed to seed network registry");
+    let rating_service = web::Data::new(RatingService::new(storage.clone()));
+    let idempotency_service = web::Data::new(IdempotencyService::with_config(
+        storage.clone(),
+        config.idempotency.clone(),
+    ));
+    // Signer for requests originated by the gateway
+    let signer = Arc::new(
+        RequestSigner::from_config(&config.gateway).expect("Failed to load gateway signing key"),
+    );
+    tracing::info!(
+        "Signing gateway requests as {}|{} with public key {}",
+        config.gateway.subscriber_id,
+        config.gateway.unique_key_id,
+        signer.public_key()
+    );
+    let callback_dispatcher = Arc::new(
+        CallbackDispatcher::new(
+            storage.clone(),
+            signer.clone(),
+            config.gateway.clone(),
+            config.callback.clone(),
+        )
+        .expect("Failed to create callback dispatcher"),
+    );
+    let search_service = web::Data::new(
+        SearchService::new(
+            storage.clone(),
+            signer.clone(),
+            callback_dispatcher.clone(),
+            config.gateway.clone(),
+        )
+        .expect("Failed to create search service"),
+    );
+    let callback_dispatcher = web::Data::from(callback_dispatcher);
 
     // Store config values for the HTTP server
     let server_host = config.server.host.clone();
@@ -67,6 +132,9 @@ async fn main() -> std::io::Result<()> {
             .app_data(fulfillment_service.clone())
             .app_data(provider_service.clone())
             .app_data(network_registry_service.clone())
+            .app_data(rating_service.clone())
+            .app_data(idempotency_service.clone())
+            .app_data(callback_dispatcher.clone())
             // Configure app state with configuration
             .app_data(web::Data::new(config.clone()))
             // Configure API routes
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+/// Error type for problems with the request context
+pub const CONTEXT_ERROR: &str = "CONTEXT-ERROR";
+
+/// Error type for failures in the protocol core (gateway or transport)
+pub const CORE_ERROR: &str = "CORE-ERROR";
+
+/// Error type for domain level failures (unknown items, invalid values)
+pub const DOMAIN_ERROR: &str = "DOMAIN-ERROR";
+
+/// Error type for business policy violations
+pub const POLICY_ERROR: &str = "POLICY-ERROR";
+
+/// Error type for payloads that do not match the protocol schema
+pub const JSON_SCHEMA_ERROR: &str = "JSON-SCHEMA-ERROR";
+
+/// Acknowledgement status returned synchronously for every protocol call
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum AckStatus {
+    /// Request accepted, the response follows asynchronously
+    #[serde(rename = "ACK")]
+    Ack,
+
+    /// Request rejected, no callback will follow
+    #[serde(rename = "NACK")]
+    Nack,
+}
+
+/// Acknowledgement wrapper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ack {
+    /// Acknowledgement status
+    pub status: AckStatus,
+}
+
+/// Message part of an acknowledgement response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckMessage {
+    /// Acknowledgement
+    pub ack: Ack,
+}
+
+/// Protocol error carried by NACKs and failed callbacks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolError {
+    /// Error category (e.g., "CONTEXT-ERROR", "DOMAIN-ERROR")
+    #[serde(rename = "type")]
+    pub error_type: String,
+
+    /// Machine readable error code
+    pub code: String,
+
+    /// Path to the offending field, if applicable
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Human readable error message
+    pub message: String,
+}
+
+/// Synchronous response to a protocol request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AckResponse {
+    /// Acknowledgement message
+    pub message: AckMessage,
+
+    /// Error details for NACK responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProtocolError>,
+}
+
+impl ProtocolError {
+    /// Create a new protocol error without a field path
+    pub fn new(error_type: &str, code: &str, message: String) -> Self {
+        Self {
+            error_type: error_type.to_string(),
+            code: code.to_string(),
+            path: None,
+            message,
+        }
+    }
+}
+
+impl AckResponse {
+    /// Create an ACK response
+    pub fn ack() -> Self {
+        Self {
+            message: AckMessage {
+                ack: Ack {
+                    status: AckStatus::Ack,
+                },
+            },
+            error: None,
+        }
+    }
+
+    /// Create a NACK response with the given error
+    pub fn nack(error: ProtocolError) -> Self {
+        Self {
+            message: AckMessage {
+                ack: Ack {
+                    status: AckStatus::Nack,
+                },
+            },
+            error: Some(error),
+        }
+    }
+}
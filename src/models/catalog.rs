@@ -1,3 +1,4 @@
+use crate::models::order::OrderItem;
 use crate::models::provider::{Category, Descriptor, Location};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -101,6 +102,52 @@ pub struct SearchResponse {
     pub catalog: Catalog,
 }
 
+/// Reference to a catalog item being selected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectedItem {
+    /// ID of the catalog item
+    pub id: String,
+
+    /// Quantity selected
+    #[serde(default = "default_quantity")]
+    pub quantity: i32,
+}
+
+fn default_quantity() -> i32 {
+    1
+}
+
+/// Select request for choosing items from a provider's catalog
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectRequest {
+    /// ID of the provider whose items are selected
+    pub provider_id: String,
+
+    /// Items being selected
+    pub items: Vec<SelectedItem>,
+
+    /// Fulfillment chosen for the selected items
+    #[serde(default)]
+    pub fulfillment_id: Option<String>,
+}
+
+/// Select response with the selected items and their quotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectResponse {
+    /// ID of the provider quoting the items
+    pub provider_id: String,
+
+    /// Items selected, with their quantities
+    pub items: Vec<OrderItem>,
+
+    /// Fulfillment chosen for the selected items
+    #[serde(default)]
+    pub fulfillment_id: Option<String>,
+
+    /// Price quotation for the selected items
+    pub quote: Quotation,
+}
+
 /// Item response for selected items
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ItemResponse {
@@ -117,8 +164,48 @@ pub struct Quotation {
     /// Breakdown of price components
     pub breakup: Vec<QuotationBreakup>,
 
-    /// Time when quotation was generated
+    /// Validity of the quotation as an ISO 8601 duration (e.g. "PT15M")
     pub ttl: String,
+
+    /// Time until which the quotation can be confirmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl Quotation {
+    /// Parse the `ttl` duration, supporting days, hours, minutes and seconds
+    /// (e.g. "P1D", "PT1H30M", "PT900S")
+    pub fn ttl_duration(&self) -> Option<chrono::Duration> {
+        let rest = self.ttl.strip_prefix('P')?;
+        let (date, time) = rest.split_once('T').unwrap_or((rest, ""));
+
+        let mut seconds = 0i64;
+        let mut parsed = false;
+        let parts: [(&str, &[(char, i64)]); 2] = [
+            (date, &[('D', 86400)]),
+            (time, &[('H', 3600), ('M', 60), ('S', 1)]),
+        ];
+        for (part, units) in parts {
+            let mut number = String::new();
+            let mut allowed = units.iter();
+            for c in part.chars() {
+                if c.is_ascii_digit() {
+                    number.push(c);
+                    continue;
+                }
+                // Units must appear in order, each at most once
+                let (_, factor) = allowed.by_ref().find(|(unit, _)| *unit == c)?;
+                seconds += number.parse::<i64>().ok()? * factor;
+                number.clear();
+                parsed = true;
+            }
+            if !number.is_empty() {
+                return None;
+            }
+        }
+
+        parsed.then(|| chrono::Duration::seconds(seconds))
+    }
 }
 
 /// Breakdown component of a quotation
@@ -130,3 +217,34 @@ pub struct QuotationBreakup {
     /// Price for this component
     pub price: Price,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quotation(ttl: &str) -> Quotation {
+        Quotation {
+            price: Price {
+                currency: "INR".to_string(),
+                value: "500".to_string(),
+                maximum_value: None,
+            },
+            breakup: Vec::new(),
+            ttl: ttl.to_string(),
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn test_ttl_duration() {
+        let seconds = |ttl: &str| quotation(ttl).ttl_duration().map(|d| d.num_seconds());
+
+        assert_eq!(seconds("PT15M"), Some(900));
+        assert_eq!(seconds("PT1H30M"), Some(5400));
+        assert_eq!(seconds("P1DT30S"), Some(86430));
+        assert_eq!(seconds("PT"), None);
+        assert_eq!(seconds("PT15"), None);
+        assert_eq!(seconds("PT30M1H"), None);
+        assert_eq!(seconds("15 minutes"), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::ack::ProtocolError;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -69,7 +70,9 @@ impl Context {
         }
     }
 
-    /// Create a response context from a request context
+    /// Create a response context from a request context. The response keeps
+    /// the request's transaction and message IDs so the consumer can match it
+    /// to the request it answers.
     pub fn create_response_context(
         &self,
         action: String,
@@ -87,7 +90,7 @@ impl Context {
             provider_id: Some(provider_id),
             provider_uri: Some(provider_uri),
             transaction_id: self.transaction_id.clone(),
-            message_id: Uuid::new_v4().to_string(),
+            message_id: self.message_id.clone(),
             timestamp: Utc::now(),
         }
     }
@@ -97,3 +100,23 @@ impl Context {
 pub trait WithContext {
     fn context(&self) -> &Context;
 }
+
+/// Protocol envelope wrapping a message with its context
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope<M> {
+    /// Context of the request or callback
+    pub context: Context,
+
+    /// Action specific message
+    pub message: M,
+
+    /// Error reported by the sender, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProtocolError>,
+}
+
+impl<M> WithContext for Envelope<M> {
+    fn context(&self) -> &Context {
+        &self.context
+    }
+}
@@ -1,6 +1,7 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 
 /// Person info with contact details
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,11 +61,85 @@ pub struct TimeSlot {
     pub duration: Option<i64>,
 }
 
+/// Lifecycle state of a fulfillment.
+///
+/// Which states a fulfillment passes through depends on its type; see
+/// `FulfillmentConfig` for the transitions allowed per type.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum FulfillmentState {
+    /// Booked for its time slot
+    #[serde(rename = "SCHEDULED")]
+    Scheduled,
+
+    /// The patient is waiting for a consultation to start
+    #[serde(rename = "WAITING")]
+    Waiting,
+
+    /// The agent is travelling to the patient
+    #[serde(rename = "EN_ROUTE")]
+    EnRoute,
+
+    /// The service is being delivered
+    #[serde(rename = "IN_PROGRESS")]
+    InProgress,
+
+    /// A sample has been collected and is on its way to the lab
+    #[serde(rename = "SAMPLE_COLLECTED")]
+    SampleCollected,
+
+    /// The service has been delivered
+    #[serde(rename = "COMPLETED")]
+    Completed,
+
+    /// Cancelled before being delivered
+    #[serde(rename = "CANCELLED")]
+    Cancelled,
+
+    /// The patient did not show up
+    #[serde(rename = "NO_SHOW")]
+    NoShow,
+
+    /// Being moved to a new time slot
+    #[serde(rename = "RESCHEDULED")]
+    Rescheduled,
+}
+
+impl FulfillmentState {
+    /// Protocol code of the state
+    pub fn code(&self) -> &'static str {
+        match self {
+            FulfillmentState::Scheduled => "SCHEDULED",
+            FulfillmentState::Waiting => "WAITING",
+            FulfillmentState::EnRoute => "EN_ROUTE",
+            FulfillmentState::InProgress => "IN_PROGRESS",
+            FulfillmentState::SampleCollected => "SAMPLE_COLLECTED",
+            FulfillmentState::Completed => "COMPLETED",
+            FulfillmentState::Cancelled => "CANCELLED",
+            FulfillmentState::NoShow => "NO_SHOW",
+            FulfillmentState::Rescheduled => "RESCHEDULED",
+        }
+    }
+
+    /// Whether the fulfillment is over once in this state
+    pub fn has_ended(&self) -> bool {
+        matches!(
+            self,
+            FulfillmentState::Completed | FulfillmentState::Cancelled | FulfillmentState::NoShow
+        )
+    }
+}
+
+impl fmt::Display for FulfillmentState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
 /// State of the fulfillment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct State {
     /// Current state of the fulfillment
-    pub descriptor: String,
+    pub descriptor: FulfillmentState,
 
     /// Updated time for this state
     pub updated_at: DateTime<Utc>,
@@ -110,3 +185,34 @@ pub struct Fulfillment {
     /// Additional metadata about the fulfillment
     pub tags: HashMap<String, String>,
 }
+
+impl Fulfillment {
+    /// Time slot held by the fulfillment. Without a duration the slot runs
+    /// until the end time, or for an hour when that is not set either.
+    pub fn slot(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start = self.start.time.timestamp;
+        let end = if let Some(duration) = self.start.duration {
+            start + Duration::seconds(duration)
+        } else if self.end.time.timestamp > start {
+            self.end.time.timestamp
+        } else {
+            start + Duration::seconds(3600)
+        };
+        (start, end)
+    }
+
+    /// Whether the fulfillment holds a slot overlapping `start..end`;
+    /// cancelled fulfillments no longer hold their slot
+    pub fn overlaps(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+        if self
+            .state
+            .as_ref()
+            .is_some_and(|state| state.descriptor == FulfillmentState::Cancelled)
+        {
+            return false;
+        }
+
+        let (held_start, held_end) = self.slot();
+        start < held_end && end > held_start
+    }
+}
@@ -0,0 +1,115 @@
+use crate::auth::AuthenticatedSubscriber;
+use crate::models::context::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Record changed by an order event
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum EventEntity {
+    /// The order itself
+    #[serde(rename = "ORDER")]
+    Order,
+
+    /// The fulfillment booked for the order
+    #[serde(rename = "FULFILLMENT")]
+    Fulfillment,
+}
+
+/// Protocol message that caused a change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventOrigin {
+    /// Subscriber ID of the sender
+    pub subscriber_id: String,
+
+    /// Protocol action of the message (e.g., "confirm", "on_status")
+    pub action: String,
+
+    /// ID of the message
+    pub message_id: String,
+}
+
+impl EventOrigin {
+    /// Create an origin for a message
+    pub fn new(
+        subscriber_id: impl Into<String>,
+        action: impl Into<String>,
+        message_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            subscriber_id: subscriber_id.into(),
+            action: action.into(),
+            message_id: message_id.into(),
+        }
+    }
+
+    /// Origin of the message with `context`, attributed to the subscriber
+    /// that signed it. Without a verified signer, callbacks are attributed
+    /// to the provider and requests to the consumer named in the context.
+    pub fn from_context(context: &Context, signer: Option<&AuthenticatedSubscriber>) -> Self {
+        let subscriber_id = match signer {
+            Some(signer) => signer.subscriber_id.clone(),
+            None if context.action.starts_with("on_") => {
+                context.provider_id.clone().unwrap_or_default()
+            }
+            None => context.consumer_id.clone(),
+        };
+
+        Self::new(
+            subscriber_id,
+            context.action.clone(),
+            context.message_id.clone(),
+        )
+    }
+}
+
+/// Entry in the history of an order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrderEvent {
+    /// ID of the order
+    pub order_id: String,
+
+    /// Record that changed
+    pub entity: EventEntity,
+
+    /// ID of the changed order or fulfillment
+    pub entity_id: String,
+
+    /// Subscriber whose message caused the change
+    pub actor: String,
+
+    /// Protocol action of that message
+    pub action: String,
+
+    /// State before the change; absent for newly created records
+    pub previous_state: Option<String>,
+
+    /// State after the change
+    pub new_state: Option<String>,
+
+    /// ID of that message
+    pub message_id: String,
+
+    /// When the change was recorded
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Chronological history of an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTimeline {
+    /// ID of the order
+    pub order_id: String,
+
+    /// ID of the order's provider
+    pub provider_id: String,
+
+    /// Events, oldest first
+    pub events: Vec<OrderEvent>,
+}
+
+impl OrderTimeline {
+    /// Whether `subscriber_id` is the order's provider or took part in it
+    pub fn involves(&self, subscriber_id: &str) -> bool {
+        self.provider_id == subscriber_id
+            || self.events.iter().any(|event| event.actor == subscriber_id)
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Protocol message claimed for processing, identified by its sender,
+/// message ID and action
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProcessedMessage {
+    /// Subscriber ID of the sender
+    pub subscriber_id: String,
+
+    /// ID of the message
+    pub message_id: String,
+
+    /// Protocol action of the message
+    pub action: String,
+
+    /// Response returned for the message; absent while it is being processed
+    pub response: Option<RecordedResponse>,
+
+    /// When the message was first received
+    pub received_at: DateTime<Utc>,
+
+    /// When the record stops deduplicating replays of the message; while
+    /// the message is being processed, when its processing lease lapses
+    pub expires_at: DateTime<Utc>,
+}
+
+impl ProcessedMessage {
+    /// Whether the record still deduplicates messages received at `at`
+    pub fn is_live(&self, at: DateTime<Utc>) -> bool {
+        self.expires_at > at
+    }
+}
+
+/// Synchronous response recorded for a processed message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecordedResponse {
+    /// HTTP status code
+    pub status: u16,
+
+    /// JSON body
+    pub body: serde_json::Value,
+}
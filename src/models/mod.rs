@@ -1,8 +1,14 @@
+pub mod ack;
 pub mod billing;
 pub mod catalog;
 pub mod context;
 pub mod fulfillment;
+pub mod history;
+pub mod idempotency;
 pub mod network_registry;
 pub mod order;
 pub mod payment;
 pub mod provider;
+pub mod rating;
+pub mod support;
+pub mod tracking;
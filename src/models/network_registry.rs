@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 /// Network participant types
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -18,14 +20,34 @@ pub enum ParticipantType {
     Gateway,
 }
 
+impl ParticipantType {
+    /// Protocol code of the type, as registered in `Subscriber::type_field`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParticipantType::Eua => "EUA",
+            ParticipantType::Hsp => "HSP",
+            ParticipantType::Gateway => "GATEWAY",
+        }
+    }
+}
+
 /// Network participant status
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ParticipantStatus {
+    /// Participant has asked to join but not yet answered the registry's
+    /// challenge
+    #[serde(rename = "INITIATED")]
+    Initiated,
+
+    /// Participant proved ownership of its encryption key
+    #[serde(rename = "SUBSCRIBED")]
+    Subscribed,
+
     /// Participant is active
     #[serde(rename = "ACTIVE")]
     Active,
 
-    /// Participant is inactive
+    /// Participant is inactive, having been deregistered
     #[serde(rename = "INACTIVE")]
     Inactive,
 
@@ -34,6 +56,108 @@ pub enum ParticipantStatus {
     Suspended,
 }
 
+impl ParticipantStatus {
+    /// Protocol code of the status
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParticipantStatus::Initiated => "INITIATED",
+            ParticipantStatus::Subscribed => "SUBSCRIBED",
+            ParticipantStatus::Active => "ACTIVE",
+            ParticipantStatus::Inactive => "INACTIVE",
+            ParticipantStatus::Suspended => "SUSPENDED",
+        }
+    }
+
+    /// Whether participants with this status may sign protocol requests and
+    /// are listed in lookups. Subscribed participants may only sign their own
+    /// onboarding requests until an administrator activates them, which
+    /// requires a verified domain.
+    pub fn is_participating(&self) -> bool {
+        *self == ParticipantStatus::Active
+    }
+}
+
+impl fmt::Display for ParticipantStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Administrative change of a participant's status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParticipantAction {
+    /// Admit a subscribed participant to the network
+    Activate,
+
+    /// Temporarily exclude a participant from the network
+    Suspend,
+
+    /// Readmit a suspended participant
+    Reinstate,
+
+    /// Permanently remove a participant from the network
+    Deregister,
+}
+
+impl ParticipantAction {
+    /// Name of the action in admin endpoints
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParticipantAction::Activate => "activate",
+            ParticipantAction::Suspend => "suspend",
+            ParticipantAction::Reinstate => "reinstate",
+            ParticipantAction::Deregister => "deregister",
+        }
+    }
+
+    /// Status a participant with status `from` moves to, if the action
+    /// applies to it
+    pub fn status_after(&self, from: ParticipantStatus) -> Option<ParticipantStatus> {
+        use ParticipantStatus::*;
+
+        match (self, from) {
+            (ParticipantAction::Activate, Subscribed) => Some(Active),
+            (ParticipantAction::Suspend, Subscribed | Active) => Some(Suspended),
+            (ParticipantAction::Reinstate, Suspended) => Some(Active),
+            (ParticipantAction::Deregister, Initiated | Subscribed | Active | Suspended) => {
+                Some(Inactive)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Audit record of a change to a participant's status
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubscriberStatusChange {
+    /// Subscriber whose status changed
+    pub subscriber_id: String,
+
+    /// Status before the change
+    pub from: ParticipantStatus,
+
+    /// Status after the change
+    pub to: ParticipantStatus,
+
+    /// Why the status changed
+    pub reason: String,
+
+    /// Subscriber that made the change; absent when it was made by the
+    /// registry itself or without verified signatures
+    pub changed_by: Option<String>,
+
+    /// When the status changed
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Request to change a participant's status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChangeRequest {
+    /// Why the status is changed
+    pub reason: String,
+}
+
 /// Subscriber in the network registry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subscriber {
@@ -56,11 +180,25 @@ pub struct Subscriber {
     pub url: String,
 
     /// Status of the subscriber
-    pub status: String,
+    pub status: ParticipantStatus,
 
     /// Public key for signature verification
     pub public_key: String,
 
+    /// Base64 X25519 public key used to encrypt messages to the subscriber
+    #[serde(default)]
+    pub encryption_public_key: Option<String>,
+
+    /// Keys registered with validity windows. While no signing key is
+    /// registered, signatures are verified with `public_key`.
+    #[serde(default)]
+    pub keys: Vec<SubscriberKey>,
+
+    /// Latest proof of control of the host of `url` requested from the
+    /// subscriber
+    #[serde(default)]
+    pub domain_verification: Option<DomainVerification>,
+
     /// Time when the subscriber was created
     pub created_at: DateTime<Utc>,
 
@@ -68,6 +206,226 @@ pub struct Subscriber {
     pub updated_at: DateTime<Utc>,
 }
 
+impl Subscriber {
+    /// Whether the subscriber has registered any key for `purpose`
+    pub fn has_keys(&self, purpose: KeyPurpose) -> bool {
+        self.keys.iter().any(|key| key.purpose == purpose)
+    }
+
+    /// Registered key with the given ID and purpose
+    pub fn key(&self, unique_key_id: &str, purpose: KeyPurpose) -> Option<&SubscriberKey> {
+        self.keys
+            .iter()
+            .find(|key| key.unique_key_id == unique_key_id && key.purpose == purpose)
+    }
+
+    /// Whether the subscriber has proven control of the host of its URL
+    pub fn is_domain_verified(&self) -> bool {
+        self.domain_verification
+            .as_ref()
+            .is_some_and(|verification| verification.verified_at.is_some())
+    }
+}
+
+/// How a subscriber proves control of the host of its URL
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum DomainVerificationMethod {
+    /// Signed request ID served in the site verification file
+    #[default]
+    #[serde(rename = "SITE_VERIFICATION")]
+    SiteVerification,
+
+    /// Signed request ID published in a DNS TXT record
+    #[serde(rename = "DNS_TXT")]
+    DnsTxt,
+}
+
+impl DomainVerificationMethod {
+    /// Code of the method in requests
+    pub fn code(&self) -> &'static str {
+        match self {
+            DomainVerificationMethod::SiteVerification => "SITE_VERIFICATION",
+            DomainVerificationMethod::DnsTxt => "DNS_TXT",
+        }
+    }
+}
+
+impl fmt::Display for DomainVerificationMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Domain ownership verification issued to a subscriber.
+///
+/// The subscriber signs `request_id` with its signing key and serves the
+/// signature in the site verification file of `host`, or publishes it in a
+/// DNS TXT record.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DomainVerification {
+    /// Random ID issued by the registry for the subscriber to sign
+    pub request_id: String,
+
+    /// Host whose ownership is verified, from the subscriber's URL
+    pub host: String,
+
+    /// Time when the request ID was issued
+    pub issued_at: DateTime<Utc>,
+
+    /// Time when ownership was proven; pending while absent
+    pub verified_at: Option<DateTime<Utc>>,
+
+    /// How ownership was proven
+    pub method: Option<DomainVerificationMethod>,
+}
+
+/// Request to check a subscriber's proof of domain ownership
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct VerifyDomainRequest {
+    /// Where the signed request ID is published
+    #[serde(default)]
+    pub method: DomainVerificationMethod,
+}
+
+/// What a subscriber key is used for
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum KeyPurpose {
+    /// Verifying signatures of the subscriber's requests
+    #[serde(rename = "SIGNING")]
+    Signing,
+
+    /// Encrypting messages to the subscriber
+    #[serde(rename = "ENCRYPTION")]
+    Encryption,
+}
+
+/// Algorithm of a subscriber signing key
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SignatureAlgorithm {
+    /// Ed25519
+    #[default]
+    #[serde(rename = "ed25519")]
+    Ed25519,
+
+    /// ECDSA on P-256 with SHA-256
+    #[serde(rename = "ecdsa-p256-sha256")]
+    EcdsaP256Sha256,
+
+    /// RSASSA-PSS with SHA-256
+    #[serde(rename = "rsa-pss-sha256")]
+    RsaPssSha256,
+}
+
+impl SignatureAlgorithm {
+    /// Name of the algorithm in signature headers
+    pub fn code(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::EcdsaP256Sha256 => "ecdsa-p256-sha256",
+            SignatureAlgorithm::RsaPssSha256 => "rsa-pss-sha256",
+        }
+    }
+
+    /// Type of the keys used with the algorithm
+    pub fn key_type(&self) -> &'static str {
+        match self {
+            SignatureAlgorithm::Ed25519 => "Ed25519",
+            SignatureAlgorithm::EcdsaP256Sha256 => "ECDSA P-256",
+            SignatureAlgorithm::RsaPssSha256 => "RSA",
+        }
+    }
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for SignatureAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [
+            SignatureAlgorithm::Ed25519,
+            SignatureAlgorithm::EcdsaP256Sha256,
+            SignatureAlgorithm::RsaPssSha256,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.code() == s)
+        .ok_or_else(|| format!("Unsupported signature algorithm: {}", s))
+    }
+}
+
+/// Public key of a subscriber, valid for a window of time
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SubscriberKey {
+    /// ID of the key, referenced by the `keyId` of signature headers
+    pub unique_key_id: String,
+
+    /// What the key is used for
+    pub purpose: KeyPurpose,
+
+    /// Public key, PEM or base64 SPKI DER or raw encoded
+    pub public_key: String,
+
+    /// Algorithm of signing keys
+    #[serde(default)]
+    pub algorithm: SignatureAlgorithm,
+
+    /// Time from which the key is valid
+    pub valid_from: DateTime<Utc>,
+
+    /// Time at which the key is retired; open-ended when absent
+    pub valid_until: Option<DateTime<Utc>>,
+}
+
+impl SubscriberKey {
+    /// Whether the key is valid at `at`
+    pub fn is_valid_at(&self, at: DateTime<Utc>) -> bool {
+        self.valid_from <= at && self.valid_until.is_none_or(|until| at < until)
+    }
+}
+
+/// Request to register a new subscriber key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddKeyRequest {
+    /// ID of the new key
+    pub unique_key_id: String,
+
+    /// What the key is used for
+    pub purpose: KeyPurpose,
+
+    /// Public key, PEM or base64 SPKI DER or raw encoded
+    pub public_key: String,
+
+    /// Algorithm of signing keys
+    #[serde(default)]
+    pub algorithm: SignatureAlgorithm,
+
+    /// Time from which the key is valid; defaults to now
+    #[serde(default)]
+    pub valid_from: Option<DateTime<Utc>>,
+
+    /// Time at which the key is retired
+    #[serde(default)]
+    pub valid_until: Option<DateTime<Utc>>,
+
+    /// Key with the same purpose to retire in favour of the new key
+    #[serde(default)]
+    pub retire: Option<KeyRetirement>,
+}
+
+/// Scheduled retirement of a subscriber key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRetirement {
+    /// ID of the key to retire
+    pub unique_key_id: String,
+
+    /// Time at which the key stops being valid
+    pub at: DateTime<Utc>,
+}
+
 /// Network registry lookup criteria
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkRegistryLookup {
@@ -100,7 +458,7 @@ pub struct Participant {
     pub url: String,
 
     /// Participant status
-    pub status: String,
+    pub status: ParticipantStatus,
 
     /// Public key for signature verification
     pub public_key: String,
@@ -167,7 +525,55 @@ pub struct RegistrationResponse {
     pub subscriber_id: String,
 
     /// Registration status
-    pub status: String,
+    pub status: ParticipantStatus,
+}
+
+/// Request of a participant to join the network (subscribe)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeRequest {
+    /// Subscriber ID the participant wants to register under
+    pub subscriber_id: String,
+
+    /// Type of subscriber (EUA, HSP, GATEWAY)
+    pub type_field: String,
+
+    /// Domain of operation
+    pub domain: String,
+
+    /// City of operation
+    pub city: Option<String>,
+
+    /// Country of operation
+    pub country: Option<String>,
+
+    /// Base URL of the participant, which must serve `/on_subscribe`
+    pub url: String,
+
+    /// Base64 public key for signature verification
+    pub signing_public_key: String,
+
+    /// Base64 X25519 public key the registry encrypts its challenge to
+    pub encryption_public_key: String,
+}
+
+/// Challenge sent by the registry to a subscribing participant (on_subscribe)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnSubscribeRequest {
+    /// Subscriber ID being verified
+    pub subscriber_id: String,
+
+    /// Base64 AES-256-GCM nonce and ciphertext of the challenge
+    pub challenge: String,
+
+    /// Base64 ephemeral X25519 public key of the registry for this challenge
+    pub ephemeral_public_key: String,
+}
+
+/// Participant's answer to an on_subscribe challenge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnSubscribeResponse {
+    /// Decrypted challenge
+    pub answer: String,
 }
 
 /// Subscription details for a participant
@@ -1,9 +1,10 @@
 use crate::models::billing::Billing;
-use crate::models::catalog::{Item, Quotation};
-use crate::models::fulfillment::Fulfillment;
+use crate::models::catalog::{Item, Price, Quotation, SelectedItem};
+use crate::models::fulfillment::{Fulfillment, TimeSlot};
 use crate::models::payment::Payment;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// Summary of a provider for order references
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,22 +32,238 @@ pub struct OrderItem {
     pub item: Item,
 }
 
+/// Lifecycle state of an order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderState {
+    /// Draft order awaiting the provider's quote
+    #[serde(rename = "INITIALIZED")]
+    Initialized,
+
+    /// Quoted by the provider, awaiting confirmation
+    #[serde(rename = "QUOTED")]
+    Quoted,
+
+    /// Confirmed, with its fulfillment booked
+    #[serde(rename = "CONFIRMED")]
+    Confirmed,
+
+    /// The patient is waiting for the fulfillment to start
+    #[serde(rename = "FULFILLMENT_PENDING")]
+    FulfillmentPending,
+
+    /// The fulfillment is under way
+    #[serde(rename = "IN_PROGRESS")]
+    InProgress,
+
+    /// The fulfillment has been delivered
+    #[serde(rename = "COMPLETED")]
+    Completed,
+
+    /// Cancelled by the patient or the provider
+    #[serde(rename = "CANCELLED")]
+    Cancelled,
+
+    /// The patient did not show up
+    #[serde(rename = "NO_SHOW")]
+    NoShow,
+
+    /// The fulfillment is being moved to a new slot
+    #[serde(rename = "RESCHEDULED")]
+    Rescheduled,
+}
+
+impl OrderState {
+    /// Protocol code of the state
+    pub fn code(&self) -> &'static str {
+        match self {
+            OrderState::Initialized => "INITIALIZED",
+            OrderState::Quoted => "QUOTED",
+            OrderState::Confirmed => "CONFIRMED",
+            OrderState::FulfillmentPending => "FULFILLMENT_PENDING",
+            OrderState::InProgress => "IN_PROGRESS",
+            OrderState::Completed => "COMPLETED",
+            OrderState::Cancelled => "CANCELLED",
+            OrderState::NoShow => "NO_SHOW",
+            OrderState::Rescheduled => "RESCHEDULED",
+        }
+    }
+
+    /// States an order in this state may move to
+    pub fn next_states(&self) -> &'static [OrderState] {
+        use OrderState::*;
+
+        match self {
+            Initialized => &[Quoted, Cancelled],
+            Quoted => &[Confirmed, Cancelled],
+            Confirmed => &[
+                FulfillmentPending,
+                InProgress,
+                Cancelled,
+                NoShow,
+                Rescheduled,
+            ],
+            FulfillmentPending => &[InProgress, Cancelled, NoShow],
+            InProgress => &[Completed, Cancelled],
+            NoShow => &[Rescheduled],
+            Rescheduled => &[Confirmed, Cancelled],
+            // Terminal states
+            Completed | Cancelled => &[],
+        }
+    }
+
+    /// Whether an order may move from this state to `next`
+    pub fn can_transition_to(&self, next: OrderState) -> bool {
+        self.next_states().contains(&next)
+    }
+}
+
+impl fmt::Display for OrderState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Party acting on an order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum OrderActor {
+    /// The patient, through their EUA
+    #[serde(rename = "CONSUMER")]
+    Consumer,
+
+    /// The provider, through its HSPA
+    #[serde(rename = "PROVIDER")]
+    Provider,
+
+    /// The gateway, e.g. following a fulfillment state change
+    #[serde(rename = "GATEWAY")]
+    Gateway,
+}
+
+/// Record of an order moving from one state to another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderTransition {
+    /// State before the transition
+    pub from: OrderState,
+
+    /// State after the transition
+    pub to: OrderState,
+
+    /// Party that made the transition
+    pub actor: OrderActor,
+
+    /// Why the transition was made
+    pub reason: String,
+
+    /// Time of the transition
+    pub transitioned_at: DateTime<Utc>,
+}
+
 /// Order status object
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderStatus {
     /// Current state of the order
-    pub state: String,
+    pub state: OrderState,
 
     /// Timestamp when status was updated
     pub updated_at: DateTime<Utc>,
 }
 
+/// Reason code given for cancelling an order
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CancellationReason {
+    /// The patient no longer needs the service
+    #[serde(rename = "CUSTOMER_REQUEST")]
+    CustomerRequest,
+
+    /// The patient cannot make the booked slot
+    #[serde(rename = "SCHEDULE_CONFLICT")]
+    ScheduleConflict,
+
+    /// The order was placed more than once
+    #[serde(rename = "DUPLICATE_ORDER")]
+    DuplicateOrder,
+
+    /// The provider cannot deliver the service
+    #[serde(rename = "PROVIDER_UNAVAILABLE")]
+    ProviderUnavailable,
+
+    /// Payment for the order failed
+    #[serde(rename = "PAYMENT_FAILED")]
+    PaymentFailed,
+
+    /// Any other reason, described in the cancellation descriptor
+    #[serde(rename = "OTHER")]
+    Other,
+}
+
+impl CancellationReason {
+    /// Protocol code of the reason
+    pub fn code(&self) -> &'static str {
+        match self {
+            CancellationReason::CustomerRequest => "CUSTOMER_REQUEST",
+            CancellationReason::ScheduleConflict => "SCHEDULE_CONFLICT",
+            CancellationReason::DuplicateOrder => "DUPLICATE_ORDER",
+            CancellationReason::ProviderUnavailable => "PROVIDER_UNAVAILABLE",
+            CancellationReason::PaymentFailed => "PAYMENT_FAILED",
+            CancellationReason::Other => "OTHER",
+        }
+    }
+
+    /// Whether the cancellation is not the patient's doing, in which case
+    /// no cancellation fee is charged
+    pub fn waives_fee(&self) -> bool {
+        matches!(self, CancellationReason::ProviderUnavailable)
+    }
+}
+
+/// Record of an order cancellation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cancellation {
+    /// Reason code for the cancellation
+    pub reason: CancellationReason,
+
+    /// Free text description of the reason
+    pub descriptor: Option<String>,
+
+    /// Party that cancelled the order
+    pub cancelled_by: OrderActor,
+
+    /// Cancellation fee charged under the provider's policy
+    pub fee: Option<Price>,
+
+    /// Time when the order was cancelled
+    pub cancelled_at: DateTime<Utc>,
+}
+
+/// Versioned record of a change made to an order through update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderChange {
+    /// Version of the order produced by the change, starting at 1
+    pub version: u32,
+
+    /// Part of the order that changed ("fulfillment", "items" or "billing")
+    pub target: String,
+
+    /// The changed part before the update
+    pub previous: serde_json::Value,
+
+    /// The changed part after the update
+    pub current: serde_json::Value,
+
+    /// Time when the change was made
+    pub changed_at: DateTime<Utc>,
+}
+
 /// Order representing a healthcare service booking
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
     /// Unique ID for the order
     pub id: String,
 
+    /// Subscriber ID of the consumer that initialized the order
+    #[serde(default)]
+    pub consumer_id: String,
+
     /// Provider summary
     pub provider: ProviderSummary,
 
@@ -66,7 +283,19 @@ pub struct Order {
     pub payment: Option<Payment>,
 
     /// Current state of the order
-    pub state: String,
+    pub state: OrderState,
+
+    /// State transitions of the order, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<OrderTransition>,
+
+    /// Cancellation details, once the order has been cancelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancellation: Option<Cancellation>,
+
+    /// Changes made to the order after initialization, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<OrderChange>,
 
     /// Time when the order was created
     pub created_at: DateTime<Utc>,
@@ -78,6 +307,9 @@ pub struct Order {
 /// Order initialization request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderInitRequest {
+    /// Provider fulfilling the order
+    pub provider: ProviderSummary,
+
     /// List of items to order
     pub items: Vec<OrderItem>,
 
@@ -112,6 +344,80 @@ pub struct OrderConfirmResponse {
     pub order: Order,
 }
 
+/// Order cancellation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCancelRequest {
+    /// Order ID to cancel
+    pub order_id: String,
+
+    /// Reason code for the cancellation
+    pub cancellation_reason_id: CancellationReason,
+
+    /// Free text description of the reason
+    #[serde(default)]
+    pub descriptor: Option<String>,
+}
+
+/// Order cancellation response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderCancelResponse {
+    /// Cancelled order details
+    pub order: Order,
+}
+
+/// Change requested to an existing order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "update_target", rename_all = "lowercase")]
+pub enum OrderUpdate {
+    /// Reschedule the fulfillment to a new slot
+    Fulfillment {
+        /// New start of the fulfillment; the duration defaults to the
+        /// current one
+        start: TimeSlot,
+    },
+
+    /// Replace the ordered items, adding or removing items
+    Items {
+        /// Complete list of items after the update
+        items: Vec<SelectedItem>,
+    },
+
+    /// Correct the billing details
+    Billing {
+        /// Corrected billing details
+        billing: Box<Billing>,
+    },
+}
+
+impl OrderUpdate {
+    /// Part of the order changed by the update
+    pub fn target(&self) -> &'static str {
+        match self {
+            OrderUpdate::Fulfillment { .. } => "fulfillment",
+            OrderUpdate::Items { .. } => "items",
+            OrderUpdate::Billing { .. } => "billing",
+        }
+    }
+}
+
+/// Order update request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderUpdateRequest {
+    /// Order ID to update
+    pub order_id: String,
+
+    /// Requested change
+    #[serde(flatten)]
+    pub update: OrderUpdate,
+}
+
+/// Order update response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderUpdateResponse {
+    /// Updated order details
+    pub order: Order,
+}
+
 /// Order status request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderStatusRequest {
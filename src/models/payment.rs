@@ -1,4 +1,5 @@
 use crate::models::catalog::Price;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -68,6 +69,23 @@ pub struct Payment {
 
     /// Currency code for the payment
     pub currency: Option<String>,
+
+    /// Refund initiated for this payment, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refund: Option<Refund>,
+}
+
+/// Refund of (part of) a payment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Refund {
+    /// Amount to be refunded
+    pub amount: Price,
+
+    /// Current status of the refund (e.g. "INITIATED")
+    pub status: String,
+
+    /// Time when the refund was initiated
+    pub initiated_at: DateTime<Utc>,
 }
 
 /// Payment details for a transaction
@@ -46,6 +46,10 @@ pub struct Provider {
     /// Categories of services offered by the provider
     pub categories: Vec<Category>,
 
+    /// Contact details of the provider's support
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub support: Option<SupportContact>,
+
     /// Time when the provider was created
     pub created_at: DateTime<Utc>,
 
@@ -53,6 +57,29 @@ pub struct Provider {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Support contact details of a provider
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SupportContact {
+    /// Support phone number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+
+    /// Support email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    /// Support web page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl SupportContact {
+    /// Whether no contact channel is set
+    pub fn is_empty(&self) -> bool {
+        self.phone.is_none() && self.email.is_none() && self.url.is_none()
+    }
+}
+
 /// Location information for a provider
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Location {
@@ -79,6 +106,9 @@ pub struct Location {
 
     /// Area code or pincode
     pub area_code: Option<String>,
+
+    /// Tags associated with the location
+    pub tags: Option<HashMap<String, String>>,
 }
 
 /// Circle representing a service area
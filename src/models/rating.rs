@@ -0,0 +1,114 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Entity a rating applies to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RatingCategory {
+    /// The order as a whole
+    #[serde(rename = "ORDER")]
+    Order,
+
+    /// The fulfillment (consultation) of the order
+    #[serde(rename = "FULFILLMENT")]
+    Fulfillment,
+
+    /// The agent (doctor) who fulfilled the order
+    #[serde(rename = "AGENT")]
+    Agent,
+
+    /// The provider of the order
+    #[serde(rename = "PROVIDER")]
+    Provider,
+}
+
+impl RatingCategory {
+    /// Protocol code of the category
+    pub fn code(&self) -> &'static str {
+        match self {
+            RatingCategory::Order => "ORDER",
+            RatingCategory::Fulfillment => "FULFILLMENT",
+            RatingCategory::Agent => "AGENT",
+            RatingCategory::Provider => "PROVIDER",
+        }
+    }
+}
+
+/// Rating given by a patient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rating {
+    /// Kind of entity being rated
+    pub rating_category: RatingCategory,
+
+    /// ID of the rated entity
+    pub id: String,
+
+    /// Rating value from 1 to 5
+    pub value: u8,
+
+    /// Free text feedback
+    #[serde(default)]
+    pub feedback: Option<String>,
+}
+
+/// Aggregated rating of an entity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateRating {
+    /// Kind of entity rated
+    pub rating_category: RatingCategory,
+
+    /// ID of the rated entity
+    pub id: String,
+
+    /// Number of ratings received
+    pub count: u64,
+
+    /// Average rating value
+    pub average: f64,
+
+    /// Time of the latest rating
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AggregateRating {
+    /// Aggregate of `count` ratings whose values add up to `total`
+    pub fn from_total(
+        rating_category: RatingCategory,
+        id: impl Into<String>,
+        count: u64,
+        total: u64,
+        updated_at: DateTime<Utc>,
+    ) -> Self {
+        let average = if count == 0 {
+            0.0
+        } else {
+            total as f64 / count as f64
+        };
+        Self {
+            rating_category,
+            id: id.into(),
+            count,
+            average,
+            updated_at,
+        }
+    }
+}
+
+/// Rating request for a completed order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingRequest {
+    /// ID of the rated order
+    pub order_id: String,
+
+    /// Ratings for the order and the entities involved in it
+    pub ratings: Vec<Rating>,
+}
+
+/// Rating response with the updated aggregates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatingResponse {
+    /// ID of the rated order
+    pub order_id: String,
+
+    /// Aggregated ratings of the rated entities
+    pub ratings: Vec<AggregateRating>,
+}
@@ -0,0 +1,20 @@
+use super::provider::SupportContact;
+use serde::{Deserialize, Serialize};
+
+/// Support request for an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportRequest {
+    /// ID of the order support is needed for
+    pub order_id: String,
+}
+
+/// Support response with the provider's support contact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportResponse {
+    /// ID of the order
+    pub order_id: String,
+
+    /// Contact details of the provider's support
+    #[serde(flatten)]
+    pub contact: SupportContact,
+}
@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Whether live tracking of a fulfillment is available
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TrackingStatus {
+    /// The fulfillment can be tracked
+    #[serde(rename = "ACTIVE")]
+    Active,
+
+    /// No tracking is available, e.g. before the agent sets out or once the
+    /// fulfillment has ended
+    #[serde(rename = "INACTIVE")]
+    Inactive,
+}
+
+/// Position of the agent at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocationUpdate {
+    /// GPS coordinates as "latitude,longitude"
+    pub gps: String,
+
+    /// Time the position was recorded
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Live tracking information of a fulfillment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tracking {
+    /// ID of the tracked fulfillment
+    pub fulfillment_id: String,
+
+    /// URL where the patient can follow the fulfillment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// Tracking availability
+    pub status: TrackingStatus,
+
+    /// Location updates, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<LocationUpdate>,
+
+    /// Time of the latest tracking update
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Track request for an order's fulfillment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackRequest {
+    /// ID of the order to track
+    pub order_id: String,
+}
+
+/// Track response with the fulfillment's tracking information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackResponse {
+    /// ID of the tracked order
+    pub order_id: String,
+
+    /// Tracking information
+    pub tracking: Tracking,
+}
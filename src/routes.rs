@@ -1,33 +1,98 @@
+use crate::auth::SignatureAuth;
 use crate::handlers::{
+    ack::json_error_handler,
+    cancel::{cancel, on_cancel},
     confirm::{confirm, on_confirm},
     init::{init, on_init},
-    network_registry::{lookup, validate_signature},
+    network_registry::{
+        add_key, change_status, issue_domain_verification, lookup, status_history, subscribe,
+        validate_signature, verify_domain,
+    },
+    rating::{aggregate_rating, on_rating, rating},
     search::{on_search, search},
     select::{on_select, select},
     status::{on_status, status},
+    support::{on_support, support},
+    timeline::timeline,
+    track::{on_track, track},
+    update::{on_update, update},
 };
+use crate::idempotency::Idempotency;
 use actix_web::web;
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/v1")
-            // Search endpoints
-            .route("/search", web::post().to(search))
-            .route("/on_search", web::post().to(on_search))
-            // Select endpoints
-            .route("/select", web::post().to(select))
-            .route("/on_select", web::post().to(on_select))
-            // Init endpoints
-            .route("/init", web::post().to(init))
-            .route("/on_init", web::post().to(on_init))
-            // Confirm endpoints
-            .route("/confirm", web::post().to(confirm))
-            .route("/on_confirm", web::post().to(on_confirm))
-            // Status endpoints
-            .route("/status", web::post().to(status))
-            .route("/on_status", web::post().to(on_status))
+            // Malformed envelopes are rejected with a protocol NACK
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
             // Network registry endpoints
-            .route("/networkregistry/lookup", web::post().to(lookup))
-            .route("/networkregistry/validate", web::post().to(validate_signature)),
+            .service(
+                web::scope("/networkregistry")
+                    .route("/subscribe", web::post().to(subscribe))
+                    .route("/lookup", web::post().to(lookup))
+                    .route("/validate", web::post().to(validate_signature))
+                    // Subscribers manage their own keys and verify their
+                    // domain with signed requests, already while SUBSCRIBED
+                    .service(
+                        web::scope("/subscribers")
+                            .wrap(SignatureAuth::onboarding())
+                            .route("/{subscriber_id}/keys", web::post().to(add_key))
+                            .route(
+                                "/{subscriber_id}/domain-verification",
+                                web::post().to(issue_domain_verification),
+                            )
+                            .route(
+                                "/{subscriber_id}/domain-verification/verify",
+                                web::post().to(verify_domain),
+                            ),
+                    )
+                    // Participant lifecycle administration by registry admins
+                    .service(
+                        web::scope("/admin/subscribers")
+                            .wrap(SignatureAuth::new())
+                            .route("/{subscriber_id}/history", web::get().to(status_history))
+                            .route("/{subscriber_id}/{action}", web::post().to(change_status)),
+                    ),
+            )
+            // Protocol endpoints require signed requests; replayed messages
+            // are answered from the response to their first delivery
+            .service(
+                web::scope("")
+                    .wrap(Idempotency)
+                    .wrap(SignatureAuth::new())
+                    // Search endpoints
+                    .route("/search", web::post().to(search))
+                    .route("/on_search", web::post().to(on_search))
+                    // Select endpoints
+                    .route("/select", web::post().to(select))
+                    .route("/on_select", web::post().to(on_select))
+                    // Init endpoints
+                    .route("/init", web::post().to(init))
+                    .route("/on_init", web::post().to(on_init))
+                    // Confirm endpoints
+                    .route("/confirm", web::post().to(confirm))
+                    .route("/on_confirm", web::post().to(on_confirm))
+                    // Cancel endpoints
+                    .route("/cancel", web::post().to(cancel))
+                    .route("/on_cancel", web::post().to(on_cancel))
+                    // Update endpoints
+                    .route("/update", web::post().to(update))
+                    .route("/on_update", web::post().to(on_update))
+                    // Rating endpoints
+                    .route("/rating", web::post().to(rating))
+                    .route("/on_rating", web::post().to(on_rating))
+                    .route("/ratings/{category}/{id}", web::get().to(aggregate_rating))
+                    // Status endpoints
+                    .route("/status", web::post().to(status))
+                    .route("/on_status", web::post().to(on_status))
+                    // Track endpoints
+                    .route("/track", web::post().to(track))
+                    .route("/on_track", web::post().to(on_track))
+                    // Support endpoints
+                    .route("/support", web::post().to(support))
+                    .route("/on_support", web::post().to(on_support))
+                    // Order history
+                    .route("/orders/{order_id}/timeline", web::get().to(timeline)),
+            ),
     );
 }
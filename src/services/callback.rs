@@ -0,0 +1,435 @@
+use super::error::ServiceError;
+use crate::auth::signer::RequestSigner;
+use crate::config::{CallbackConfig, GatewayConfig};
+use crate::models::ack::ProtocolError;
+use crate::models::context::{Context, Envelope};
+use crate::storage::Storage;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Prefix for transaction store keys holding undeliverable callbacks
+pub const DEAD_LETTER_PREFIX: &str = "dead_letter:";
+
+/// Dispatcher delivering asynchronous on_* callbacks to the consumer_uri
+/// of the original request
+pub struct CallbackDispatcher {
+    /// Storage used to record dead letters
+    storage: Arc<dyn Storage>,
+    /// HTTP client for callback delivery
+    http_client: Client,
+    /// Signer attaching the gateway's signature to callbacks
+    signer: Arc<RequestSigner>,
+    /// Identity used in response contexts
+    gateway: GatewayConfig,
+    /// Delivery settings
+    config: CallbackConfig,
+}
+
+impl CallbackDispatcher {
+    /// Create a new callback dispatcher, failing if the HTTP client cannot
+    /// be built with the configured timeout
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        signer: Arc<RequestSigner>,
+        gateway: GatewayConfig,
+        config: CallbackConfig,
+    ) -> Result<Self, ServiceError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| {
+                ServiceError::Internal(format!("Failed to build callback HTTP client: {}", e))
+            })?;
+
+        Ok(Self {
+            storage,
+            http_client: client,
+            signer,
+            gateway,
+            config,
+        })
+    }
+
+    /// Build the response context the gateway uses for an `action` callback
+    pub fn response_context(&self, request_context: &Context, action: &str) -> Context {
+        request_context.create_response_context(
+            action.to_string(),
+            self.gateway.subscriber_id.clone(),
+            self.gateway.subscriber_url.clone(),
+        )
+    }
+
+    /// Deliver the outcome of processing `request_context` as an `action`
+    /// callback, returning the response context that was sent
+    pub async fn send<T: Serialize>(
+        &self,
+        request_context: &Context,
+        action: &str,
+        outcome: Result<T, ServiceError>,
+    ) -> Result<Context, ServiceError> {
+        let context = self.response_context(request_context, action);
+
+        let envelope = match outcome {
+            Ok(message) => Envelope {
+                context: context.clone(),
+                message: serde_json::to_value(message).map_err(|e| {
+                    ServiceError::Internal(format!("Failed to serialize callback: {}", e))
+                })?,
+                error: None,
+            },
+            Err(err) => Envelope {
+                context: context.clone(),
+                message: json!({}),
+                error: Some(ProtocolError::from(err)),
+            },
+        };
+
+        self.dispatch(&request_context.consumer_uri, &envelope)
+            .await?;
+        Ok(context)
+    }
+
+    /// Post a callback envelope to `{consumer_uri}/{action}`.
+    ///
+    /// Failed deliveries are retried with exponential backoff; once retries
+    /// are exhausted the envelope is recorded as a dead letter keyed by its
+    /// message ID.
+    pub async fn dispatch(
+        &self,
+        consumer_uri: &str,
+        envelope: &Envelope<Value>,
+    ) -> Result<(), ServiceError> {
+        let action = envelope.context.action.as_str();
+        let url = callback_url(consumer_uri, action);
+        let mut last_error = String::new();
+        let attempts = self.config.max_retries + 1;
+
+        for attempt in 0..attempts {
+            if attempt > 0 {
+                tokio::time::sleep(retry_delay(&self.config, attempt)).await;
+            }
+
+            match self.deliver(&url, envelope).await {
+                Ok(()) => {
+                    tracing::info!(
+                        "Delivered {} callback for transaction {} to {}",
+                        action,
+                        envelope.context.transaction_id,
+                        url
+                    );
+                    return Ok(());
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Callback {} to {} failed (attempt {}/{}): {}",
+                        action,
+                        url,
+                        attempt + 1,
+                        attempts,
+                        err
+                    );
+                    last_error = err;
+                }
+            }
+        }
+
+        self.record_dead_letter(&url, envelope, attempts, &last_error)
+            .await?;
+
+        Err(ServiceError::ExternalService(format!(
+            "Callback {} to {} failed after {} attempts: {}",
+            action, url, attempts, last_error
+        )))
+    }
+
+    /// Perform a single delivery attempt
+    async fn deliver(&self, url: &str, envelope: &Envelope<Value>) -> Result<(), String> {
+        let body = serde_json::to_vec(envelope).map_err(|e| e.to_string())?;
+        let request = self
+            .signer
+            .sign_request(self.http_client.post(url), body)
+            .map_err(|e| e.to_string())?;
+        let response = request.send().await.map_err(|e| e.to_string())?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Consumer responded with status {}",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Record an undeliverable callback in the transaction store
+    async fn record_dead_letter(
+        &self,
+        url: &str,
+        envelope: &Envelope<Value>,
+        attempts: u32,
+        last_error: &str,
+    ) -> Result<(), ServiceError> {
+        let record = json!({
+            "action": envelope.context.action,
+            "url": url,
+            "payload": envelope,
+            "attempts": attempts,
+            "last_error": last_error,
+            "failed_at": Utc::now(),
+        });
+
+        self.storage
+            .record_transaction(
+                &format!("{}{}", DEAD_LETTER_PREFIX, envelope.context.message_id),
+                record,
+            )
+            .await?;
+
+        tracing::error!(
+            "Recorded dead letter for {} callback {}",
+            envelope.context.action,
+            envelope.context.message_id
+        );
+        Ok(())
+    }
+}
+
+/// Delay before the given retry (starting at 1): the base backoff doubled on
+/// every retry, capped at `max_retry_backoff_ms`
+fn retry_delay(config: &CallbackConfig, retry: u32) -> Duration {
+    let factor = 2u64.checked_pow(retry - 1).unwrap_or(u64::MAX);
+    let delay = config
+        .retry_backoff_ms
+        .saturating_mul(factor)
+        .min(config.max_retry_backoff_ms);
+    Duration::from_millis(delay)
+}
+
+/// Build the callback URL for an action from the consumer's base URI
+fn callback_url(consumer_uri: &str, action: &str) -> String {
+    format!("{}/{}", consumer_uri.trim_end_matches('/'), action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::header::{SignatureHeader, AUTHORIZATION};
+    use crate::storage::memory::MemoryStorage;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// State shared with the stub consumer server
+    #[derive(Default)]
+    struct StubConsumer {
+        /// Number of requests to reject before accepting
+        failures: usize,
+        /// Number of requests received
+        hits: AtomicUsize,
+        /// Bodies of accepted callbacks
+        received: Mutex<Vec<Value>>,
+        /// Authorization headers of accepted callbacks
+        authorizations: Mutex<Vec<String>>,
+    }
+
+    async fn stub_callback(
+        req: HttpRequest,
+        body: web::Json<Value>,
+        state: web::Data<StubConsumer>,
+    ) -> HttpResponse {
+        let hit = state.hits.fetch_add(1, Ordering::SeqCst);
+        if hit < state.failures {
+            return HttpResponse::InternalServerError().finish();
+        }
+        if let Some(authorization) = req.headers().get(AUTHORIZATION) {
+            let authorization = authorization.to_str().unwrap().to_string();
+            state.authorizations.lock().unwrap().push(authorization);
+        }
+        state.received.lock().unwrap().push(body.into_inner());
+        HttpResponse::Ok().json(json!({"message": {"ack": {"status": "ACK"}}}))
+    }
+
+    // Start a local consumer that rejects the first `failures` callbacks
+    fn start_consumer(failures: usize) -> (String, web::Data<StubConsumer>) {
+        let state = web::Data::new(StubConsumer {
+            failures,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/{action}", web::post().to(stub_callback))
+        })
+        .listen(listener)
+        .unwrap()
+        .workers(1)
+        .run();
+        actix_rt::spawn(server);
+
+        (format!("http://127.0.0.1:{}", port), state)
+    }
+
+    fn create_dispatcher(storage: Arc<dyn Storage>, max_retries: u32) -> CallbackDispatcher {
+        let gateway = GatewayConfig {
+            allow_ephemeral_signing_key: true,
+            ..GatewayConfig::default()
+        };
+        let signer = RequestSigner::from_config(&gateway).unwrap();
+        CallbackDispatcher::new(
+            storage,
+            Arc::new(signer),
+            gateway,
+            CallbackConfig {
+                timeout_secs: 2,
+                max_retries,
+                retry_backoff_ms: 10,
+                max_retry_backoff_ms: 100,
+            },
+        )
+        .unwrap()
+    }
+
+    fn create_request_context(consumer_uri: &str) -> Context {
+        Context::new(
+            "nic2004:85111".to_string(),
+            "IND".to_string(),
+            "std:080".to_string(),
+            "search".to_string(),
+            "0.7.1".to_string(),
+            "eua-1".to_string(),
+            consumer_uri.to_string(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_send_delivers_callback() {
+        let (uri, consumer) = start_consumer(0);
+        let dispatcher = create_dispatcher(Arc::new(MemoryStorage::new()), 0);
+        let request_context = create_request_context(&uri);
+
+        let context = dispatcher
+            .send(&request_context, "on_search", Ok(json!({"catalog": {}})))
+            .await
+            .unwrap();
+
+        assert_eq!(context.action, "on_search");
+        assert_eq!(context.transaction_id, request_context.transaction_id);
+        assert_eq!(context.message_id, request_context.message_id);
+
+        let received = consumer.received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0]["context"]["action"], "on_search");
+        assert_eq!(received[0]["context"]["provider_id"], "uhi-gateway");
+        assert_eq!(received[0]["message"]["catalog"], json!({}));
+        assert!(received[0].get("error").is_none());
+
+        // Callbacks are signed with the gateway key
+        let authorizations = consumer.authorizations.lock().unwrap();
+        let header = SignatureHeader::parse(&authorizations[0]).unwrap();
+        assert_eq!(header.subscriber_id, "uhi-gateway");
+    }
+
+    #[actix_rt::test]
+    async fn test_send_reports_service_error() {
+        let (uri, consumer) = start_consumer(0);
+        let dispatcher = create_dispatcher(Arc::new(MemoryStorage::new()), 0);
+
+        dispatcher
+            .send::<Value>(
+                &create_request_context(&uri),
+                "on_search",
+                Err(ServiceError::NotFound("No catalogs found".to_string())),
+            )
+            .await
+            .unwrap();
+
+        let received = consumer.received.lock().unwrap();
+        assert_eq!(received[0]["error"]["type"], "DOMAIN-ERROR");
+        assert_eq!(received[0]["error"]["code"], "NOT_FOUND");
+    }
+
+    #[actix_rt::test]
+    async fn test_send_retries_failed_delivery() {
+        let (uri, consumer) = start_consumer(2);
+        let dispatcher = create_dispatcher(Arc::new(MemoryStorage::new()), 3);
+
+        let result = dispatcher
+            .send(&create_request_context(&uri), "on_select", Ok(json!({})))
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(consumer.hits.load(Ordering::SeqCst), 3);
+        assert_eq!(consumer.received.lock().unwrap().len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_dispatch_records_dead_letter() {
+        let (uri, consumer) = start_consumer(usize::MAX);
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let dispatcher = create_dispatcher(storage.clone(), 2);
+        let request_context = create_request_context(&uri);
+
+        let envelope = Envelope {
+            context: dispatcher.response_context(&request_context, "on_confirm"),
+            message: json!({"order": {"id": "order-1"}}),
+            error: None,
+        };
+        let result = dispatcher.dispatch(&uri, &envelope).await;
+
+        assert!(matches!(result, Err(ServiceError::ExternalService(_))));
+        assert_eq!(consumer.hits.load(Ordering::SeqCst), 3);
+
+        let letter = storage
+            .get_transaction(&format!(
+                "{}{}",
+                DEAD_LETTER_PREFIX, envelope.context.message_id
+            ))
+            .await
+            .unwrap();
+        assert_eq!(letter["action"], "on_confirm");
+        assert_eq!(letter["attempts"], 3);
+        assert_eq!(letter["url"], format!("{}/on_confirm", uri));
+        assert_eq!(letter["payload"]["message"]["order"]["id"], "order-1");
+    }
+
+    #[test]
+    fn test_callback_url() {
+        assert_eq!(
+            callback_url("http://eua/api/", "on_search"),
+            "http://eua/api/on_search"
+        );
+        assert_eq!(
+            callback_url("http://eua/api", "on_status"),
+            "http://eua/api/on_status"
+        );
+    }
+
+    #[test]
+    fn test_retry_delay_is_capped() {
+        let config = CallbackConfig {
+            retry_backoff_ms: 500,
+            max_retry_backoff_ms: 30_000,
+            ..CallbackConfig::default()
+        };
+
+        assert_eq!(retry_delay(&config, 1), Duration::from_millis(500));
+        assert_eq!(retry_delay(&config, 3), Duration::from_millis(2000));
+        assert_eq!(retry_delay(&config, 7), Duration::from_millis(30_000));
+
+        // Exponents and products beyond u64 saturate instead of overflowing
+        assert_eq!(retry_delay(&config, 64), Duration::from_millis(30_000));
+        assert_eq!(
+            retry_delay(&config, u32::MAX),
+            Duration::from_millis(30_000)
+        );
+    }
+}
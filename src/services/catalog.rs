@@ -1,6 +1,9 @@
 use super::error::ServiceError;
 use super::fulfillment::FulfillmentService;
-use crate::models::catalog::{Catalog, Item, Price, Quotation, QuotationBreakup};
+use crate::models::catalog::{
+    Catalog, Item, Price, Quotation, QuotationBreakup, SelectRequest, SelectResponse,
+};
+use crate::models::order::OrderItem;
 use crate::storage::Storage;
 use chrono::{DateTime, Duration, Utc};
 use serde_json::json;
@@ -175,15 +178,137 @@ impl CatalogService {
         Ok(selected_items)
     }
 
+    /// Select items with their quantities and fulfillment choice, returning
+    /// the selected items together with their quotation
+    pub async fn select_items(
+        &self,
+        request: &SelectRequest,
+    ) -> Result<SelectResponse, ServiceError> {
+        for (index, selected) in request.items.iter().enumerate() {
+            if selected.quantity < 1 {
+                return Err(ServiceError::Validation(format!(
+                    "Quantity for item {} must be at least 1",
+                    selected.id
+                )));
+            }
+            if request.items[..index].iter().any(|s| s.id == selected.id) {
+                return Err(ServiceError::Validation(format!(
+                    "Item {} is selected more than once",
+                    selected.id
+                )));
+            }
+        }
+
+        let item_ids = request.items.iter().map(|s| s.id.clone()).collect();
+        let items = self.select(&request.provider_id, item_ids).await?;
+
+        // Every selected item must be in the provider's catalog
+        let mut order_items = Vec::with_capacity(request.items.len());
+        for selected in &request.items {
+            let item = items
+                .iter()
+                .find(|item| item.id == selected.id)
+                .ok_or_else(|| {
+                    ServiceError::NotFound(format!(
+                        "Item {} not found in catalog of provider {}",
+                        selected.id, request.provider_id
+                    ))
+                })?;
+
+            if let Some(fulfillment_id) = &request.fulfillment_id {
+                if item.fulfillment_id != *fulfillment_id {
+                    return Err(ServiceError::Validation(format!(
+                        "Fulfillment {} is not offered for item {}",
+                        fulfillment_id, item.id
+                    )));
+                }
+            }
+
+            order_items.push(OrderItem {
+                id: item.id.clone(),
+                quantity: selected.quantity,
+                item: item.clone(),
+            });
+        }
+
+        let quote = self.quote(&request.provider_id, &order_items).await?;
+
+        Ok(SelectResponse {
+            provider_id: request.provider_id.clone(),
+            items: order_items,
+            fulfillment_id: request.fulfillment_id.clone(),
+            quote,
+        })
+    }
+
     /// Process price quotation
     pub async fn on_select(
         &self,
         provider_id: &str,
         items: Vec<Item>,
+    ) -> Result<Quotation, ServiceError> {
+        let order_items: Vec<OrderItem> = items
+            .into_iter()
+            .map(|item| OrderItem {
+                id: item.id.clone(),
+                quantity: 1,
+                item,
+            })
+            .collect();
+
+        self.quote(provider_id, &order_items).await
+    }
+
+    /// Re-validate ordered items against the provider's current catalog,
+    /// replacing the consumer's copy of each item with the catalog version
+    pub async fn resolve_items(
+        &self,
+        provider_id: &str,
+        order_items: &[OrderItem],
+    ) -> Result<Vec<OrderItem>, ServiceError> {
+        let catalog = self.get_catalog(provider_id).await?;
+
+        order_items
+            .iter()
+            .map(|order_item| {
+                if order_item.quantity < 1 {
+                    return Err(ServiceError::Validation(format!(
+                        "Quantity for item {} must be at least 1",
+                        order_item.id
+                    )));
+                }
+
+                let item = catalog
+                    .items
+                    .iter()
+                    .find(|item| item.id == order_item.id)
+                    .ok_or_else(|| {
+                        ServiceError::NotFound(format!(
+                            "Item {} not found in catalog of provider {}",
+                            order_item.id, provider_id
+                        ))
+                    })?;
+
+                Ok(OrderItem {
+                    id: item.id.clone(),
+                    quantity: order_item.quantity,
+                    item: item.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Quote items in the ordered quantities, with one breakup line per item
+    pub async fn quote(
+        &self,
+        provider_id: &str,
+        order_items: &[OrderItem],
     ) -> Result<Quotation, ServiceError> {
         // Validate provider exists
         let _ = self.storage.get_provider(provider_id).await?;
 
+        let items: Vec<Item> = order_items.iter().map(|o| o.item.clone()).collect();
+
         // Check availability for items that require specific fulfillment slots
         let availability = self.check_item_availability(provider_id, &items).await?;
         
@@ -201,24 +326,34 @@ impl CatalogService {
         let mut total = 0.0;
         let mut breakup = Vec::new();
 
-        for item in &items {
+        for order_item in order_items {
+            let item = &order_item.item;
             let price_value = item.price.value.parse::<f64>().unwrap_or(0.0);
-            total += price_value;
+            total += price_value * order_item.quantity as f64;
 
-            // Add a breakdown entry for each item
-            breakup.push(QuotationBreakup {
-                title: item.descriptor.name.clone(),
-                price: Price {
-                    currency: item.price.currency.clone(),
-                    value: item.price.value.clone(),
-                    maximum_value: item.price.maximum_value.clone(),
-                },
-            });
+            // Add a breakdown entry for each item, priced for its quantity
+            let breakup_item = if order_item.quantity == 1 {
+                QuotationBreakup {
+                    title: item.descriptor.name.clone(),
+                    price: item.price.clone(),
+                }
+            } else {
+                QuotationBreakup {
+                    title: format!("{} x {}", item.descriptor.name, order_item.quantity),
+                    price: Price {
+                        currency: item.price.currency.clone(),
+                        value: self
+                            .round_price(price_value * order_item.quantity as f64)
+                            .to_string(),
+                        maximum_value: None,
+                    },
+                }
+            };
+            breakup.push(breakup_item);
         }
 
         // Round to the configured precision
-        total = (total * 10.0_f64.powi(self.config.price_precision as i32)).round() 
-                / 10.0_f64.powi(self.config.price_precision as i32);
+        total = self.round_price(total);
 
         // Create the complete quotation
         let quotation = Quotation {
@@ -233,6 +368,9 @@ impl CatalogService {
             },
             breakup,
             ttl: format!("PT{}M", self.config.quotation_ttl_default),
+            valid_until: Some(
+                Utc::now() + Duration::minutes(self.config.quotation_ttl_default as i64),
+            ),
         };
 
         // Track this quotation in a transaction
@@ -250,6 +388,12 @@ impl CatalogService {
         Ok(quotation)
     }
 
+    /// Round a price to the configured precision
+    fn round_price(&self, value: f64) -> f64 {
+        let factor = 10.0_f64.powi(self.config.price_precision as i32);
+        (value * factor).round() / factor
+    }
+
     /// Check availability for specific items
     pub async fn check_availability(
         &self,
@@ -417,6 +561,7 @@ mod tests {
                 state: None,
                 country: None,
                 area_code: None,
+                tags: None,
             }],
             items: vec![Item {
                 id: "item-1".to_string(),
@@ -457,6 +602,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -488,6 +634,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -521,6 +668,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -542,6 +690,91 @@ mod tests {
         assert_eq!(quotation.breakup[0].price.value, "100.0");
     }
     
+    // Helper function to create a provider with the test catalog
+    async fn create_service_with_catalog(provider_id: &str) -> CatalogService {
+        let storage = Arc::new(MemoryStorage::new());
+        let provider = Provider {
+            id: provider_id.to_string(),
+            descriptor: Descriptor {
+                name: "Test Provider".to_string(),
+                short_desc: None,
+                long_desc: None,
+                images: None,
+            },
+            categories: Vec::new(),
+            support: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let _ = storage.create_provider(provider).await.unwrap();
+
+        let service = CatalogService::new(storage);
+        let _ = service
+            .create_catalog(provider_id, create_test_catalog())
+            .await
+            .unwrap();
+        service
+    }
+
+    fn select_request(provider_id: &str, quantity: i32, fulfillment_id: &str) -> SelectRequest {
+        SelectRequest {
+            provider_id: provider_id.to_string(),
+            items: vec![crate::models::catalog::SelectedItem {
+                id: "item-1".to_string(),
+                quantity,
+            }],
+            fulfillment_id: Some(fulfillment_id.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_select_items_quotes_quantities() {
+        let service = create_service_with_catalog("provider-4").await;
+
+        let response = service
+            .select_items(&select_request("provider-4", 3, "fulfillment-1"))
+            .await
+            .unwrap();
+
+        assert_eq!(response.provider_id, "provider-4");
+        assert_eq!(response.fulfillment_id.as_deref(), Some("fulfillment-1"));
+        assert_eq!(response.items.len(), 1);
+        assert_eq!(response.items[0].quantity, 3);
+        assert_eq!(response.items[0].item.descriptor.name, "Test Item");
+
+        // Breakup is priced for the selected quantity
+        let quote = response.quote;
+        assert_eq!(quote.price.value, "300");
+        assert_eq!(quote.price.currency, "INR");
+        assert_eq!(quote.breakup[0].title, "Test Item x 3");
+        assert_eq!(quote.breakup[0].price.value, "300");
+        assert_eq!(quote.ttl, "PT15M");
+    }
+
+    #[tokio::test]
+    async fn test_select_items_rejects_invalid_selection() {
+        let service = create_service_with_catalog("provider-5").await;
+
+        let result = service
+            .select_items(&select_request("provider-5", 0, "fulfillment-1"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let result = service
+            .select_items(&select_request("provider-5", 1, "home-visit"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Unknown items are reported even when other items match
+        let mut request = select_request("provider-5", 1, "fulfillment-1");
+        request.items.push(crate::models::catalog::SelectedItem {
+            id: "item-404".to_string(),
+            quantity: 1,
+        });
+        let result = service.select_items(&request).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(msg)) if msg.contains("item-404")));
+    }
+
     #[tokio::test]
     async fn test_validate_catalog_invalid_price() {
         let storage = Arc::new(MemoryStorage::new());
@@ -0,0 +1,131 @@
+use super::error::ServiceError;
+use async_trait::async_trait;
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::error::ResolveErrorKind;
+use hickory_resolver::TokioAsyncResolver;
+
+/// Path of the site verification file on the subscriber's host
+pub const SITE_VERIFICATION_PATH: &str = "/uhi-site-verification.html";
+
+/// Name of the meta tag carrying the signed request ID in the site
+/// verification file
+pub const SITE_VERIFICATION_META: &str = "uhi-site-verification";
+
+/// Label prefixed to the subscriber's host to name the TXT record carrying
+/// the signed request ID
+pub const TXT_RECORD_LABEL: &str = "_uhi-site-verification";
+
+/// Resolver of DNS TXT records for domain verification
+#[async_trait]
+pub trait TxtResolver: Send + Sync {
+    /// TXT records published for `name`; empty when there are none
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>, ServiceError>;
+}
+
+/// TXT resolver using the system DNS configuration
+pub struct SystemResolver {
+    resolver: TokioAsyncResolver,
+}
+
+impl SystemResolver {
+    /// Create a resolver from the system configuration, falling back to the
+    /// default upstream servers when it cannot be read
+    pub fn new() -> Self {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf().unwrap_or_else(|err| {
+            tracing::warn!("Failed to read the system DNS configuration: {}", err);
+            TokioAsyncResolver::tokio(ResolverConfig::default(), ResolverOpts::default())
+        });
+        Self { resolver }
+    }
+}
+
+impl Default for SystemResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TxtResolver for SystemResolver {
+    async fn txt_records(&self, name: &str) -> Result<Vec<String>, ServiceError> {
+        match self.resolver.txt_lookup(name).await {
+            Ok(lookup) => Ok(lookup
+                .iter()
+                .map(|txt| {
+                    txt.txt_data()
+                        .iter()
+                        .map(|chunk| String::from_utf8_lossy(chunk))
+                        .collect()
+                })
+                .collect()),
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                Ok(Vec::new())
+            }
+            Err(err) => Err(ServiceError::ExternalService(format!(
+                "Failed to resolve TXT records of {}: {}",
+                name, err
+            ))),
+        }
+    }
+}
+
+/// Name of the TXT record carrying the signed request ID of `host`
+pub fn txt_record_name(host: &str) -> String {
+    format!("{}.{}", TXT_RECORD_LABEL, host)
+}
+
+/// Signed request ID in the verification meta tag of a site verification
+/// file, e.g. `<meta name="uhi-site-verification" content="..." />`
+pub fn site_verification_signature(html: &str) -> Option<&str> {
+    html.split("<meta").skip(1).find_map(|tag| {
+        let tag = &tag[..tag.find('>')?];
+        if attribute(tag, "name")? != SITE_VERIFICATION_META {
+            return None;
+        }
+        attribute(tag, "content")
+    })
+}
+
+/// Value of a quoted attribute of an HTML tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut from = 0;
+    while let Some(offset) = tag[from..].find(name) {
+        let start = from + offset;
+        from = start + name.len();
+        if !tag[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = tag[from..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        return value.find(quote).map(|end| value[..end].trim());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_site_verification_signature() {
+        let html = r#"<html>
+            <head>
+                <meta charset="utf-8">
+                <meta name='uhi-site-verification' content='c2lnbmVk' />
+            </head>
+            <body>UHI site verification</body>
+        </html>"#;
+        assert_eq!(site_verification_signature(html), Some("c2lnbmVk"));
+
+        let reordered = r#"<meta content="c2lnbmVk" name="uhi-site-verification">"#;
+        assert_eq!(site_verification_signature(reordered), Some("c2lnbmVk"));
+
+        let other = r#"<meta name="description" content="c2lnbmVk">"#;
+        assert_eq!(site_verification_signature(other), None);
+        assert_eq!(site_verification_signature("<html></html>"), None);
+    }
+}
@@ -16,6 +16,9 @@ pub enum ServiceError {
     /// Business logic error
     BusinessLogic(String),
 
+    /// Caller may not act on the resource
+    Forbidden(String),
+
     /// External service error
     ExternalService(String),
 
@@ -30,6 +33,7 @@ impl fmt::Display for ServiceError {
             ServiceError::NotFound(msg) => write!(f, "Not found: {}", msg),
             ServiceError::Validation(msg) => write!(f, "Validation error: {}", msg),
             ServiceError::BusinessLogic(msg) => write!(f, "Business logic error: {}", msg),
+            ServiceError::Forbidden(msg) => write!(f, "Forbidden: {}", msg),
             ServiceError::ExternalService(msg) => write!(f, "External service error: {}", msg),
             ServiceError::Internal(msg) => write!(f, "Internal error: {}", msg),
         }
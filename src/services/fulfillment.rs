@@ -1,10 +1,16 @@
 use super::error::ServiceError;
 use super::provider::ProviderService;
-use crate::models::fulfillment::{Fulfillment, TimeSlot, State};
-use crate::storage::Storage;
+use crate::config::FulfillmentConfig;
+use crate::models::fulfillment::{Fulfillment, FulfillmentState, State, TimeSlot};
+use crate::models::tracking::{Tracking, TrackingStatus};
+use crate::storage::search::parse_gps;
+use crate::storage::{Storage, StorageError};
 use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
-use std::sync::Arc;
 use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Number of location updates kept per fulfillment
+const MAX_LOCATION_UPDATES: usize = 100;
 
 /// Fulfillment service for managing healthcare service delivery
 pub struct FulfillmentService {
@@ -12,42 +18,52 @@ pub struct FulfillmentService {
     storage: Arc<dyn Storage>,
     /// Provider service for checking provider availability
     provider_service: ProviderService,
+    /// State transitions allowed per fulfillment type
+    config: FulfillmentConfig,
 }
 
 impl FulfillmentService {
     /// Create a new fulfillment service with storage dependency
     pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self::with_config(storage, FulfillmentConfig::default())
+    }
+
+    /// Create a new fulfillment service enforcing the given lifecycles
+    pub fn with_config(storage: Arc<dyn Storage>, config: FulfillmentConfig) -> Self {
         let provider_service = ProviderService::new(storage.clone());
         Self {
             storage,
             provider_service,
+            config,
         }
     }
 
-    /// Create a new fulfillment
+    /// Create a new fulfillment, booking its time slot.
+    ///
+    /// The slot must lie within the provider's working hours. Storage books
+    /// it atomically, so a slot can never be double booked, even across
+    /// gateway instances.
     pub async fn create_fulfillment(
         &self,
         fulfillment: Fulfillment,
     ) -> Result<Fulfillment, ServiceError> {
-        // First check if the requested time slot is available
-        let is_available = self
-            .check_availability(
-                &fulfillment.provider_id,
+        let provider_id = fulfillment.provider_id.clone();
+        let within_hours = self
+            .within_working_hours(
+                &provider_id,
                 &fulfillment.start.time.timestamp,
                 fulfillment.start.duration.unwrap_or(3600),
             )
             .await?;
 
-        if !is_available {
-            return Err(ServiceError::BusinessLogic(format!(
-                "Requested time slot is not available for provider {}",
-                fulfillment.provider_id
-            )));
+        if !within_hours {
+            return Err(slot_unavailable(&provider_id));
         }
 
-        // Create the fulfillment in storage
-        let created = self.storage.create_fulfillment(fulfillment).await?;
-        Ok(created)
+        self.storage
+            .book_fulfillment(fulfillment)
+            .await
+            .map_err(|err| booking_error(err, &provider_id))
     }
 
     /// Get a fulfillment by ID
@@ -82,74 +98,215 @@ impl FulfillmentService {
     }
 
     /// Update the state of a fulfillment
-    /// 
+    ///
     /// # Parameters
     /// * `fulfillment_id` - The ID of the fulfillment to update
-    /// * `state` - The new state descriptor (e.g., "SCHEDULED", "IN_PROGRESS", "COMPLETED")
+    /// * `state` - The new state, which must be reachable under the lifecycle of the fulfillment type
     /// * `context` - Optional context information for the state change
-    /// 
+    ///
     /// # Returns
     /// * `Result<Fulfillment, ServiceError>` - Updated fulfillment or error
     pub async fn update_state(
         &self,
         fulfillment_id: &str,
-        state: &str,
+        state: FulfillmentState,
         context: Option<HashMap<String, String>>,
     ) -> Result<Fulfillment, ServiceError> {
         // Get the current fulfillment
         let mut fulfillment = self.get_fulfillment(fulfillment_id).await?;
-        
+
         // Validate state transition
         self.validate_state_transition(&fulfillment, state)?;
-        
+
         // Update the state
         fulfillment.state = Some(State {
-            descriptor: state.to_string(),
+            descriptor: state,
             updated_at: Utc::now(),
         });
-        
+
         // Add context information to tags if provided
         if let Some(ctx) = context {
             for (key, value) in ctx {
                 fulfillment.tags.insert(format!("state_change_{}", key), value);
             }
         }
-        
+
         // Update in storage
         let updated = self.storage.update_fulfillment(fulfillment).await?;
         Ok(updated)
     }
-    
-    /// Validate if the state transition is allowed
-    fn validate_state_transition(&self, fulfillment: &Fulfillment, new_state: &str) -> Result<(), ServiceError> {
-        // Get current state, if not set, any transition is valid
-        let current_state = match &fulfillment.state {
-            Some(state) => &state.descriptor,
-            None => return Ok(()),
-        };
-        
-        // Define allowed state transitions
-        let allowed_transitions: HashMap<&str, Vec<&str>> = [
-            // From -> To
-            ("SCHEDULED", vec!["WAITING", "IN_PROGRESS", "CANCELLED", "NO_SHOW", "RESCHEDULED"]),
-            ("WAITING", vec!["IN_PROGRESS", "CANCELLED", "NO_SHOW"]),
-            ("IN_PROGRESS", vec!["COMPLETED", "CANCELLED"]),
-            ("COMPLETED", vec![]),  // Terminal state
-            ("CANCELLED", vec![]),  // Terminal state
-            ("NO_SHOW", vec!["RESCHEDULED"]),
-            ("RESCHEDULED", vec!["SCHEDULED"]),
-        ].iter().cloned().collect();
-        
-        // Check if transition is allowed
-        if let Some(allowed) = allowed_transitions.get(current_state.as_str()) {
-            if !allowed.contains(&new_state) {
-                return Err(ServiceError::BusinessLogic(format!(
-                    "Invalid state transition from '{}' to '{}'",
-                    current_state, new_state
+
+    /// Move a scheduled fulfillment to a new time slot.
+    ///
+    /// The fulfillment passes through RESCHEDULED back to SCHEDULED. Storage
+    /// books the new slot atomically, so it cannot be double booked.
+    pub async fn reschedule(
+        &self,
+        fulfillment_id: &str,
+        start: DateTime<Utc>,
+        duration_seconds: i64,
+    ) -> Result<Fulfillment, ServiceError> {
+        let mut fulfillment = self.get_fulfillment(fulfillment_id).await?;
+        self.validate_state_transition(&fulfillment, FulfillmentState::Rescheduled)?;
+
+        let within_hours = self
+            .within_working_hours(&fulfillment.provider_id, &start, duration_seconds)
+            .await?;
+
+        if !within_hours {
+            return Err(slot_unavailable(&fulfillment.provider_id));
+        }
+
+        fulfillment.tags.insert(
+            "rescheduled_from".to_string(),
+            fulfillment.start.time.timestamp.to_rfc3339(),
+        );
+        fulfillment.start.time.timestamp = start;
+        fulfillment.start.duration = Some(duration_seconds);
+        fulfillment.end.time.timestamp = start + Duration::seconds(duration_seconds);
+        fulfillment.state = Some(State {
+            descriptor: FulfillmentState::Scheduled,
+            updated_at: Utc::now(),
+        });
+
+        let provider_id = fulfillment.provider_id.clone();
+        self.storage
+            .rebook_fulfillment(fulfillment)
+            .await
+            .map_err(|err| booking_error(err, &provider_id))
+    }
+
+    /// Get the tracking information of a fulfillment.
+    ///
+    /// Fulfillments without tracking updates, and fulfillments that have
+    /// ended, are reported as INACTIVE.
+    pub async fn get_tracking(&self, fulfillment: &Fulfillment) -> Result<Tracking, ServiceError> {
+        let mut tracking = self
+            .find_tracking(&fulfillment.id)
+            .await?
+            .unwrap_or_else(|| Tracking {
+                fulfillment_id: fulfillment.id.clone(),
+                url: None,
+                status: TrackingStatus::Inactive,
+                locations: Vec::new(),
+                updated_at: Utc::now(),
+            });
+
+        if has_ended(fulfillment) {
+            tracking.status = TrackingStatus::Inactive;
+        }
+        Ok(tracking)
+    }
+
+    /// Record a tracking update for a fulfillment.
+    ///
+    /// The tracking URL is kept unless the update replaces it, and location
+    /// updates are appended to the ones received so far.
+    pub async fn record_tracking(
+        &self,
+        fulfillment: &Fulfillment,
+        update: Tracking,
+    ) -> Result<Tracking, ServiceError> {
+        if update.fulfillment_id != fulfillment.id {
+            return Err(ServiceError::Validation(format!(
+                "Tracking is for fulfillment {}, expected {}",
+                update.fulfillment_id, fulfillment.id
+            )));
+        }
+
+        if let Some(url) = &update.url {
+            let valid = url::Url::parse(url)
+                .is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"));
+            if !valid {
+                return Err(ServiceError::Validation(format!(
+                    "Invalid tracking URL: {}",
+                    url
                 )));
             }
         }
-        
+
+        if let Some(location) = update
+            .locations
+            .iter()
+            .find(|location| parse_gps(&location.gps).is_none())
+        {
+            return Err(ServiceError::Validation(format!(
+                "Invalid GPS coordinates: {}",
+                location.gps
+            )));
+        }
+
+        if update.status == TrackingStatus::Active && has_ended(fulfillment) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Fulfillment {} has ended and cannot be tracked",
+                fulfillment.id
+            )));
+        }
+
+        let mut tracking = match self.find_tracking(&fulfillment.id).await? {
+            Some(mut tracking) => {
+                tracking.url = update.url.or(tracking.url);
+                tracking.status = update.status;
+                tracking.locations.extend(update.locations);
+                tracking
+            }
+            None => update,
+        };
+        tracking
+            .locations
+            .sort_by_key(|location| location.timestamp);
+        let excess = tracking
+            .locations
+            .len()
+            .saturating_sub(MAX_LOCATION_UPDATES);
+        tracking.locations.drain(..excess);
+        tracking.updated_at = Utc::now();
+
+        Ok(self.storage.save_tracking(tracking).await?)
+    }
+
+    /// Load the tracking information recorded for a fulfillment
+    async fn find_tracking(&self, fulfillment_id: &str) -> Result<Option<Tracking>, ServiceError> {
+        match self.storage.get_tracking(fulfillment_id).await {
+            Ok(tracking) => Ok(Some(tracking)),
+            Err(StorageError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Validate a state transition against the lifecycle of the
+    /// fulfillment's type.
+    ///
+    /// Fulfillments without a state may move to any state. A current state
+    /// that is not part of the lifecycle is rejected rather than trusted.
+    fn validate_state_transition(
+        &self,
+        fulfillment: &Fulfillment,
+        new_state: FulfillmentState,
+    ) -> Result<(), ServiceError> {
+        let Some(current_state) = fulfillment.state.as_ref().map(|state| state.descriptor) else {
+            return Ok(());
+        };
+
+        let transitions = self
+            .config
+            .transitions_for(&fulfillment.fulfillment_type)
+            .iter()
+            .find(|transitions| transitions.from == current_state)
+            .ok_or_else(|| {
+                ServiceError::Validation(format!(
+                    "State '{}' is not part of the '{}' fulfillment lifecycle",
+                    current_state, fulfillment.fulfillment_type
+                ))
+            })?;
+
+        if !transitions.to.contains(&new_state) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Invalid state transition from '{}' to '{}'",
+                current_state, new_state
+            )));
+        }
+
         Ok(())
     }
 
@@ -168,72 +325,72 @@ impl FulfillmentService {
         requested_time: &DateTime<Utc>,
         duration_seconds: i64,
     ) -> Result<bool, ServiceError> {
-        // First check if the provider is available at the requested time
-        let provider_available = self
-            .provider_service
-            .check_provider_availability(provider_id, requested_time)
-            .await?;
-
-        if !provider_available {
-            // Provider is not available at this time (outside working hours)
+        if !self
+            .within_working_hours(provider_id, requested_time, duration_seconds)
+            .await?
+        {
             return Ok(false);
         }
 
-        // Calculate the end time based on duration
+        // Check for time slot overlaps with existing fulfillments
         let requested_end_time = *requested_time + Duration::seconds(duration_seconds);
-
-        // Check if the end time is still within the provider's working hours
-        let end_time_available = self
-            .provider_service
-            .check_provider_availability(provider_id, &requested_end_time)
-            .await?;
-
-        if !end_time_available {
-            // The appointment would end outside of working hours
-            return Ok(false);
-        }
-
-        // Get all existing fulfillments for this provider
         let provider_fulfillments = self
             .storage
             .list_fulfillments_by_provider(provider_id)
             .await?;
 
-        // Check for time slot overlaps with existing fulfillments
-        for fulfillment in provider_fulfillments {
-            // Calculate the existing fulfillment's start and end times
-            let existing_start_time = fulfillment.start.time.timestamp;
-            let existing_end_time = if let Some(duration) = fulfillment.start.duration {
-                existing_start_time + Duration::seconds(duration)
-            } else if fulfillment.end.time.timestamp > existing_start_time {
-                // If no duration but end time exists, use that
-                fulfillment.end.time.timestamp
-            } else {
-                // Default to 1-hour appointment if no duration or end time
-                existing_start_time + Duration::seconds(3600)
-            };
-
-            // Check for overlap
-            // An overlap occurs if:
-            // - The requested start time is within an existing slot, or
-            // - The requested end time is within an existing slot, or
-            // - The requested slot completely contains an existing slot
-            if (requested_time >= &existing_start_time && requested_time < &existing_end_time)
-                || (requested_end_time > existing_start_time
-                    && requested_end_time <= existing_end_time)
-                || (requested_time <= &existing_start_time
-                    && requested_end_time >= existing_end_time)
+        Ok(!provider_fulfillments
+            .iter()
+            .any(|fulfillment| fulfillment.overlaps(*requested_time, requested_end_time)))
+    }
+
+    /// Check that a slot starts and ends within the provider's working hours
+    async fn within_working_hours(
+        &self,
+        provider_id: &str,
+        requested_time: &DateTime<Utc>,
+        duration_seconds: i64,
+    ) -> Result<bool, ServiceError> {
+        let requested_end_time = *requested_time + Duration::seconds(duration_seconds);
+
+        for time in [requested_time, &requested_end_time] {
+            if !self
+                .provider_service
+                .check_provider_availability(provider_id, time)
+                .await?
             {
-                // Time slot overlaps with an existing appointment
                 return Ok(false);
             }
         }
 
-        // If we get here, no overlaps were found and the provider is available
         Ok(true)
     }
 }
 
+/// Error for a slot the provider cannot take
+fn slot_unavailable(provider_id: &str) -> ServiceError {
+    ServiceError::BusinessLogic(format!(
+        "Requested time slot is not available for provider {}",
+        provider_id
+    ))
+}
+
+/// Report booking conflicts as an unavailable slot
+fn booking_error(err: StorageError, provider_id: &str) -> ServiceError {
+    match err {
+        StorageError::Conflict(_) => slot_unavailable(provider_id),
+        err => err.into(),
+    }
+}
+
+/// Whether a fulfillment has reached a state in which it is over
+fn has_ended(fulfillment: &Fulfillment) -> bool {
+    fulfillment
+        .state
+        .as_ref()
+        .is_some_and(|state| state.descriptor.has_ended())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -294,7 +451,7 @@ mod tests {
                 },
             }),
             state: Some(State {
-                descriptor: "SCHEDULED".to_string(),
+                descriptor: FulfillmentState::Scheduled,
                 updated_at: Utc::now(),
             }),
             tags: HashMap::new(),
@@ -316,6 +473,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -374,6 +532,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -425,6 +584,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -502,6 +662,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -582,4 +743,119 @@ mod tests {
             }
         }
     }
+
+    // Store a fulfillment of `fulfillment_type` in `state`, bypassing booking
+    async fn store_fulfillment(
+        storage: &Arc<MemoryStorage>,
+        id: &str,
+        fulfillment_type: &str,
+        state: FulfillmentState,
+    ) {
+        let mut fulfillment = create_test_fulfillment(id, "provider-5", Utc::now(), 3600);
+        fulfillment.fulfillment_type = fulfillment_type.to_string();
+        fulfillment.state.as_mut().unwrap().descriptor = state;
+        storage.create_fulfillment(fulfillment).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_state_transitions_follow_fulfillment_type() {
+        let storage = Arc::new(MemoryStorage::new());
+        let service = FulfillmentService::new(storage.clone());
+        store_fulfillment(
+            &storage,
+            "visit-1",
+            "home-visit",
+            FulfillmentState::Scheduled,
+        )
+        .await;
+        store_fulfillment(
+            &storage,
+            "pickup-1",
+            "lab-pickup",
+            FulfillmentState::Scheduled,
+        )
+        .await;
+
+        // Home visits have no waiting room; the agent travels instead
+        let result = service
+            .update_state("visit-1", FulfillmentState::Waiting, None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+        for state in [
+            FulfillmentState::EnRoute,
+            FulfillmentState::InProgress,
+            FulfillmentState::Completed,
+        ] {
+            let updated = service.update_state("visit-1", state, None).await.unwrap();
+            assert_eq!(updated.state.unwrap().descriptor, state);
+        }
+
+        // A collected sample can no longer be cancelled
+        for state in [FulfillmentState::EnRoute, FulfillmentState::SampleCollected] {
+            service.update_state("pickup-1", state, None).await.unwrap();
+        }
+        let result = service
+            .update_state("pickup-1", FulfillmentState::Cancelled, None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_state_outside_lifecycle_is_rejected() {
+        let storage = Arc::new(MemoryStorage::new());
+        let service = FulfillmentService::new(storage.clone());
+        store_fulfillment(&storage, "visit-2", "home-visit", FulfillmentState::Waiting).await;
+
+        let result = service
+            .update_state("visit-2", FulfillmentState::InProgress, None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Unknown state codes do not deserialize
+        let state = serde_json::from_value::<State>(serde_json::json!({
+            "descriptor": "ON_HOLD",
+            "updated_at": Utc::now(),
+        }));
+        assert!(state.is_err());
+    }
+
+    #[test]
+    fn test_configured_transitions_replace_builtin() {
+        let load = |toml: &str| {
+            config::Config::builder()
+                .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+                .build()
+                .unwrap()
+                .try_deserialize::<FulfillmentConfig>()
+        };
+
+        let config = load(
+            r#"
+            [types]
+            Home-Visit = [
+                { from = "SCHEDULED", to = ["IN_PROGRESS"] },
+                { from = "IN_PROGRESS", to = ["COMPLETED"] },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let home_visit = config.transitions_for("home-visit");
+        assert_eq!(home_visit.len(), 2);
+        assert_eq!(home_visit[0].to, vec![FulfillmentState::InProgress]);
+
+        // Other types keep their built-in tables, unknown types the default
+        let lab_pickup = config.transitions_for("lab-pickup");
+        assert!(lab_pickup
+            .iter()
+            .any(|transitions| transitions.from == FulfillmentState::SampleCollected));
+        assert_eq!(
+            config.transitions_for("clinic-visit"),
+            FulfillmentConfig::default().default.as_slice()
+        );
+
+        // Unknown states are a configuration error
+        let result = load(r#"default = [{ from = "SCHEDULED", to = ["ON_HOLD"] }]"#);
+        assert!(result.is_err());
+    }
 }
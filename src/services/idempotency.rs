@@ -0,0 +1,203 @@
+use super::error::ServiceError;
+use crate::config::IdempotencyConfig;
+use crate::models::history::EventOrigin;
+use crate::models::idempotency::{ProcessedMessage, RecordedResponse};
+use crate::storage::{Storage, StorageError};
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+
+/// Outcome of claiming a message for processing
+#[derive(Debug, Clone, PartialEq)]
+pub enum Claim {
+    /// First delivery of the message; it should be processed
+    New,
+
+    /// Replay of a processed message, answered with its recorded response
+    Replay(RecordedResponse),
+
+    /// Replay of a message whose first delivery is still being processed
+    InProgress,
+}
+
+/// Idempotency service deduplicating protocol messages by sender, message ID
+/// and action
+pub struct IdempotencyService {
+    /// Storage implementation injected via constructor
+    storage: Arc<dyn Storage>,
+    /// Deduplication settings
+    config: IdempotencyConfig,
+}
+
+impl IdempotencyService {
+    /// Create a new idempotency service with the default window
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self::with_config(storage, IdempotencyConfig::default())
+    }
+
+    /// Create a new idempotency service with the given settings
+    pub fn with_config(storage: Arc<dyn Storage>, config: IdempotencyConfig) -> Self {
+        Self { storage, config }
+    }
+
+    /// Whether messages are deduplicated
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Claim the message described by `origin` for processing.
+    ///
+    /// The first delivery within the window claims the message; later
+    /// deliveries get the response recorded for it, if any. A claim without
+    /// a response only holds for the processing lease, so a message whose
+    /// processing was abandoned, e.g. by a crashed instance, can be claimed
+    /// again.
+    pub async fn claim(&self, origin: &EventOrigin) -> Result<Claim, ServiceError> {
+        let now = Utc::now();
+        let message = ProcessedMessage {
+            subscriber_id: origin.subscriber_id.clone(),
+            message_id: origin.message_id.clone(),
+            action: origin.action.clone(),
+            response: None,
+            received_at: now,
+            expires_at: now + Duration::seconds(self.config.processing_lease_secs),
+        };
+
+        match self.storage.create_processed_message(message).await {
+            Ok(_) => Ok(Claim::New),
+            Err(StorageError::Duplicate(_)) => {
+                let existing = self.get(origin).await?;
+                Ok(match existing.response {
+                    Some(response) => Claim::Replay(response),
+                    None => Claim::InProgress,
+                })
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Record the response to a claimed message, answering its replays with
+    /// it for the rest of the window
+    pub async fn complete(
+        &self,
+        origin: &EventOrigin,
+        response: RecordedResponse,
+    ) -> Result<(), ServiceError> {
+        let mut message = self.get(origin).await?;
+        message.response = Some(response);
+        message.expires_at = message.received_at + Duration::seconds(self.config.window_secs);
+        self.storage.update_processed_message(message).await?;
+        Ok(())
+    }
+
+    /// Release a claimed message so that it can be delivered again
+    pub async fn release(&self, origin: &EventOrigin) -> Result<(), ServiceError> {
+        self.storage
+            .delete_processed_message(&origin.subscriber_id, &origin.message_id, &origin.action)
+            .await?;
+        Ok(())
+    }
+
+    async fn get(&self, origin: &EventOrigin) -> Result<ProcessedMessage, ServiceError> {
+        let message = self
+            .storage
+            .get_processed_message(&origin.subscriber_id, &origin.message_id, &origin.action)
+            .await?;
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryStorage;
+    use serde_json::json;
+
+    fn origin(subscriber_id: &str, action: &str) -> EventOrigin {
+        EventOrigin::new(subscriber_id, action, "message-1")
+    }
+
+    fn ack() -> RecordedResponse {
+        RecordedResponse {
+            status: 200,
+            body: json!({"message": {"ack": {"status": "ACK"}}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_claim_replays_recorded_response() {
+        let service = IdempotencyService::new(Arc::new(MemoryStorage::new()));
+        let confirm = origin("eua-1", "confirm");
+
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::New);
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::InProgress);
+
+        service.complete(&confirm, ack()).await.unwrap();
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::Replay(ack()));
+
+        // The same message ID from another sender or for another action is new
+        assert_eq!(
+            service.claim(&origin("eua-2", "confirm")).await.unwrap(),
+            Claim::New
+        );
+        assert_eq!(
+            service.claim(&origin("eua-1", "cancel")).await.unwrap(),
+            Claim::New
+        );
+    }
+
+    #[tokio::test]
+    async fn test_released_and_expired_messages_are_claimed_again() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = IdempotencyService::new(storage.clone());
+        let init = origin("eua-1", "init");
+
+        service.claim(&init).await.unwrap();
+        service.release(&init).await.unwrap();
+        assert_eq!(service.claim(&init).await.unwrap(), Claim::New);
+
+        // Records stop deduplicating once the window has passed
+        let expiring = IdempotencyService::with_config(
+            storage,
+            IdempotencyConfig {
+                enabled: true,
+                window_secs: 0,
+                ..IdempotencyConfig::default()
+            },
+        );
+        let status = origin("eua-1", "status");
+        assert_eq!(expiring.claim(&status).await.unwrap(), Claim::New);
+        expiring.complete(&status, ack()).await.unwrap();
+        assert_eq!(expiring.claim(&status).await.unwrap(), Claim::New);
+    }
+
+    #[tokio::test]
+    async fn test_stale_in_progress_claims_are_claimed_again() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = IdempotencyService::new(storage.clone());
+        let confirm = origin("eua-1", "confirm");
+
+        // A claim whose processing lease has lapsed was abandoned
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::New);
+        let mut claimed = storage
+            .get_processed_message("eua-1", "message-1", "confirm")
+            .await
+            .unwrap();
+        assert!(claimed.expires_at <= claimed.received_at + Duration::seconds(60));
+        claimed.expires_at = Utc::now() - Duration::seconds(1);
+        storage.update_processed_message(claimed).await.unwrap();
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::New);
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::InProgress);
+
+        // Completed messages are deduplicated for the whole window
+        service.complete(&confirm, ack()).await.unwrap();
+        let completed = storage
+            .get_processed_message("eua-1", "message-1", "confirm")
+            .await
+            .unwrap();
+        assert_eq!(
+            completed.expires_at,
+            completed.received_at + Duration::seconds(86400)
+        );
+        assert_eq!(service.claim(&confirm).await.unwrap(), Claim::Replay(ack()));
+    }
+}
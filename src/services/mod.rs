@@ -1,15 +1,22 @@
+pub mod callback;
 pub mod catalog;
+pub mod domain_verification;
 pub mod error;
 pub mod fulfillment;
+pub mod idempotency;
 pub mod network_registry;
 pub mod order;
 pub mod provider;
+pub mod rating;
 pub mod search;
 
+pub use callback::CallbackDispatcher;
 pub use catalog::CatalogService;
 pub use error::ServiceError;
 pub use fulfillment::FulfillmentService;
+pub use idempotency::IdempotencyService;
 pub use network_registry::NetworkRegistryService;
 pub use order::OrderService;
 pub use provider::ProviderService;
+pub use rating::RatingService;
 pub use search::SearchService;
@@ -1,17 +1,34 @@
+use super::domain_verification::{
+    site_verification_signature, txt_record_name, SystemResolver, TxtResolver,
+    SITE_VERIFICATION_META, SITE_VERIFICATION_PATH,
+};
 use super::error::ServiceError;
 use crate::models::network_registry::{NetworkRegistryLookup, Subscriber, LookupRequest, LookupResponse, Participant};
-use crate::storage::Storage;
+use crate::models::network_registry::{
+    AddKeyRequest, DomainVerification, DomainVerificationMethod, KeyPurpose, OnSubscribeRequest,
+    OnSubscribeResponse, ParticipantAction, ParticipantStatus, RegistrationResponse,
+    SignatureAlgorithm, SubscribeRequest, SubscriberKey, SubscriberStatusChange,
+};
+use crate::auth::keys;
+use crate::config::SeedSubscriber;
+use crate::storage::{Storage, StorageError};
 use std::sync::Arc;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use ring::signature::{self, UnparsedPublicKey, KeyPair, Ed25519KeyPair, ECDSA_P256_SHA256_ASN1};
-use ring::rand::SystemRandom;
+use ring::signature::{KeyPair, Ed25519KeyPair};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{aead, hkdf};
+use x25519_dalek::{PublicKey, StaticSecret};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use std::time::Duration;
 use reqwest::Client;
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use url::Url;
 use std::str::FromStr;
+use uuid::Uuid;
+
+/// HKDF info binding keys derived for subscribe challenges to that purpose
+const CHALLENGE_KEY_INFO: &[u8] = b"uhi-registry-subscribe-challenge";
 
 /// Network registry service for managing network participants
 pub struct NetworkRegistryService {
@@ -19,21 +36,36 @@ pub struct NetworkRegistryService {
     storage: Arc<dyn Storage>,
     /// HTTP client for domain verification
     http_client: Client,
+    /// Resolver of TXT records for DNS domain verification
+    resolver: Arc<dyn TxtResolver>,
 }
 
 impl NetworkRegistryService {
-    /// Create a new network registry service with storage dependency
-    pub fn new(storage: Arc<dyn Storage>) -> Self {
+    /// Create a new network registry service with storage dependency,
+    /// failing if the HTTP client cannot be built
+    pub fn new(storage: Arc<dyn Storage>) -> Result<Self, ServiceError> {
+        Self::with_resolver(storage, Arc::new(SystemResolver::new()))
+    }
+
+    /// Create a new network registry service resolving TXT records with the
+    /// given resolver, failing if the HTTP client cannot be built
+    pub fn with_resolver(
+        storage: Arc<dyn Storage>,
+        resolver: Arc<dyn TxtResolver>,
+    ) -> Result<Self, ServiceError> {
         // Create HTTP client with reasonable timeout
         let client = Client::builder()
             .timeout(Duration::from_secs(10))
             .build()
-            .unwrap_or_default();
+            .map_err(|e| {
+                ServiceError::Internal(format!("Failed to build registry HTTP client: {}", e))
+            })?;
 
-        Self { 
+        Ok(Self {
             storage,
             http_client: client,
-        }
+            resolver,
+        })
     }
 
     /// Register a new subscriber
@@ -44,16 +76,318 @@ impl NetworkRegistryService {
         // Validate subscriber data
         self.validate_subscriber(&subscriber)?;
 
-        // Optionally verify domain if URL is provided
-        if !subscriber.url.is_empty() {
-            self.verify_domain_ownership(&subscriber).await?;
-        }
-
         // Register in storage
         let registered = self.storage.register_subscriber(subscriber).await?;
         Ok(registered)
     }
 
+    /// Subscribe a participant to the network (subscribe).
+    ///
+    /// The participant is recorded as INITIATED and sent a random challenge,
+    /// encrypted to its X25519 key, at `{url}/on_subscribe`. It becomes
+    /// SUBSCRIBED only when it answers with the decrypted challenge;
+    /// participants that fail the challenge stay INITIATED and may subscribe
+    /// again from the same URL. The keys of a repeated subscription replace
+    /// the stored ones only once its challenge is answered.
+    pub async fn subscribe(
+        &self,
+        request: SubscribeRequest,
+    ) -> Result<RegistrationResponse, ServiceError> {
+        let encryption_key = decode_x25519_key(&request.encryption_public_key)?;
+        let now = Utc::now();
+        let subscriber = Subscriber {
+            id: request.subscriber_id,
+            type_field: request.type_field,
+            domain: request.domain,
+            city: request.city,
+            country: request.country,
+            url: request.url,
+            status: ParticipantStatus::Initiated,
+            public_key: request.signing_public_key,
+            encryption_public_key: Some(request.encryption_public_key),
+            keys: Vec::new(),
+            domain_verification: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.validate_subscriber(&subscriber)?;
+
+        let subscriber = match self.storage.get_subscriber(&subscriber.id).await {
+            Ok(existing) if existing.status == ParticipantStatus::Initiated => {
+                if existing.url != subscriber.url {
+                    return Err(ServiceError::BusinessLogic(format!(
+                        "Subscriber {} is already subscribing from another URL",
+                        existing.id
+                    )));
+                }
+                Subscriber {
+                    created_at: existing.created_at,
+                    ..subscriber
+                }
+            }
+            Ok(existing) => {
+                return Err(ServiceError::BusinessLogic(format!(
+                    "Subscriber {} is already {}",
+                    existing.id, existing.status
+                )))
+            }
+            Err(StorageError::NotFound(_)) => self.storage.register_subscriber(subscriber).await?,
+            Err(err) => return Err(err.into()),
+        };
+
+        let (challenge, on_subscribe) = encrypt_challenge(&subscriber.id, encryption_key)?;
+        let answer = self.send_challenge(&subscriber.url, &on_subscribe).await?;
+        if answer != challenge {
+            return Err(ServiceError::Validation(format!(
+                "Subscriber {} did not answer the on_subscribe challenge correctly",
+                subscriber.id
+            )));
+        }
+
+        let subscribed = self
+            .set_status(
+                subscriber,
+                ParticipantStatus::Subscribed,
+                "Answered the on_subscribe challenge",
+                None,
+            )
+            .await?;
+
+        Ok(RegistrationResponse {
+            subscriber_id: subscribed.id,
+            status: subscribed.status,
+        })
+    }
+
+    /// Register the configured seed participants as ACTIVE.
+    ///
+    /// Seeds already registered with the same signing key are left as they
+    /// are, so a suspended admin stays suspended across restarts; a seed
+    /// registered with another key is an error.
+    pub async fn seed(&self, seeds: &[SeedSubscriber]) -> Result<(), ServiceError> {
+        for seed in seeds {
+            match self.storage.get_subscriber(&seed.subscriber_id).await {
+                Ok(existing) if existing.public_key == seed.signing_public_key => continue,
+                Ok(existing) => {
+                    return Err(ServiceError::BusinessLogic(format!(
+                        "Subscriber {} is already registered with another signing key",
+                        existing.id
+                    )))
+                }
+                Err(StorageError::NotFound(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let now = Utc::now();
+            let subscriber = self
+                .register_subscriber(Subscriber {
+                    id: seed.subscriber_id.clone(),
+                    type_field: seed.type_field.clone(),
+                    domain: seed.domain.clone(),
+                    city: None,
+                    country: None,
+                    url: seed.url.clone(),
+                    status: ParticipantStatus::Initiated,
+                    public_key: seed.signing_public_key.clone(),
+                    encryption_public_key: None,
+                    keys: Vec::new(),
+                    domain_verification: None,
+                    created_at: now,
+                    updated_at: now,
+                })
+                .await?;
+            self.set_status(
+                subscriber,
+                ParticipantStatus::Active,
+                "Seeded from the registry configuration",
+                None,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Post an on_subscribe challenge to a participant and return its answer
+    async fn send_challenge(
+        &self,
+        url: &str,
+        request: &OnSubscribeRequest,
+    ) -> Result<String, ServiceError> {
+        let url = format!("{}/on_subscribe", url.trim_end_matches('/'));
+        let response = self
+            .http_client
+            .post(&url)
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| {
+                ServiceError::ExternalService(format!("on_subscribe to {} failed: {}", url, e))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(ServiceError::ExternalService(format!(
+                "on_subscribe to {} failed with status {}",
+                url,
+                response.status()
+            )));
+        }
+
+        let response: OnSubscribeResponse = response.json().await.map_err(|e| {
+            ServiceError::ExternalService(format!(
+                "Invalid on_subscribe response from {}: {}",
+                url, e
+            ))
+        })?;
+        Ok(response.answer)
+    }
+
+    /// Admit a subscribed participant to the network
+    pub async fn activate(
+        &self,
+        subscriber_id: &str,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        self.change_status(
+            subscriber_id,
+            ParticipantAction::Activate,
+            reason,
+            changed_by,
+        )
+        .await
+    }
+
+    /// Suspend a participant; suspended participants fail signature
+    /// validation and are left out of lookups
+    pub async fn suspend(
+        &self,
+        subscriber_id: &str,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        self.change_status(
+            subscriber_id,
+            ParticipantAction::Suspend,
+            reason,
+            changed_by,
+        )
+        .await
+    }
+
+    /// Readmit a suspended participant
+    pub async fn reinstate(
+        &self,
+        subscriber_id: &str,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        self.change_status(
+            subscriber_id,
+            ParticipantAction::Reinstate,
+            reason,
+            changed_by,
+        )
+        .await
+    }
+
+    /// Permanently remove a participant from the network
+    pub async fn deregister(
+        &self,
+        subscriber_id: &str,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        self.change_status(
+            subscriber_id,
+            ParticipantAction::Deregister,
+            reason,
+            changed_by,
+        )
+        .await
+    }
+
+    /// Audit trail of a participant's status changes, oldest first
+    pub async fn status_history(
+        &self,
+        subscriber_id: &str,
+    ) -> Result<Vec<SubscriberStatusChange>, ServiceError> {
+        self.get_subscriber(subscriber_id).await?;
+        let changes = self
+            .storage
+            .list_subscriber_status_changes(subscriber_id)
+            .await?;
+        Ok(changes)
+    }
+
+    /// Apply an administrative action to a participant, failing when the
+    /// action does not apply to its current status
+    async fn change_status(
+        &self,
+        subscriber_id: &str,
+        action: ParticipantAction,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        let reason = reason.trim();
+        if reason.is_empty() {
+            return Err(ServiceError::Validation(format!(
+                "A reason is required to {} a participant",
+                action.code()
+            )));
+        }
+
+        let subscriber = self.get_subscriber(subscriber_id).await?;
+        let status = action.status_after(subscriber.status).ok_or_else(|| {
+            ServiceError::BusinessLogic(format!(
+                "Cannot {} subscriber {} while it is {}",
+                action.code(),
+                subscriber_id,
+                subscriber.status
+            ))
+        })?;
+        if action == ParticipantAction::Activate && !subscriber.is_domain_verified() {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Subscriber {} has not verified the ownership of its domain",
+                subscriber_id
+            )));
+        }
+
+        self.set_status(subscriber, status, reason, changed_by)
+            .await
+    }
+
+    /// Move a subscriber to `status`, recording the change in its audit trail
+    async fn set_status(
+        &self,
+        subscriber: Subscriber,
+        status: ParticipantStatus,
+        reason: &str,
+        changed_by: Option<&str>,
+    ) -> Result<Subscriber, ServiceError> {
+        let now = Utc::now();
+        let change = SubscriberStatusChange {
+            subscriber_id: subscriber.id.clone(),
+            from: subscriber.status,
+            to: status,
+            reason: reason.to_string(),
+            changed_by: changed_by.map(str::to_string),
+            changed_at: now,
+        };
+
+        let subscriber = self
+            .storage
+            .update_subscriber(Subscriber {
+                status,
+                updated_at: now,
+                ..subscriber
+            })
+            .await?;
+        self.storage.append_subscriber_status_change(change).await?;
+        tracing::info!("Subscriber {} is now {}: {}", subscriber.id, status, reason);
+
+        Ok(subscriber)
+    }
+
     /// Get a subscriber by ID
     pub async fn get_subscriber(&self, id: &str) -> Result<Subscriber, ServiceError> {
         let subscriber = self.storage.get_subscriber(id).await?;
@@ -73,6 +407,12 @@ impl NetworkRegistryService {
         }
 
         let subscriber = self.storage.lookup_subscriber(lookup).await?;
+        if !subscriber.status.is_participating() {
+            return Err(ServiceError::NotFound(format!(
+                "Subscriber {} is {}",
+                subscriber.id, subscriber.status
+            )));
+        }
         Ok(subscriber)
     }
 
@@ -95,7 +435,8 @@ impl NetworkRegistryService {
         let mut participants = Vec::new();
         
         for subscriber in subscribers {
-            let mut matches = true;
+            // Only active participants are listed
+            let mut matches = subscriber.status.is_participating();
             
             // Filter by subscriber_id if provided
             if let Some(ref id) = request.subscriber_id {
@@ -106,7 +447,7 @@ impl NetworkRegistryService {
             
             // Filter by domain if provided
             if let Some(ref domain) = request.domain {
-                if !subscriber.domain.contains(domain) {
+                if subscriber.domain != *domain {
                     matches = false;
                 }
             }
@@ -146,35 +487,288 @@ impl NetworkRegistryService {
         Ok(subscribers)
     }
 
-    /// Validate a signature using the subscriber's public key
+    /// Register a new key for a subscriber, optionally scheduling the
+    /// retirement of the key it replaces.
+    ///
+    /// Subscribers that have not registered a signing key yet sign with their
+    /// `public_key`; their first signing key must retire it, which registers
+    /// it under the retired key ID until the retirement time.
+    pub async fn add_key(
+        &self,
+        subscriber_id: &str,
+        request: AddKeyRequest,
+    ) -> Result<Subscriber, ServiceError> {
+        let mut subscriber = self.get_subscriber(subscriber_id).await?;
+        let now = Utc::now();
+        let key = SubscriberKey {
+            unique_key_id: request.unique_key_id,
+            purpose: request.purpose,
+            public_key: request.public_key,
+            algorithm: request.algorithm,
+            valid_from: request.valid_from.unwrap_or(now),
+            valid_until: request.valid_until,
+        };
+        validate_key(&key)?;
+
+        if subscriber
+            .keys
+            .iter()
+            .any(|existing| existing.unique_key_id == key.unique_key_id)
+        {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Subscriber {} already has a key {}",
+                subscriber_id, key.unique_key_id
+            )));
+        }
+
+        match request.retire {
+            Some(retire) => {
+                if retire.unique_key_id == key.unique_key_id {
+                    return Err(ServiceError::Validation(
+                        "A key cannot retire itself".to_string(),
+                    ));
+                }
+                if retire.at < key.valid_from {
+                    return Err(ServiceError::Validation(format!(
+                        "Key {} cannot be retired before key {} becomes valid",
+                        retire.unique_key_id, key.unique_key_id
+                    )));
+                }
+
+                let signs_with_public_key =
+                    key.purpose == KeyPurpose::Signing && !subscriber.has_keys(KeyPurpose::Signing);
+                let public_key = subscriber.public_key.clone();
+                let created_at = subscriber.created_at;
+                match subscriber.keys.iter_mut().find(|existing| {
+                    existing.unique_key_id == retire.unique_key_id
+                        && existing.purpose == key.purpose
+                }) {
+                    Some(existing) => existing.valid_until = Some(retire.at),
+                    None if signs_with_public_key => subscriber.keys.push(SubscriberKey {
+                        unique_key_id: retire.unique_key_id,
+                        purpose: KeyPurpose::Signing,
+                        public_key,
+                        algorithm: SignatureAlgorithm::Ed25519,
+                        valid_from: created_at,
+                        valid_until: Some(retire.at),
+                    }),
+                    None => {
+                        return Err(ServiceError::NotFound(format!(
+                            "Subscriber {} has no {:?} key {}",
+                            subscriber_id, key.purpose, retire.unique_key_id
+                        )))
+                    }
+                }
+            }
+            None if key.purpose == KeyPurpose::Signing
+                && !subscriber.has_keys(KeyPurpose::Signing) =>
+            {
+                return Err(ServiceError::Validation(format!(
+                    "The first signing key of subscriber {} must retire the key it signs with",
+                    subscriber_id
+                )));
+            }
+            None => {}
+        }
+
+        tracing::info!(
+            "Adding {:?} key {} for subscriber {}",
+            key.purpose,
+            key.unique_key_id,
+            subscriber_id
+        );
+        subscriber.keys.push(key);
+        subscriber.updated_at = now;
+        let subscriber = self.storage.update_subscriber(subscriber).await?;
+        Ok(subscriber)
+    }
+
+    /// Validate a signature using the subscriber's signing key.
+    ///
+    /// The key is selected by `unique_key_id` and must be valid now; without
+    /// a key ID the most recent valid signing key is used. Subscribers that
+    /// have not registered signing keys are verified with their `public_key`
+    /// as an Ed25519 key. A signature `algorithm` other than the key's is a
+    /// validation error.
     pub async fn validate_signature(
         &self,
         subscriber_id: &str,
+        unique_key_id: Option<&str>,
+        algorithm: Option<SignatureAlgorithm>,
         signature: &str,
         message: &[u8],
     ) -> Result<bool, ServiceError> {
         // Get the subscriber to retrieve their public key
         let subscriber = self.get_subscriber(subscriber_id).await?;
+        if !subscriber.status.is_participating() {
+            return Err(ServiceError::Validation(format!(
+                "Subscriber {} is {} and may not sign requests",
+                subscriber_id, subscriber.status
+            )));
+        }
         
-        // Decode the signature from base64
-        let signature_bytes = match BASE64.decode(signature) {
-            Ok(bytes) => bytes,
-            Err(_) => return Err(ServiceError::Validation("Invalid signature format".to_string())),
+        verify_signature(&subscriber, unique_key_id, algorithm, signature, message)
+    }
+
+    /// Authenticate the signer of a request, returning its registry entry.
+    ///
+    /// Only signers with one of the `admitted` statuses are accepted; the
+    /// signature is checked as in `validate_signature`, failing with a
+    /// validation error when it does not verify.
+    pub async fn authenticate(
+        &self,
+        subscriber_id: &str,
+        unique_key_id: &str,
+        algorithm: SignatureAlgorithm,
+        signature: &str,
+        message: &[u8],
+        admitted: &[ParticipantStatus],
+    ) -> Result<Subscriber, ServiceError> {
+        let subscriber = self.get_subscriber(subscriber_id).await?;
+        if !admitted.contains(&subscriber.status) {
+            return Err(ServiceError::Validation(format!(
+                "Subscriber {} is {} and may not sign these requests",
+                subscriber_id, subscriber.status
+            )));
+        }
+
+        let valid = verify_signature(
+            &subscriber,
+            Some(unique_key_id),
+            Some(algorithm),
+            signature,
+            message,
+        )?;
+        if !valid {
+            return Err(ServiceError::Validation(format!(
+                "Signature does not verify with key {} of subscriber {}",
+                unique_key_id, subscriber_id
+            )));
+        }
+        Ok(subscriber)
+    }
+
+    /// Issue a request ID for the subscriber to sign and publish on the host
+    /// of its URL, replacing any previous domain verification
+    pub async fn issue_domain_verification(
+        &self,
+        subscriber_id: &str,
+    ) -> Result<DomainVerification, ServiceError> {
+        let mut subscriber = self.get_subscriber(subscriber_id).await?;
+        let now = Utc::now();
+        let verification = DomainVerification {
+            request_id: Uuid::new_v4().to_string(),
+            host: url_host(&subscriber.url)?,
+            issued_at: now,
+            verified_at: None,
+            method: None,
         };
-        
-        // Decode the public key from base64
-        let public_key_bytes = match BASE64.decode(&subscriber.public_key) {
-            Ok(bytes) => bytes,
-            Err(_) => return Err(ServiceError::Validation("Invalid public key format".to_string())),
+
+        subscriber.domain_verification = Some(verification.clone());
+        subscriber.updated_at = now;
+        self.storage.update_subscriber(subscriber).await?;
+        Ok(verification)
+    }
+
+    /// Verify that the subscriber controls the host of its URL.
+    ///
+    /// The request ID issued to the subscriber must be signed with its
+    /// signing key and served in the site verification file of the host, or
+    /// published in the host's verification TXT record.
+    pub async fn verify_domain(
+        &self,
+        subscriber_id: &str,
+        method: DomainVerificationMethod,
+    ) -> Result<Subscriber, ServiceError> {
+        let mut subscriber = self.get_subscriber(subscriber_id).await?;
+        let mut verification = subscriber.domain_verification.clone().ok_or_else(|| {
+            ServiceError::BusinessLogic(format!(
+                "No domain verification was issued to subscriber {}",
+                subscriber_id
+            ))
+        })?;
+
+        let signatures = match method {
+            DomainVerificationMethod::SiteVerification => {
+                vec![self.fetch_site_verification(&subscriber.url).await?]
+            }
+            DomainVerificationMethod::DnsTxt => {
+                self.resolver
+                    .txt_records(&txt_record_name(&verification.host))
+                    .await?
+            }
         };
-        
-        // Verify the signature (assuming Ed25519 algorithm, you might need to support others)
-        let public_key = UnparsedPublicKey::new(&signature::ED25519, &public_key_bytes);
-        
-        match public_key.verify(message, &signature_bytes) {
-            Ok(_) => Ok(true),
-            Err(_) => Ok(false),
+
+        // Unrelated TXT records and malformed signatures do not verify
+        let request_id = verification.request_id.as_bytes();
+        let verified = signatures.iter().any(|signature| {
+            verify_signature(&subscriber, None, None, signature.trim(), request_id).unwrap_or(false)
+        });
+        if !verified {
+            return Err(ServiceError::Validation(format!(
+                "Domain verification of {} by {} failed: request ID {} is not signed by subscriber {}",
+                verification.host, method, verification.request_id, subscriber_id
+            )));
         }
+
+        let now = Utc::now();
+        verification.verified_at = Some(now);
+        verification.method = Some(method);
+        subscriber.domain_verification = Some(verification);
+        subscriber.updated_at = now;
+        let subscriber = self.storage.update_subscriber(subscriber).await?;
+        tracing::info!(
+            "Subscriber {} verified its domain by {}",
+            subscriber_id,
+            method
+        );
+
+        Ok(subscriber)
+    }
+
+    /// Fetch the signed request ID from the site verification file of the
+    /// host of `url`
+    async fn fetch_site_verification(&self, url: &str) -> Result<String, ServiceError> {
+        let host = url_host(url)?;
+        let file_url = Url::parse(url)
+            .and_then(|url| url.join(SITE_VERIFICATION_PATH))
+            .map_err(|err| ServiceError::Validation(format!("Invalid URL {}: {}", url, err)))?;
+
+        let response = self
+            .http_client
+            .get(file_url.clone())
+            .send()
+            .await
+            .map_err(|err| {
+                ServiceError::ExternalService(format!("Failed to fetch {}: {}", file_url, err))
+            })?;
+        // Redirects must not hand the proof over to another host
+        if response.url().host_str() != Some(host.as_str()) {
+            return Err(ServiceError::Validation(format!(
+                "Site verification file {} redirected to {}",
+                file_url,
+                response.url()
+            )));
+        }
+        if !response.status().is_success() {
+            return Err(ServiceError::Validation(format!(
+                "Site verification file {} returned HTTP status {}",
+                file_url,
+                response.status()
+            )));
+        }
+
+        let html = response.text().await.map_err(|err| {
+            ServiceError::ExternalService(format!("Failed to read {}: {}", file_url, err))
+        })?;
+        site_verification_signature(&html)
+            .map(str::to_string)
+            .ok_or_else(|| {
+                ServiceError::Validation(format!(
+                    "Site verification file {} has no {} meta tag",
+                    file_url, SITE_VERIFICATION_META
+                ))
+            })
     }
 
     /// Create a test key pair for development and testing
@@ -201,70 +795,6 @@ impl NetworkRegistryService {
         Ok((public_key, private_key))
     }
 
-    /// Verify domain ownership using DNS verification or web verification
-    async fn verify_domain_ownership(&self, subscriber: &Subscriber) -> Result<(), ServiceError> {
-        // Parse URL to extract domain
-        let url = match Url::parse(&subscriber.url) {
-            Ok(u) => u,
-            Err(_) => return Err(ServiceError::Validation(format!(
-                "Invalid URL: {}", subscriber.url
-            ))),
-        };
-        
-        let domain = match url.host_str() {
-            Some(host) => host,
-            None => return Err(ServiceError::Validation(
-                "URL has no host component".to_string()
-            )),
-        };
-        
-        // Check if the domain matches the claimed domain in the subscriber record
-        if domain != subscriber.domain {
-            return Err(ServiceError::Validation(format!(
-                "URL domain {} does not match claimed domain {}",
-                domain, subscriber.domain
-            )));
-        }
-        
-        // In a real implementation, we would perform actual domain verification such as:
-        // 1. DNS TXT record verification
-        // 2. Serving a specific file at a well-known URL
-        // 3. HTTPS certificate validation
-        
-        // For now, we'll just do a simple HTTP GET to verify the domain is reachable
-        // In production, this should be replaced with proper verification
-        
-        // Skip actual HTTP verification for localhost/development
-        if domain.contains("localhost") || domain.contains("127.0.0.1") {
-            return Ok(());
-        }
-        
-        // Make a HEAD request to the URL to check if it's reachable
-        match self.http_client.head(&subscriber.url).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    Ok(())
-                } else {
-                    Err(ServiceError::Validation(format!(
-                        "Domain verification failed: HTTP status {}", response.status()
-                    )))
-                }
-            },
-            Err(e) => Err(ServiceError::Validation(format!(
-                "Domain verification failed: {}", e
-            ))),
-        }
-    }
-    
-    /// Generate a verification token for a domain
-    pub fn generate_verification_token(&self, subscriber_id: &str) -> String {
-        // In a real implementation, this would generate a secure random token
-        // and store it associated with the domain for later verification
-        
-        // For simplicity, we're just creating a deterministic token here
-        format!("uhi-verify-{}-{}", subscriber_id, Utc::now().timestamp())
-    }
-
     /// Validate subscriber data
     fn validate_subscriber(&self, subscriber: &Subscriber) -> Result<(), ServiceError> {
         // Check that required fields are present
@@ -310,11 +840,269 @@ impl NetworkRegistryService {
     }
 }
 
+/// Host of a subscriber URL, whose ownership the subscriber proves
+fn url_host(url: &str) -> Result<String, ServiceError> {
+    let url = Url::parse(url)
+        .map_err(|err| ServiceError::Validation(format!("Invalid URL {}: {}", url, err)))?;
+    url.host_str()
+        .map(str::to_string)
+        .ok_or_else(|| ServiceError::Validation("URL has no host component".to_string()))
+}
+
+/// Verify a base64 signature of `message` with the subscriber's signing key
+fn verify_signature(
+    subscriber: &Subscriber,
+    unique_key_id: Option<&str>,
+    algorithm: Option<SignatureAlgorithm>,
+    signature: &str,
+    message: &[u8],
+) -> Result<bool, ServiceError> {
+    // Decode the signature from base64
+    let signature_bytes = match BASE64.decode(signature) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return Err(ServiceError::Validation(
+                "Invalid signature format".to_string(),
+            ))
+        }
+    };
+
+    // Decode the public key for the algorithm it was registered with
+    let (public_key, key_algorithm) = signing_key(subscriber, unique_key_id, Utc::now())?;
+    if let Some(algorithm) = algorithm.filter(|algorithm| *algorithm != key_algorithm) {
+        return Err(ServiceError::Validation(format!(
+            "Signature algorithm {} does not match the {} signing key of subscriber {}",
+            algorithm, key_algorithm, subscriber.id
+        )));
+    }
+    let public_key_bytes = keys::decode_public_key(public_key, key_algorithm)
+        .map_err(|err| ServiceError::Validation(err.to_string()))?;
+
+    Ok(keys::verify(
+        key_algorithm,
+        &public_key_bytes,
+        message,
+        &signature_bytes,
+    ))
+}
+
+/// Select the key a subscriber signs with at `at`, with its algorithm
+fn signing_key<'a>(
+    subscriber: &'a Subscriber,
+    unique_key_id: Option<&str>,
+    at: DateTime<Utc>,
+) -> Result<(&'a str, SignatureAlgorithm), ServiceError> {
+    if !subscriber.has_keys(KeyPurpose::Signing) {
+        return Ok((&subscriber.public_key, SignatureAlgorithm::Ed25519));
+    }
+
+    let key = match unique_key_id {
+        Some(unique_key_id) => subscriber
+            .key(unique_key_id, KeyPurpose::Signing)
+            .ok_or_else(|| {
+                ServiceError::Validation(format!(
+                    "Subscriber {} has no signing key {}",
+                    subscriber.id, unique_key_id
+                ))
+            })?,
+        None => subscriber
+            .keys
+            .iter()
+            .filter(|key| key.purpose == KeyPurpose::Signing && key.is_valid_at(at))
+            .max_by_key(|key| key.valid_from)
+            .ok_or_else(|| {
+                ServiceError::Validation(format!(
+                    "Subscriber {} has no valid signing key",
+                    subscriber.id
+                ))
+            })?,
+    };
+
+    if !key.is_valid_at(at) {
+        return Err(ServiceError::Validation(format!(
+            "Signing key {} of subscriber {} is not valid at {}",
+            key.unique_key_id, subscriber.id, at
+        )));
+    }
+    Ok((&key.public_key, key.algorithm))
+}
+
+/// Validate a key submitted for registration
+fn validate_key(key: &SubscriberKey) -> Result<(), ServiceError> {
+    if key.unique_key_id.is_empty() {
+        return Err(ServiceError::Validation("Key ID is required".to_string()));
+    }
+    match key.purpose {
+        KeyPurpose::Signing => {
+            keys::decode_public_key(&key.public_key, key.algorithm).map_err(|err| {
+                ServiceError::Validation(format!("Key {}: {}", key.unique_key_id, err))
+            })?;
+        }
+        KeyPurpose::Encryption => {
+            decode_x25519_key(&key.public_key)?;
+        }
+    }
+    if key.valid_until.is_some_and(|until| until <= key.valid_from) {
+        return Err(ServiceError::Validation(format!(
+            "Key {} must be valid until after it becomes valid",
+            key.unique_key_id
+        )));
+    }
+    Ok(())
+}
+
+/// Decode a base64 X25519 public key
+fn decode_x25519_key(key: &str) -> Result<[u8; 32], ServiceError> {
+    BASE64
+        .decode(key)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            ServiceError::Validation(
+                "Encryption public key must be a base64 32 byte X25519 key".to_string(),
+            )
+        })
+}
+
+/// Derive the AES-256-GCM key protecting a challenge from an X25519 shared secret
+fn challenge_key(shared_secret: &[u8]) -> Result<aead::LessSafeKey, ServiceError> {
+    let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, &[]).extract(shared_secret);
+    let okm = prk
+        .expand(&[CHALLENGE_KEY_INFO], &aead::AES_256_GCM)
+        .map_err(|_| ServiceError::Internal("Failed to derive challenge key".to_string()))?;
+    Ok(aead::LessSafeKey::new(aead::UnboundKey::from(okm)))
+}
+
+/// Create a random challenge encrypted to a participant's X25519 key with a
+/// fresh ephemeral key, returning the challenge and the on_subscribe message
+fn encrypt_challenge(
+    subscriber_id: &str,
+    encryption_key: [u8; 32],
+) -> Result<(String, OnSubscribeRequest), ServiceError> {
+    let rng = SystemRandom::new();
+    let random = |len: usize| {
+        let mut bytes = vec![0u8; len];
+        rng.fill(&mut bytes)
+            .map_err(|_| ServiceError::Internal("Failed to generate randomness".to_string()))?;
+        Ok::<_, ServiceError>(bytes)
+    };
+
+    let secret: [u8; 32] = random(32)?.try_into().unwrap_or_default();
+    let ephemeral = StaticSecret::from(secret);
+    let shared_secret = ephemeral.diffie_hellman(&PublicKey::from(encryption_key));
+    let key = challenge_key(shared_secret.as_bytes())?;
+
+    let challenge = BASE64.encode(random(32)?);
+    let nonce = random(aead::NONCE_LEN)?;
+    let mut sealed = challenge.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        aead::Nonce::try_assume_unique_for_key(&nonce)
+            .map_err(|_| ServiceError::Internal("Invalid challenge nonce".to_string()))?,
+        aead::Aad::empty(),
+        &mut sealed,
+    )
+    .map_err(|_| ServiceError::Internal("Failed to encrypt challenge".to_string()))?;
+
+    let request = OnSubscribeRequest {
+        subscriber_id: subscriber_id.to_string(),
+        challenge: BASE64.encode([nonce, sealed].concat()),
+        ephemeral_public_key: BASE64.encode(PublicKey::from(&ephemeral).as_bytes()),
+    };
+    Ok((challenge, request))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::network_registry::KeyRetirement;
+    use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING};
     use crate::storage::memory::MemoryStorage;
+    use crate::auth::header::AUTHORIZATION;
+    use crate::auth::RequestSigner;
+    use crate::config::AppConfig;
+    use crate::routes::configure_routes;
+    use actix_web::{test as actix_test, web, App, HttpResponse, HttpServer};
     use chrono::Utc;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    /// Stub participant answering on_subscribe challenges
+    struct StubParticipant {
+        /// Encryption key the participant subscribed with
+        secret: StaticSecret,
+        /// Whether to answer with a wrong challenge
+        tamper: bool,
+    }
+
+    /// Decrypt an on_subscribe challenge with the participant's secret key
+    fn decrypt_challenge(secret: &StaticSecret, request: &OnSubscribeRequest) -> String {
+        let ephemeral: [u8; 32] = BASE64
+            .decode(&request.ephemeral_public_key)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let shared_secret = secret.diffie_hellman(&PublicKey::from(ephemeral));
+        let key = challenge_key(shared_secret.as_bytes()).unwrap();
+
+        let sealed = BASE64.decode(&request.challenge).unwrap();
+        let (nonce, ciphertext) = sealed.split_at(aead::NONCE_LEN);
+        let mut in_out = ciphertext.to_vec();
+        let challenge = key
+            .open_in_place(
+                aead::Nonce::try_assume_unique_for_key(nonce).unwrap(),
+                aead::Aad::empty(),
+                &mut in_out,
+            )
+            .unwrap();
+        String::from_utf8(challenge.to_vec()).unwrap()
+    }
+
+    async fn stub_on_subscribe(
+        body: web::Json<OnSubscribeRequest>,
+        state: web::Data<StubParticipant>,
+    ) -> HttpResponse {
+        let mut answer = decrypt_challenge(&state.secret, &body);
+        if state.tamper {
+            answer.push('x');
+        }
+        HttpResponse::Ok().json(OnSubscribeResponse { answer })
+    }
+
+    /// Start a stub participant, returning its URL and public encryption key
+    fn start_participant(tamper: bool) -> (String, String) {
+        let secret = StaticSecret::from([7u8; 32]);
+        let public_key = BASE64.encode(PublicKey::from(&secret).as_bytes());
+        let state = web::Data::new(StubParticipant { secret, tamper });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(state.clone())
+                .route("/on_subscribe", web::post().to(stub_on_subscribe))
+        })
+        .listen(listener)
+        .unwrap()
+        .workers(1)
+        .run();
+        actix_rt::spawn(server);
+
+        (format!("http://127.0.0.1:{}", port), public_key)
+    }
+
+    fn subscribe_request(url: &str, encryption_public_key: &str) -> SubscribeRequest {
+        let (signing_public_key, _) = NetworkRegistryService::generate_test_keypair().unwrap();
+        SubscribeRequest {
+            subscriber_id: "hsp-subscriber-1".to_string(),
+            type_field: "HSP".to_string(),
+            domain: "nic2004:85111".to_string(),
+            city: None,
+            country: Some("IND".to_string()),
+            url: url.to_string(),
+            signing_public_key,
+            encryption_public_key: encryption_public_key.to_string(),
+        }
+    }
     
     fn create_test_subscriber() -> Subscriber {
         Subscriber {
@@ -324,8 +1112,11 @@ mod tests {
             city: Some("Test City".to_string()),
             country: Some("Test Country".to_string()),
             url: "https://example.com/api".to_string(),
-            status: "ACTIVE".to_string(),
+            status: ParticipantStatus::Active,
             public_key: "dGVzdC1wdWJsaWMta2V5".to_string(), // base64 for "test-public-key"
+            encryption_public_key: None,
+            keys: Vec::new(),
+            domain_verification: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -334,7 +1125,7 @@ mod tests {
     #[tokio::test]
     async fn test_register_subscriber() {
         let storage = MemoryStorage::empty();
-        let service = NetworkRegistryService::new(storage);
+        let service = NetworkRegistryService::new(storage).unwrap();
         
         let subscriber = create_test_subscriber();
         let result = service.register_subscriber(subscriber.clone()).await;
@@ -347,7 +1138,7 @@ mod tests {
     #[tokio::test]
     async fn test_lookup_subscriber() {
         let storage = MemoryStorage::empty();
-        let service = NetworkRegistryService::new(storage);
+        let service = NetworkRegistryService::new(storage).unwrap();
         
         let subscriber = create_test_subscriber();
         let _ = service.register_subscriber(subscriber.clone()).await;
@@ -374,6 +1165,845 @@ mod tests {
         assert!(!public_key.is_empty());
         assert!(!private_key.is_empty());
     }
+
+    #[actix_rt::test]
+    async fn test_subscribe_after_answered_challenge() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let (url, encryption_key) = start_participant(false);
+
+        let response = service
+            .subscribe(subscribe_request(&url, &encryption_key))
+            .await
+            .unwrap();
+        assert_eq!(response.status, ParticipantStatus::Subscribed);
+
+        let stored = storage.get_subscriber("hsp-subscriber-1").await.unwrap();
+        assert_eq!(stored.status, ParticipantStatus::Subscribed);
+        assert_eq!(stored.encryption_public_key, Some(encryption_key.clone()));
+
+        // Subscribed participants cannot subscribe again
+        let again = service.subscribe(subscribe_request(&url, &encryption_key)).await;
+        assert!(matches!(again, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[actix_rt::test]
+    async fn test_subscribe_with_wrong_answer_stays_initiated() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let (url, encryption_key) = start_participant(true);
+
+        let result = service.subscribe(subscribe_request(&url, &encryption_key)).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let stored = storage.get_subscriber("hsp-subscriber-1").await.unwrap();
+        assert_eq!(stored.status, ParticipantStatus::Initiated);
+        let validation = service
+            .validate_signature("hsp-subscriber-1", None, None, "c2lnbmF0dXJl", b"message")
+            .await;
+        assert!(matches!(validation, Err(ServiceError::Validation(_))));
+
+        // A pending subscription cannot be moved to another URL
+        let moved = service
+            .subscribe(subscribe_request("http://127.0.0.1:1", &encryption_key))
+            .await;
+        assert!(matches!(moved, Err(ServiceError::BusinessLogic(_))));
+
+        // and the keys of a repeated one are only stored once it is answered
+        let retry = service
+            .subscribe(subscribe_request(&url, &encryption_key))
+            .await;
+        assert!(matches!(retry, Err(ServiceError::Validation(_))));
+        let retried = storage.get_subscriber("hsp-subscriber-1").await.unwrap();
+        assert_eq!(retried.public_key, stored.public_key);
+
+        // Unreachable participants stay INITIATED too
+        let mut request = subscribe_request("http://127.0.0.1:1", &encryption_key);
+        request.subscriber_id = "hsp-subscriber-2".to_string();
+        let unreachable = service.subscribe(request).await;
+        assert!(matches!(unreachable, Err(ServiceError::ExternalService(_))));
+        let stored = storage.get_subscriber("hsp-subscriber-2").await.unwrap();
+        assert_eq!(stored.status, ParticipantStatus::Initiated);
+    }
+
+    /// Generate a base64 Ed25519 public key with its key pair
+    fn signing_keypair() -> (String, Ed25519KeyPair) {
+        let (public_key, private_key) = NetworkRegistryService::generate_test_keypair().unwrap();
+        let pkcs8 = BASE64.decode(private_key).unwrap();
+        (public_key, Ed25519KeyPair::from_pkcs8(&pkcs8).unwrap())
+    }
+
+    fn add_key_request(unique_key_id: &str, public_key: &str) -> AddKeyRequest {
+        AddKeyRequest {
+            unique_key_id: unique_key_id.to_string(),
+            purpose: KeyPurpose::Signing,
+            public_key: public_key.to_string(),
+            algorithm: SignatureAlgorithm::Ed25519,
+            valid_from: None,
+            valid_until: None,
+            retire: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_with_retirement() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let (first_key, first) = signing_keypair();
+        let (second_key, second) = signing_keypair();
+        let (third_key, _) = signing_keypair();
+
+        let mut subscriber = create_test_subscriber();
+        subscriber.public_key = first_key;
+        storage.register_subscriber(subscriber.clone()).await.unwrap();
+
+        let sign = |key_pair: &Ed25519KeyPair| BASE64.encode(key_pair.sign(b"message").as_ref());
+        let validate = |unique_key_id: Option<&'static str>, signature: String| {
+            let service = &service;
+            let id = subscriber.id.clone();
+            async move {
+                service
+                    .validate_signature(&id, unique_key_id, None, &signature, b"message")
+                    .await
+            }
+        };
+
+        // Before registering keys, the public key verifies any key ID
+        assert!(validate(Some("key-1"), sign(&first)).await.unwrap());
+
+        // The first signing key must retire the key in use
+        let result = service
+            .add_key(&subscriber.id, add_key_request("key-2", &second_key))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let mut request = add_key_request("key-2", &second_key);
+        request.retire = Some(KeyRetirement {
+            unique_key_id: "key-1".to_string(),
+            at: Utc::now() + chrono::Duration::hours(1),
+        });
+        let updated = service.add_key(&subscriber.id, request).await.unwrap();
+        assert_eq!(updated.keys.len(), 2);
+
+        // Both keys are valid until the old one is retired
+        assert!(validate(Some("key-1"), sign(&first)).await.unwrap());
+        assert!(validate(Some("key-2"), sign(&second)).await.unwrap());
+        assert!(!validate(Some("key-2"), sign(&first)).await.unwrap());
+        let unknown = validate(Some("key-3"), sign(&first)).await;
+        assert!(matches!(unknown, Err(ServiceError::Validation(_))));
+
+        // Retiring the old key early stops it from verifying
+        let mut request = add_key_request("key-3", &third_key);
+        request.valid_from = Some(Utc::now() - chrono::Duration::minutes(10));
+        request.retire = Some(KeyRetirement {
+            unique_key_id: "key-1".to_string(),
+            at: Utc::now() - chrono::Duration::minutes(1),
+        });
+        service.add_key(&subscriber.id, request).await.unwrap();
+        let retired = validate(Some("key-1"), sign(&first)).await;
+        assert!(matches!(retired, Err(ServiceError::Validation(_))));
+
+        // Without a key ID the most recent valid key is used
+        assert!(validate(None, sign(&second)).await.unwrap());
+
+        let duplicate = service
+            .add_key(&subscriber.id, add_key_request("key-2", &third_key))
+            .await;
+        assert!(matches!(duplicate, Err(ServiceError::BusinessLogic(_))));
+
+        let mut encryption = add_key_request("enc-1", &third_key);
+        encryption.purpose = KeyPurpose::Encryption;
+        let updated = service.add_key(&subscriber.id, encryption).await.unwrap();
+        assert!(updated.has_keys(KeyPurpose::Encryption));
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_algorithms() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let (ed25519_key, _) = signing_keypair();
+        let subscriber = create_test_subscriber();
+        storage.register_subscriber(subscriber.clone()).await.unwrap();
+
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+        let ecdsa =
+            EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                .unwrap();
+        let mut request = add_key_request("key-2", &BASE64.encode(ecdsa.public_key().as_ref()));
+        request.algorithm = SignatureAlgorithm::EcdsaP256Sha256;
+        request.retire = Some(KeyRetirement {
+            unique_key_id: "key-1".to_string(),
+            at: Utc::now() + chrono::Duration::minutes(1),
+        });
+        service.add_key(&subscriber.id, request).await.unwrap();
+
+        let signature = BASE64.encode(ecdsa.sign(&rng, b"message").unwrap().as_ref());
+        let valid = service
+            .validate_signature(
+                &subscriber.id,
+                Some("key-2"),
+                Some(SignatureAlgorithm::EcdsaP256Sha256),
+                &signature,
+                b"message",
+            )
+            .await;
+        assert!(valid.unwrap());
+
+        // Signatures claiming another algorithm than the key's are rejected
+        let mismatch = service
+            .validate_signature(
+                &subscriber.id,
+                Some("key-2"),
+                Some(SignatureAlgorithm::Ed25519),
+                &signature,
+                b"message",
+            )
+            .await;
+        assert!(matches!(mismatch, Err(ServiceError::Validation(_))));
+
+        // Keys that are not valid for their algorithm are not registered
+        let mut request = add_key_request("key-3", &ed25519_key);
+        request.algorithm = SignatureAlgorithm::RsaPssSha256;
+        let result = service.add_key(&subscriber.id, request).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_rejects_invalid_encryption_key() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+
+        let result = service
+            .subscribe(subscribe_request("http://127.0.0.1:1", "dG9vLXNob3J0"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert!(storage.get_subscriber("hsp-subscriber-1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_participant_lifecycle() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let (public_key, keypair) = signing_keypair();
+        let subscriber = Subscriber {
+            status: ParticipantStatus::Subscribed,
+            public_key,
+            domain_verification: Some(DomainVerification {
+                request_id: "request-1".to_string(),
+                host: "example.com".to_string(),
+                issued_at: Utc::now(),
+                verified_at: Some(Utc::now()),
+                method: Some(DomainVerificationMethod::DnsTxt),
+            }),
+            ..create_test_subscriber()
+        };
+        storage
+            .register_subscriber(subscriber.clone())
+            .await
+            .unwrap();
+        let signature = BASE64.encode(keypair.sign(b"message").as_ref());
+        let lookup = || NetworkRegistryLookup {
+            type_field: subscriber.type_field.clone(),
+            domain: subscriber.domain.clone(),
+            city: None,
+            country: None,
+        };
+
+        // A reason is required and actions must apply to the current status
+        let result = service.activate(&subscriber.id, " ", None).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let result = service
+            .reinstate(&subscriber.id, "Appeal upheld", None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let active = service
+            .activate(
+                &subscriber.id,
+                "Onboarding complete",
+                Some("registry-admin"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(active.status, ParticipantStatus::Active);
+
+        // Suspended participants cannot sign requests and are not looked up
+        let suspended = service
+            .suspend(
+                &subscriber.id,
+                "Unresolved grievances",
+                Some("registry-admin"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(suspended.status, ParticipantStatus::Suspended);
+        let result = service
+            .validate_signature(&subscriber.id, None, None, &signature, b"message")
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let result = service.lookup_subscriber(lookup()).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+
+        service
+            .reinstate(
+                &subscriber.id,
+                "Grievances resolved",
+                Some("registry-admin"),
+            )
+            .await
+            .unwrap();
+        let valid = service
+            .validate_signature(&subscriber.id, None, None, &signature, b"message")
+            .await;
+        assert!(valid.unwrap());
+        assert!(service.lookup_subscriber(lookup()).await.is_ok());
+
+        let deregistered = service
+            .deregister(&subscriber.id, "Left the network", None)
+            .await
+            .unwrap();
+        assert_eq!(deregistered.status, ParticipantStatus::Inactive);
+        let result = service.activate(&subscriber.id, "Rejoined", None).await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let history = service.status_history(&subscriber.id).await.unwrap();
+        let transitions: Vec<_> = history
+            .iter()
+            .map(|change| (change.from, change.to))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                (ParticipantStatus::Subscribed, ParticipantStatus::Active),
+                (ParticipantStatus::Active, ParticipantStatus::Suspended),
+                (ParticipantStatus::Suspended, ParticipantStatus::Active),
+                (ParticipantStatus::Active, ParticipantStatus::Inactive),
+            ]
+        );
+        assert_eq!(history[1].reason, "Unresolved grievances");
+        assert_eq!(history[1].changed_by.as_deref(), Some("registry-admin"));
+        assert_eq!(history[3].changed_by, None);
+    }
+
+    /// Stub DNS resolver serving TXT records set by the test
+    #[derive(Default)]
+    struct StubResolver {
+        records: Mutex<HashMap<String, Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TxtResolver for StubResolver {
+        async fn txt_records(&self, name: &str) -> Result<Vec<String>, ServiceError> {
+            Ok(self
+                .records
+                .lock()
+                .unwrap()
+                .get(name)
+                .cloned()
+                .unwrap_or_default())
+        }
+    }
+
+    async fn stub_site_verification(content: web::Data<Mutex<String>>) -> HttpResponse {
+        let content = content.lock().unwrap().clone();
+        if content.is_empty() {
+            return HttpResponse::NotFound().finish();
+        }
+        HttpResponse::Ok().content_type("text/html").body(content)
+    }
+
+    /// Start a stub participant site serving the given site verification
+    /// file, returning its URL
+    fn start_site(content: web::Data<Mutex<String>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = HttpServer::new(move || {
+            App::new().app_data(content.clone()).route(
+                SITE_VERIFICATION_PATH,
+                web::get().to(stub_site_verification),
+            )
+        })
+        .listen(listener)
+        .unwrap()
+        .workers(1)
+        .run();
+        actix_rt::spawn(server);
+
+        format!("http://127.0.0.1:{}/api/v1", port)
+    }
+
+    fn site_verification_file(signature: &str) -> String {
+        format!(
+            "<html><head><meta name=\"uhi-site-verification\" content=\"{}\" /></head></html>",
+            signature
+        )
+    }
+
+    #[actix_rt::test]
+    async fn test_domain_verification_with_site_file() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage.clone()).unwrap();
+        let content = web::Data::new(Mutex::new(String::new()));
+        let (public_key, keypair) = signing_keypair();
+        let subscriber = Subscriber {
+            status: ParticipantStatus::Subscribed,
+            url: start_site(content.clone()),
+            public_key,
+            ..create_test_subscriber()
+        };
+        service
+            .register_subscriber(subscriber.clone())
+            .await
+            .unwrap();
+
+        let result = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::SiteVerification)
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let verification = service
+            .issue_domain_verification(&subscriber.id)
+            .await
+            .unwrap();
+        assert_eq!(verification.host, "127.0.0.1");
+
+        // Participants cannot be activated before their domain is verified
+        let result = service
+            .activate(&subscriber.id, "Onboarding complete", None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        // A missing file or a signature of anything but the request ID fails
+        let result = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::SiteVerification)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let forged = BASE64.encode(keypair.sign(b"another-request").as_ref());
+        *content.lock().unwrap() = site_verification_file(&forged);
+        let result = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::SiteVerification)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let signature = BASE64.encode(keypair.sign(verification.request_id.as_bytes()).as_ref());
+        *content.lock().unwrap() = site_verification_file(&signature);
+        let verified = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::SiteVerification)
+            .await
+            .unwrap();
+        assert!(verified.is_domain_verified());
+        assert_eq!(
+            verified.domain_verification.unwrap().method,
+            Some(DomainVerificationMethod::SiteVerification)
+        );
+
+        // Until activated, the participant may not sign requests
+        let message = b"signed request";
+        let signature = BASE64.encode(keypair.sign(message).as_ref());
+        let result = service
+            .validate_signature(&subscriber.id, None, None, &signature, message)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let active = service
+            .activate(&subscriber.id, "Onboarding complete", None)
+            .await
+            .unwrap();
+        assert_eq!(active.status, ParticipantStatus::Active);
+        assert!(service
+            .validate_signature(&subscriber.id, None, None, &signature, message)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_domain_verification_with_dns_txt() {
+        let storage = MemoryStorage::empty();
+        let resolver = Arc::new(StubResolver::default());
+        let service =
+            NetworkRegistryService::with_resolver(storage.clone(), resolver.clone()).unwrap();
+        let (public_key, keypair) = signing_keypair();
+        let subscriber = Subscriber {
+            public_key,
+            ..create_test_subscriber()
+        };
+        storage
+            .register_subscriber(subscriber.clone())
+            .await
+            .unwrap();
+
+        let verification = service
+            .issue_domain_verification(&subscriber.id)
+            .await
+            .unwrap();
+        let record = txt_record_name("example.com");
+        assert_eq!(record, "_uhi-site-verification.example.com");
+
+        // Records signed with another key do not verify
+        let (_, other_keypair) = signing_keypair();
+        let request_id = verification.request_id.as_bytes();
+        let forged = BASE64.encode(other_keypair.sign(request_id).as_ref());
+        resolver
+            .records
+            .lock()
+            .unwrap()
+            .insert(record.clone(), vec!["v=spf1 -all".to_string(), forged]);
+        let result = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::DnsTxt)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert!(!service
+            .get_subscriber(&subscriber.id)
+            .await
+            .unwrap()
+            .is_domain_verified());
+
+        let signature = BASE64.encode(keypair.sign(request_id).as_ref());
+        resolver
+            .records
+            .lock()
+            .unwrap()
+            .get_mut(&record)
+            .unwrap()
+            .push(signature);
+        let verified = service
+            .verify_domain(&subscriber.id, DomainVerificationMethod::DnsTxt)
+            .await
+            .unwrap();
+        assert!(verified.is_domain_verified());
+    }
     
-    // Additional tests would be added for signature validation, domain verification, etc.
+    fn seed_subscriber(subscriber_id: &str, signing_public_key: &str) -> SeedSubscriber {
+        SeedSubscriber {
+            subscriber_id: subscriber_id.to_string(),
+            type_field: "GATEWAY".to_string(),
+            domain: "nic2004:85111".to_string(),
+            url: format!("https://{}.example.com/api/v1", subscriber_id),
+            signing_public_key: signing_public_key.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seed_registers_active_participants() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage).unwrap();
+        let (public_key, keypair) = signing_keypair();
+        let seeds = vec![seed_subscriber("registry-admin", &public_key)];
+
+        service.seed(&seeds).await.unwrap();
+        let admin = service.get_subscriber("registry-admin").await.unwrap();
+        assert_eq!(admin.status, ParticipantStatus::Active);
+        let history = service.status_history("registry-admin").await.unwrap();
+        assert_eq!(history.len(), 1);
+
+        let message = b"signed request";
+        let signature = BASE64.encode(keypair.sign(message).as_ref());
+        assert!(service
+            .validate_signature("registry-admin", None, None, &signature, message)
+            .await
+            .unwrap());
+
+        // Seeding again leaves a suspended seed suspended
+        service
+            .suspend("registry-admin", "Key compromised", None)
+            .await
+            .unwrap();
+        service.seed(&seeds).await.unwrap();
+        let admin = service.get_subscriber("registry-admin").await.unwrap();
+        assert_eq!(admin.status, ParticipantStatus::Suspended);
+
+        // A seed cannot replace the key of a registered subscriber
+        let (other_public_key, _) = signing_keypair();
+        let result = service
+            .seed(&[seed_subscriber("registry-admin", &other_public_key)])
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_lookup_participants_matches_domain_exactly() {
+        let storage = MemoryStorage::empty();
+        let service = NetworkRegistryService::new(storage).unwrap();
+        service
+            .register_subscriber(create_test_subscriber())
+            .await
+            .unwrap();
+        let lookup = |domain: &str| LookupRequest {
+            subscriber_id: None,
+            domain: Some(domain.to_string()),
+            participant_type: None,
+        };
+
+        let response = service
+            .lookup_participants(lookup("example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.participants.len(), 1);
+        for domain in ["example", "ample.com", ""] {
+            let response = service.lookup_participants(lookup(domain)).await.unwrap();
+            assert!(response.participants.is_empty(), "matched {:?}", domain);
+        }
+    }
+
+    /// Application config verifying signatures, with `registry-admin` seeded
+    /// as the registry admin signing with the given key
+    fn registry_config(admin_public_key: &str) -> AppConfig {
+        let toml = format!(
+            r#"
+            [server]
+            host = "127.0.0.1"
+            port = 8080
+            workers = 1
+
+            [database]
+            url = "postgres://localhost/uhi_gateway"
+            max_connections = 1
+            min_connections = 1
+
+            [logging]
+            level = "info"
+            format = "json"
+
+            [registry]
+            admins = ["registry-admin"]
+
+            [[registry.seed]]
+            subscriber_id = "registry-admin"
+            type = "GATEWAY"
+            domain = "nic2004:85111"
+            url = "https://registry-admin.example.com/api/v1"
+            signing_public_key = "{}"
+            "#,
+            admin_public_key
+        );
+        ::config::Config::builder()
+            .add_source(::config::File::from_str(&toml, ::config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    /// Signing key of a participant, with a signer of its requests
+    fn participant_signer(subscriber_id: &str) -> (String, Ed25519KeyPair, RequestSigner) {
+        let (public_key, private_key) = NetworkRegistryService::generate_test_keypair().unwrap();
+        let pkcs8 = BASE64.decode(private_key).unwrap();
+        let signer = RequestSigner::new(
+            subscriber_id.to_string(),
+            "key-1".to_string(),
+            Ed25519KeyPair::from_pkcs8(&pkcs8).unwrap(),
+            300,
+        );
+        (
+            public_key,
+            Ed25519KeyPair::from_pkcs8(&pkcs8).unwrap(),
+            signer,
+        )
+    }
+
+    /// Registry request with a JSON body signed by the given signer
+    fn signed_request(uri: &str, body: &str, signer: &RequestSigner) -> actix_test::TestRequest {
+        let authorization = signer.sign(body.as_bytes()).unwrap().to_string();
+        actix_test::TestRequest::post()
+            .uri(uri)
+            .insert_header(("Content-Type", "application/json"))
+            .insert_header((AUTHORIZATION, authorization))
+            .set_payload(body.to_string())
+    }
+
+    #[actix_rt::test]
+    async fn test_onboarding_through_signed_endpoints() {
+        let storage = MemoryStorage::empty();
+        let resolver = Arc::new(StubResolver::default());
+        let service = NetworkRegistryService::with_resolver(storage, resolver.clone()).unwrap();
+        let (admin_public_key, _, admin) = participant_signer("registry-admin");
+        let config = registry_config(&admin_public_key);
+        service.seed(&config.registry.seed).await.unwrap();
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .app_data(web::Data::new(service))
+                .configure(configure_routes),
+        )
+        .await;
+
+        // Subscribing is unsigned and leaves the participant SUBSCRIBED
+        let (url, encryption_public_key) = start_participant(false);
+        let (public_key, keypair, participant) = participant_signer("hsp-subscriber-1");
+        let request = SubscribeRequest {
+            signing_public_key: public_key,
+            ..subscribe_request(&url, &encryption_public_key)
+        };
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri("/api/v1/networkregistry/subscribe")
+                .set_json(&request)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+        let registration: RegistrationResponse = actix_test::read_body_json(response).await;
+        assert_eq!(registration.status, ParticipantStatus::Subscribed);
+
+        // The participant may not sign protocol requests yet
+        let search = signed_request("/api/v1/search", "{}", &participant);
+        let response = actix_test::call_service(&app, search.to_request()).await;
+        assert_eq!(response.status(), 401);
+
+        // It verifies its domain with its own signature while SUBSCRIBED
+        let issue = signed_request(
+            "/api/v1/networkregistry/subscribers/hsp-subscriber-1/domain-verification",
+            "",
+            &participant,
+        );
+        let response = actix_test::call_service(&app, issue.to_request()).await;
+        assert_eq!(response.status(), 200);
+        let verification: DomainVerification = actix_test::read_body_json(response).await;
+
+        let signature = BASE64.encode(keypair.sign(verification.request_id.as_bytes()).as_ref());
+        resolver
+            .records
+            .lock()
+            .unwrap()
+            .insert(txt_record_name(&verification.host), vec![signature]);
+        let verify = signed_request(
+            "/api/v1/networkregistry/subscribers/hsp-subscriber-1/domain-verification/verify",
+            r#"{"method":"DNS_TXT"}"#,
+            &participant,
+        );
+        let response = actix_test::call_service(&app, verify.to_request()).await;
+        assert_eq!(response.status(), 200);
+        let verified: Subscriber = actix_test::read_body_json(response).await;
+        assert!(verified.is_domain_verified());
+
+        // Only the seeded admin, not the participant, activates it
+        let activate_uri = "/api/v1/networkregistry/admin/subscribers/hsp-subscriber-1/activate";
+        let body = r#"{"reason":"Onboarding complete"}"#;
+        let response = actix_test::call_service(
+            &app,
+            signed_request(activate_uri, body, &participant).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 401);
+        let response = actix_test::call_service(
+            &app,
+            signed_request(activate_uri, body, &admin).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+        let active: Subscriber = actix_test::read_body_json(response).await;
+        assert_eq!(active.status, ParticipantStatus::Active);
+
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post()
+                .uri("/api/v1/networkregistry/lookup")
+                .set_json(serde_json::json!({ "subscriber_id": "hsp-subscriber-1" }))
+                .to_request(),
+        )
+        .await;
+        let lookup: LookupResponse = actix_test::read_body_json(response).await;
+        assert_eq!(lookup.participants.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_domain_verification_routes() {
+        let storage = MemoryStorage::empty();
+        let resolver = Arc::new(StubResolver::default());
+        let service =
+            NetworkRegistryService::with_resolver(storage.clone(), resolver.clone()).unwrap();
+        let (public_key, keypair, participant) = participant_signer("hsp-subscriber-1");
+        let (other_public_key, _, other) = participant_signer("hsp-subscriber-2");
+        for (id, public_key, status) in [
+            (
+                "hsp-subscriber-1",
+                public_key,
+                ParticipantStatus::Subscribed,
+            ),
+            (
+                "hsp-subscriber-2",
+                other_public_key,
+                ParticipantStatus::Active,
+            ),
+        ] {
+            storage
+                .register_subscriber(Subscriber {
+                    id: id.to_string(),
+                    url: format!("https://{}.example.com/api/v1", id),
+                    status,
+                    public_key,
+                    ..create_test_subscriber()
+                })
+                .await
+                .unwrap();
+        }
+        let (admin_public_key, _, _) = participant_signer("registry-admin");
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(web::Data::new(registry_config(&admin_public_key)))
+                .app_data(web::Data::new(service))
+                .configure(configure_routes),
+        )
+        .await;
+        let issue_uri = "/api/v1/networkregistry/subscribers/hsp-subscriber-1/domain-verification";
+        let verify_uri =
+            "/api/v1/networkregistry/subscribers/hsp-subscriber-1/domain-verification/verify";
+        let verify_body = r#"{"method":"DNS_TXT"}"#;
+
+        // Requests must be signed, and by the subscriber itself
+        let response = actix_test::call_service(
+            &app,
+            actix_test::TestRequest::post().uri(issue_uri).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 401);
+        let response =
+            actix_test::call_service(&app, signed_request(issue_uri, "", &other).to_request())
+                .await;
+        assert_eq!(response.status(), 403);
+
+        let response = actix_test::call_service(
+            &app,
+            signed_request(issue_uri, "", &participant).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+        let verification: DomainVerification = actix_test::read_body_json(response).await;
+        assert_eq!(verification.host, "hsp-subscriber-1.example.com");
+        assert_eq!(verification.verified_at, None);
+
+        // Nothing is published yet
+        let response = actix_test::call_service(
+            &app,
+            signed_request(verify_uri, verify_body, &participant).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 400);
+
+        let signature = BASE64.encode(keypair.sign(verification.request_id.as_bytes()).as_ref());
+        resolver.records.lock().unwrap().insert(
+            "_uhi-site-verification.hsp-subscriber-1.example.com".to_string(),
+            vec![signature],
+        );
+        let response = actix_test::call_service(
+            &app,
+            signed_request(verify_uri, verify_body, &other).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 403);
+        let response = actix_test::call_service(
+            &app,
+            signed_request(verify_uri, verify_body, &participant).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), 200);
+        let verified: Subscriber = actix_test::read_body_json(response).await;
+        assert_eq!(verified.status, ParticipantStatus::Subscribed);
+        assert_eq!(
+            verified.domain_verification.unwrap().method,
+            Some(DomainVerificationMethod::DnsTxt)
+        );
+    }
 }
@@ -1,9 +1,26 @@
+use super::catalog::CatalogService;
 use super::error::ServiceError;
 use super::fulfillment::FulfillmentService;
-use crate::models::order::{Order, OrderStatus};
-use crate::storage::Storage;
+use crate::config::{CancellationConfig, FulfillmentConfig};
+use crate::models::catalog::{Price, SelectRequest, SelectedItem};
+use crate::models::fulfillment::{Fulfillment, FulfillmentState, State, TimeSlot};
+use crate::models::history::{EventEntity, EventOrigin, OrderEvent, OrderTimeline};
+use crate::models::order::{
+    Cancellation, CancellationReason, Order, OrderActor, OrderChange, OrderState, OrderStatus,
+    OrderTransition, OrderUpdate,
+};
+use crate::models::payment::{Payment, Refund};
+use crate::models::provider::SupportContact;
+use crate::models::tracking::Tracking;
+use crate::storage::{Storage, StorageError};
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
+use uuid::Uuid;
+
+/// Payment type used when a provider's catalog lists no accepted payments
+const DEFAULT_PAYMENT_TYPE: &str = "ON-ORDER";
 
 /// Order service for managing healthcare service bookings
 pub struct OrderService {
@@ -11,14 +28,38 @@ pub struct OrderService {
     storage: Arc<dyn Storage>,
     /// Fulfillment service for managing fulfillment details
     fulfillment_service: FulfillmentService,
+    /// Catalog service for validating and quoting ordered items
+    catalog_service: CatalogService,
+    /// Cancellation policies of providers
+    cancellation: CancellationConfig,
 }
 
 impl OrderService {
     /// Create a new order service with storage dependency
     pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self::with_cancellation_config(storage, CancellationConfig::default())
+    }
+
+    /// Create a new order service applying the given cancellation policies
+    pub fn with_cancellation_config(
+        storage: Arc<dyn Storage>,
+        cancellation: CancellationConfig,
+    ) -> Self {
+        Self::with_config(storage, cancellation, FulfillmentConfig::default())
+    }
+
+    /// Create a new order service applying the given cancellation policies
+    /// and fulfillment lifecycles
+    pub fn with_config(
+        storage: Arc<dyn Storage>,
+        cancellation: CancellationConfig,
+        fulfillment: FulfillmentConfig,
+    ) -> Self {
         Self {
-            fulfillment_service: FulfillmentService::new(storage.clone()),
+            fulfillment_service: FulfillmentService::with_config(storage.clone(), fulfillment),
+            catalog_service: CatalogService::new(storage.clone()),
             storage,
+            cancellation,
         }
     }
 
@@ -40,250 +81,1340 @@ impl OrderService {
         Ok(updated)
     }
 
-    /// Initialize an order (init)
-    pub async fn init(&self, order: Order) -> Result<Order, ServiceError> {
-        // Business logic for order initialization
-        // For simplified implementation, just create the order in storage
-        let mut order_with_state = order;
-        order_with_state.state = "INITIALIZED".to_string();
+    /// Initialize a draft order (init) from the selected items, billing and
+    /// fulfillment.
+    ///
+    /// Items are re-validated against the provider's current catalog, so
+    /// prices always come from the catalog rather than the consumer.
+    pub async fn init(&self, order: Order, origin: &EventOrigin) -> Result<Order, ServiceError> {
+        if order.items.is_empty() {
+            return Err(ServiceError::Validation(
+                "Order must contain at least one item".to_string(),
+            ));
+        }
 
-        let created = self.storage.create_order(order_with_state).await?;
-        Ok(created)
+        if !order.fulfillment.provider_id.is_empty()
+            && order.fulfillment.provider_id != order.provider.id
+        {
+            return Err(ServiceError::Validation(format!(
+                "Fulfillment provider {} does not match order provider {}",
+                order.fulfillment.provider_id, order.provider.id
+            )));
+        }
+
+        let items = self
+            .catalog_service
+            .resolve_items(&order.provider.id, &order.items)
+            .await?;
+
+        // Draft orders carry no quote or payment until on_init
+        let mut draft = order;
+        draft.consumer_id = origin.subscriber_id.clone();
+        draft.items = items;
+        draft.quote = None;
+        draft.payment = None;
+        draft.state = OrderState::Initialized;
+        draft.transitions = Vec::new();
+
+        self.save_order(None, draft, origin).await
+    }
+
+    /// Quote a draft order from the provider's catalog and attach the quote
+    /// and payment terms through `on_init`
+    pub async fn quote(&self, order_id: &str, origin: &EventOrigin) -> Result<Order, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+
+        let quote = self
+            .catalog_service
+            .quote(&order.provider.id, &order.items)
+            .await?;
+
+        // Payment is due up front in the first payment type the provider accepts
+        let catalog = self.catalog_service.get_catalog(&order.provider.id).await?;
+        let payment = Payment {
+            uri: String::new(),
+            tl_method: None,
+            params: None,
+            payment_type: catalog
+                .payments
+                .first()
+                .cloned()
+                .unwrap_or_else(|| DEFAULT_PAYMENT_TYPE.to_string()),
+            status: "DUE".to_string(),
+            time: None,
+            currency: Some(quote.price.currency.clone()),
+            amount: Some(quote.price.clone()),
+            refund: None,
+        };
+
+        let provider_id = order.provider.id.clone();
+        let provider_order = Order {
+            quote: Some(quote),
+            payment: Some(payment),
+            ..order
+        };
+        self.on_init(order_id, &provider_id, provider_order, origin)
+            .await
     }
 
-    /// Handle provider's response to order initialization (on_init)
+    /// Handle provider's response to order initialization (on_init),
+    /// attaching the provider's quote and payment terms to the draft order.
+    /// Only the order's provider may quote it.
     pub async fn on_init(
         &self,
         order_id: &str,
+        provider_id: &str,
         provider_order: Order,
+        origin: &EventOrigin,
     ) -> Result<Order, ServiceError> {
         // Get the existing order
         let existing_order = self.storage.get_order(order_id).await?;
 
-        // Validate provider ID matches
-        if existing_order.provider.id != provider_order.provider.id {
+        if existing_order.provider.id != provider_id {
             return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
         }
 
+        if existing_order.state != OrderState::Initialized {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and can no longer be quoted",
+                order_id, existing_order.state
+            )));
+        }
+
+        let mut quote = provider_order.quote.ok_or_else(|| {
+            ServiceError::Validation("on_init must carry the provider's quote".to_string())
+        })?;
+
+        // Quotes without an explicit expiry are valid for their ttl from now
+        if quote.valid_until.is_none() {
+            let ttl = quote.ttl_duration().ok_or_else(|| {
+                ServiceError::Validation(format!("Invalid quote ttl '{}'", quote.ttl))
+            })?;
+            quote.valid_until = Some(Utc::now() + ttl);
+        }
+
         // Update with provider's order information
-        let mut updated_order = existing_order;
-        updated_order.quote = provider_order.quote;
+        let mut updated_order = existing_order.clone();
+        updated_order.quote = Some(quote);
         updated_order.payment = provider_order.payment;
-        updated_order.state = "QUOTED".to_string();
+        Self::transition(
+            &mut updated_order,
+            OrderState::Quoted,
+            OrderActor::Provider,
+            "Quoted by provider",
+        )?;
 
-        let updated = self.storage.update_order(updated_order).await?;
-        Ok(updated)
+        self.save_order(Some(&existing_order), updated_order, origin)
+            .await
     }
 
-    /// Confirm an order
-    pub async fn confirm(&self, order_id: &str) -> Result<Order, ServiceError> {
-        // Get the existing order
-        let mut order = self.storage.get_order(order_id).await?;
+    /// Confirm a quoted order (confirm).
+    ///
+    /// The order must be QUOTED with an unexpired quote, and `payment` must
+    /// satisfy the payment terms from on_init. The fulfillment slot is booked
+    /// through the fulfillment service, which rejects overlapping bookings.
+    /// Only the consumer that initialized the order may confirm it.
+    pub async fn confirm(
+        &self,
+        order_id: &str,
+        payment: Payment,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let existing_order = self.storage.get_order(order_id).await?;
+        Self::check_consumer(&existing_order, origin)?;
+        let mut order = existing_order.clone();
 
-        // Update state to CONFIRMED
-        order.state = "CONFIRMED".to_string();
+        if order.state != OrderState::Quoted {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and cannot be confirmed",
+                order_id, order.state
+            )));
+        }
 
-        let updated = self.storage.update_order(order).await?;
-        Ok(updated)
+        let quote = order
+            .quote
+            .as_ref()
+            .ok_or_else(|| ServiceError::Validation(format!("Order {} has no quote", order_id)))?;
+        if quote
+            .valid_until
+            .is_none_or(|valid_until| valid_until <= Utc::now())
+        {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Quote for order {} has expired",
+                order_id
+            )));
+        }
+
+        Self::check_payment(&order, &payment)?;
+        order.payment = Some(payment);
+        Self::transition(
+            &mut order,
+            OrderState::Confirmed,
+            OrderActor::Consumer,
+            "Confirmed with payment",
+        )?;
+
+        self.book_fulfillment(&mut order).await?;
+        self.save_booked(&existing_order, order, origin).await
+    }
+
+    /// Book the slot requested for an order being confirmed
+    async fn book_fulfillment(&self, order: &mut Order) -> Result<(), ServiceError> {
+        let mut fulfillment = order.fulfillment.clone();
+        if fulfillment.id.is_empty() {
+            fulfillment.id = Uuid::new_v4().to_string();
+        }
+        if fulfillment.provider_id.is_empty() {
+            fulfillment.provider_id = order.provider.id.clone();
+        }
+        fulfillment.state = Some(State {
+            descriptor: FulfillmentState::Scheduled,
+            updated_at: Utc::now(),
+        });
+        order.fulfillment = self
+            .fulfillment_service
+            .create_fulfillment(fulfillment)
+            .await?;
+        Ok(())
+    }
+
+    /// Save an order whose fulfillment was just booked, releasing the slot
+    /// unless the order was stored before the failure, e.g. while recording
+    /// its events
+    async fn save_booked(
+        &self,
+        existing_order: &Order,
+        order: Order,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let fulfillment_id = order.fulfillment.id.clone();
+        let saved = self.save_order(Some(existing_order), order, origin).await;
+
+        if saved.is_err() {
+            let stored = self.storage.get_order(&existing_order.id).await;
+            if !stored.is_ok_and(|stored| stored.fulfillment.id == fulfillment_id) {
+                self.release_fulfillment(&fulfillment_id, &existing_order.id)
+                    .await;
+            }
+        }
+        saved
+    }
+
+    /// Delete a fulfillment booked for an order that was not confirmed, so
+    /// that its slot can be booked again
+    async fn release_fulfillment(&self, fulfillment_id: &str, order_id: &str) {
+        if let Err(err) = self.storage.delete_fulfillment(fulfillment_id).await {
+            tracing::error!(
+                "Failed to release fulfillment {} of order {}: {}",
+                fulfillment_id,
+                order_id,
+                err
+            );
+        }
+    }
+
+    /// Check a consumer's payment against the payment terms of a quoted order
+    fn check_payment(order: &Order, payment: &Payment) -> Result<(), ServiceError> {
+        let terms = order.payment.as_ref().ok_or_else(|| {
+            ServiceError::Validation(format!("Order {} has no payment terms", order.id))
+        })?;
+
+        if payment.payment_type != terms.payment_type {
+            return Err(ServiceError::Validation(format!(
+                "Payment type {} does not match the agreed {}",
+                payment.payment_type, terms.payment_type
+            )));
+        }
+
+        if payment.status == "FAILED" {
+            return Err(ServiceError::BusinessLogic(
+                "Payment has failed".to_string(),
+            ));
+        }
+
+        // Orders paid up front must be settled before they are confirmed
+        if payment.payment_type == "ON-ORDER" && payment.status != "PAID" {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Payment must be PAID for {} orders, got {}",
+                payment.payment_type, payment.status
+            )));
+        }
+
+        if let (Some(amount), Some(quote)) = (&payment.amount, &order.quote) {
+            if amount.currency != quote.price.currency || amount.value != quote.price.value {
+                return Err(ServiceError::Validation(format!(
+                    "Payment of {} {} does not match the quoted {} {}",
+                    amount.currency, amount.value, quote.price.currency, quote.price.value
+                )));
+            }
+        }
+
+        Ok(())
     }
 
-    /// Handle provider's confirmation response
+    /// Handle provider's confirmation response (on_confirm), reconciling the
+    /// provider's final order with the one confirmed by the consumer. Only
+    /// the order's provider may confirm it.
     pub async fn on_confirm(
         &self,
         order_id: &str,
+        provider_id: &str,
         provider_order: Order,
+        origin: &EventOrigin,
     ) -> Result<Order, ServiceError> {
         // Get the existing order
         let existing_order = self.storage.get_order(order_id).await?;
 
-        // Validate provider ID matches
-        if existing_order.provider.id != provider_order.provider.id {
+        if existing_order.provider.id != provider_id {
             return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
         }
 
-        // Update with provider's confirmation information
-        let mut updated_order = existing_order;
-        updated_order.state = provider_order.state;
+        if !matches!(
+            existing_order.state,
+            OrderState::Quoted | OrderState::Confirmed
+        ) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and cannot be confirmed",
+                order_id, existing_order.state
+            )));
+        }
+
+        // The provider may not change what was ordered
+        Self::check_items_match(&existing_order, &provider_order)?;
 
-        let updated = self.storage.update_order(updated_order).await?;
-        Ok(updated)
+        // Adopt the provider's payment and quote; fulfillment changes go
+        // through the fulfillment service
+        let mut updated_order = existing_order.clone();
+        if provider_order.payment.is_some() {
+            updated_order.payment = provider_order.payment;
+        }
+        if provider_order.quote.is_some() {
+            updated_order.quote = provider_order.quote;
+        }
+        self.reconcile_fulfillment(&mut updated_order, provider_order.fulfillment, "on_confirm")
+            .await?;
+        Self::transition(
+            &mut updated_order,
+            provider_order.state,
+            OrderActor::Provider,
+            "Confirmed by provider",
+        )?;
+
+        // Orders the gateway has not confirmed yet have no slot booked
+        if existing_order.state == OrderState::Quoted
+            && updated_order.state == OrderState::Confirmed
+        {
+            self.book_fulfillment(&mut updated_order).await?;
+            return self
+                .save_booked(&existing_order, updated_order, origin)
+                .await;
+        }
+
+        self.save_order(Some(&existing_order), updated_order, origin)
+            .await
     }
 
-    /// Get order status
-    pub async fn status(&self, order_id: &str) -> Result<OrderStatus, ServiceError> {
-        // Get the order
-        let order = self.storage.get_order(order_id).await?;
+    /// Apply the changes a provider made to an order's fulfillment.
+    ///
+    /// A booked fulfillment is changed through the fulfillment service only:
+    /// a new slot is rebooked, a new state must be reachable in the
+    /// fulfillment's lifecycle, and the assigned agent is adopted. Before
+    /// confirmation nothing is booked, so only the slot and agent are taken.
+    async fn reconcile_fulfillment(
+        &self,
+        order: &mut Order,
+        provider_fulfillment: Fulfillment,
+        source: &str,
+    ) -> Result<(), ServiceError> {
+        if provider_fulfillment.id.is_empty() {
+            return Ok(());
+        }
 
-        // If the order has a fulfillment ID, check the fulfillment status
-        if !order.fulfillment.id.is_empty() {
-            let fulfillment_id = &order.fulfillment.id;
-            // Get fulfillment status from FulfillmentService
-            match self.fulfillment_service.get_fulfillment(fulfillment_id).await {
-                Ok(fulfillment) => {
-                    // If fulfillment has state, use it to update order status
-                    if let Some(state) = &fulfillment.state {
-                        // Map fulfillment state to order state
-                        let order_state = match state.descriptor.as_str() {
-                            "SCHEDULED" => "CONFIRMED",
-                            "WAITING" => "FULFILLMENT_PENDING",
-                            "IN_PROGRESS" => "IN_PROGRESS",
-                            "COMPLETED" => "COMPLETED",
-                            "CANCELLED" => "CANCELLED",
-                            "NO_SHOW" => "NO_SHOW",
-                            "RESCHEDULED" => "RESCHEDULED",
-                            _ => &order.state, // Keep existing state if unknown
-                        };
+        let (start, end) = provider_fulfillment.slot();
+        let duration = (end - start).num_seconds();
+        let slot_changed = provider_fulfillment.slot() != order.fulfillment.slot();
 
-                        // If state doesn't match the order's current state, update the order
-                        if order_state != order.state {
-                            let mut updated_order = order.clone();
-                            updated_order.state = order_state.to_string();
-                            // Update the order in storage
-                            let _ = self.storage.update_order(updated_order).await?;
-                        }
+        if matches!(order.state, OrderState::Initialized | OrderState::Quoted) {
+            if slot_changed {
+                order.fulfillment.start.time.timestamp = start;
+                order.fulfillment.start.duration = Some(duration);
+                order.fulfillment.end.time.timestamp = end;
+            }
+            if provider_fulfillment.agent.is_some() {
+                order.fulfillment.agent = provider_fulfillment.agent;
+            }
+            return Ok(());
+        }
 
-                        // Return the mapped status
-                        return Ok(OrderStatus {
-                            state: order_state.to_string(),
-                            updated_at: state.updated_at,
-                        });
-                    }
+        if provider_fulfillment.id != order.fulfillment.id {
+            return Err(ServiceError::Validation(format!(
+                "Provider's fulfillment does not match order {}",
+                order.id
+            )));
+        }
+
+        let fulfillment_id = order.fulfillment.id.clone();
+        let mut fulfillment = if slot_changed {
+            self.fulfillment_service
+                .reschedule(&fulfillment_id, start, duration)
+                .await?
+        } else {
+            self.fulfillment_service
+                .get_fulfillment(&fulfillment_id)
+                .await?
+        };
+
+        let current_state = fulfillment.state.as_ref().map(|state| state.descriptor);
+        if let Some(state) = provider_fulfillment.state {
+            if current_state != Some(state.descriptor) {
+                let context = HashMap::from([
+                    ("source".to_string(), source.to_string()),
+                    ("order_id".to_string(), order.id.clone()),
+                ]);
+                fulfillment = self
+                    .fulfillment_service
+                    .update_state(&fulfillment_id, state.descriptor, Some(context))
+                    .await?;
+            }
+        }
+
+        if provider_fulfillment.agent.is_some() {
+            fulfillment.agent = provider_fulfillment.agent;
+            fulfillment = self
+                .fulfillment_service
+                .update_fulfillment(fulfillment)
+                .await?;
+        }
+
+        order.fulfillment = fulfillment;
+        Ok(())
+    }
+
+    /// Check that a provider's order carries the same items and quantities
+    fn check_items_match(order: &Order, provider_order: &Order) -> Result<(), ServiceError> {
+        let ordered: Vec<(&str, i32)> = order
+            .items
+            .iter()
+            .map(|item| (item.id.as_str(), item.quantity))
+            .collect();
+        let returned: Vec<(&str, i32)> = provider_order
+            .items
+            .iter()
+            .map(|item| (item.id.as_str(), item.quantity))
+            .collect();
+
+        if ordered != returned {
+            return Err(ServiceError::Validation(format!(
+                "Provider's items do not match order {}",
+                order.id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Move an order to a new state, recording who made the transition and
+    /// why. Staying in the same state is not a transition and is not recorded.
+    fn transition(
+        order: &mut Order,
+        to: OrderState,
+        actor: OrderActor,
+        reason: impl Into<String>,
+    ) -> Result<(), ServiceError> {
+        if order.state == to {
+            return Ok(());
+        }
+
+        if !order.state.can_transition_to(to) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Invalid order state transition from '{}' to '{}'",
+                order.state, to
+            )));
+        }
+
+        order.transitions.push(OrderTransition {
+            from: order.state,
+            to,
+            actor,
+            reason: reason.into(),
+            transitioned_at: Utc::now(),
+        });
+        order.state = to;
+        Ok(())
+    }
+
+    /// Update an order (update): reschedule its fulfillment, change its
+    /// items or correct its billing details.
+    ///
+    /// Every update is recorded as a new version in the order's changes;
+    /// an update racing another one for the same version is refused. Only
+    /// the consumer that initialized the order may update it.
+    pub async fn update(
+        &self,
+        order_id: &str,
+        update: OrderUpdate,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let existing_order = self.storage.get_order(order_id).await?;
+        Self::check_consumer(&existing_order, origin)?;
+        let order = existing_order.clone();
+
+        if !matches!(
+            order.state,
+            OrderState::Initialized | OrderState::Quoted | OrderState::Confirmed
+        ) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and can no longer be updated",
+                order_id, order.state
+            )));
+        }
+
+        let target = update.target();
+        let previous = change_snapshot(&order, target);
+
+        let mut order = match update {
+            OrderUpdate::Fulfillment { start } => self.reschedule(order, start).await?,
+            OrderUpdate::Items { items } => self.change_items(order, items).await?,
+            OrderUpdate::Billing { billing } => {
+                if billing.name.is_empty() || billing.phone.is_empty() {
+                    return Err(ServiceError::Validation(
+                        "Billing name and phone are required".to_string(),
+                    ));
                 }
-                Err(_) => {
-                    // If we can't get the fulfillment, just use the order's state
-                    // This could happen if the fulfillment was deleted or is invalid
+                Order {
+                    billing: *billing,
+                    ..order
                 }
             }
+        };
+
+        let current = change_snapshot(&order, target);
+        order.changes.push(OrderChange {
+            version: order.changes.len() as u32 + 1,
+            target: target.to_string(),
+            previous,
+            current,
+            changed_at: Utc::now(),
+        });
+
+        let saved = self
+            .storage
+            .record_order_change(order)
+            .await
+            .map_err(|err| match err {
+                StorageError::Conflict(_) => ServiceError::BusinessLogic(format!(
+                    "Order {} was updated concurrently; retry the update",
+                    order_id
+                )),
+                err => err.into(),
+            })?;
+        self.record_events(Some(&existing_order), &saved, origin)
+            .await?;
+        Ok(saved)
+    }
+
+    /// Move an order's fulfillment to a new slot, rebooking it when the
+    /// order is confirmed
+    async fn reschedule(&self, mut order: Order, start: TimeSlot) -> Result<Order, ServiceError> {
+        let duration = start
+            .duration
+            .or(order.fulfillment.start.duration)
+            .unwrap_or(3600);
+
+        if order.state == OrderState::Confirmed {
+            order.fulfillment = self
+                .fulfillment_service
+                .reschedule(&order.fulfillment.id, start.time.timestamp, duration)
+                .await?;
+        } else {
+            // Nothing is booked before confirmation
+            order.fulfillment.start.time.timestamp = start.time.timestamp;
+            order.fulfillment.start.duration = Some(duration);
+            order.fulfillment.end.time.timestamp =
+                start.time.timestamp + Duration::seconds(duration);
         }
 
-        // Return status information from the order if no fulfillment or fulfillment not found
-        let status = OrderStatus {
-            state: order.state,
-            updated_at: order.updated_at,
-        };
+        Ok(order)
+    }
 
-        Ok(status)
+    /// Replace an order's items, re-quoting quoted orders
+    async fn change_items(
+        &self,
+        mut order: Order,
+        items: Vec<SelectedItem>,
+    ) -> Result<Order, ServiceError> {
+        if items.is_empty() {
+            return Err(ServiceError::Validation(
+                "Order must contain at least one item".to_string(),
+            ));
+        }
+
+        if order.payment.as_ref().is_some_and(|p| p.status == "PAID") {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Items of order {} cannot be changed after payment",
+                order.id
+            )));
+        }
+
+        let selection = self
+            .catalog_service
+            .select_items(&SelectRequest {
+                provider_id: order.provider.id.clone(),
+                items,
+                fulfillment_id: None,
+            })
+            .await?;
+        order.items = selection.items;
+
+        // Payment terms follow the new quote
+        if order.quote.is_some() {
+            if let Some(payment) = order.payment.as_mut() {
+                payment.amount = Some(selection.quote.price.clone());
+                payment.currency = Some(selection.quote.price.currency.clone());
+            }
+            order.quote = Some(selection.quote);
+        }
+
+        Ok(order)
     }
 
-    /// Handle provider's status response
-    pub async fn on_status(
+    /// Handle provider's update response (on_update), adopting the
+    /// provider's quote and payment terms. Fulfillment changes go through
+    /// the fulfillment service; billing belongs to the consumer and only
+    /// changes through `update`. Only the order's provider may respond.
+    pub async fn on_update(
         &self,
         order_id: &str,
-        status: OrderStatus,
+        provider_id: &str,
+        provider_order: Order,
+        origin: &EventOrigin,
     ) -> Result<Order, ServiceError> {
-        // Get the existing order
-        let mut order = self.storage.get_order(order_id).await?;
+        let existing_order = self.storage.get_order(order_id).await?;
 
-        // Update state with provider's status
-        order.state = status.state.clone();
+        if existing_order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
 
-        // If there's a fulfillment ID associated with this order, update its state too
-        if !order.fulfillment.id.is_empty() {
-            let fulfillment_id = &order.fulfillment.id;
-            // Map order state to fulfillment state
-            let fulfillment_state = match status.state.as_str() {
-                "CONFIRMED" => "SCHEDULED",
-                "IN_PROGRESS" => "IN_PROGRESS",
-                "COMPLETED" => "COMPLETED",
-                "CANCELLED" => "CANCELLED",
-                "NO_SHOW" => "NO_SHOW",
-                "RESCHEDULED" => "RESCHEDULED",
-                _ => return Err(ServiceError::Validation(format!(
-                    "Unsupported order state for fulfillment mapping: {}",
-                    status.state
-                ))),
-            };
+        if !matches!(
+            existing_order.state,
+            OrderState::Initialized | OrderState::Quoted | OrderState::Confirmed
+        ) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and can no longer be updated",
+                order_id, existing_order.state
+            )));
+        }
 
-            // Update the fulfillment state
-            let context = HashMap::from([
-                ("source".to_string(), "order_status_update".to_string()),
-                ("order_id".to_string(), order_id.to_string()),
-            ]);
+        Self::check_items_match(&existing_order, &provider_order)?;
 
-            let _ = self.fulfillment_service
-                .update_state(fulfillment_id, fulfillment_state, Some(context))
-                .await;
-            // We don't propagate errors here, as we want to continue updating the order
-            // even if the fulfillment update fails
+        let mut updated_order = existing_order.clone();
+        if provider_order.quote.is_some() {
+            updated_order.quote = provider_order.quote;
         }
+        if provider_order.payment.is_some() {
+            updated_order.payment = provider_order.payment;
+        }
+        self.reconcile_fulfillment(&mut updated_order, provider_order.fulfillment, "on_update")
+            .await?;
 
-        let updated = self.storage.update_order(order).await?;
-        Ok(updated)
+        self.save_order(Some(&existing_order), updated_order, origin)
+            .await
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::models::billing::{Address, Billing};
-    use crate::models::fulfillment::{Agent, Customer, Fulfillment, Person, State, Time, TimeSlot};
-    use crate::models::order::ProviderSummary;
-    use crate::storage::memory::MemoryStorage;
-    use chrono::Utc;
-    use std::collections::HashMap;
-    use uuid::Uuid;
+    /// Cancel an order on the consumer's request (cancel).
+    ///
+    /// Confirmed orders are subject to the provider's cancellation policy:
+    /// cancellations too close to the fulfillment start are refused, and a
+    /// fee may be deducted from the refund of a settled payment.
+    pub async fn cancel(
+        &self,
+        order_id: &str,
+        reason: CancellationReason,
+        descriptor: Option<String>,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+        Self::check_consumer(&order, origin)?;
+        Self::check_cancellable(&order)?;
 
-    // Helper function to create a test order
-    fn create_test_order(id: &str, provider_id: &str, fulfillment_id: &str) -> Order {
-        Order {
-            id: id.to_string(),
-            provider: ProviderSummary {
-                id: provider_id.to_string(),
-                descriptor: "Test Provider".to_string(),
-                categories: vec!["Healthcare".to_string()],
-            },
-            items: Vec::new(),
-            billing: Billing {
-                name: "John Doe".to_string(),
-                organization: None,
-                address: Address {
-                    door: Some("101".to_string()),
-                    building: Some("Test Building".to_string()),
-                    street: Some("Test Street".to_string()),
-                    locality: Some("Test Locality".to_string()),
-                    city: "Test City".to_string(),
-                    state: "Test State".to_string(),
-                    country: "Test Country".to_string(),
-                    area_code: "12345".to_string(),
-                },
-                email: Some("john@example.com".to_string()),
-                phone: "1234567890".to_string(),
-                tax_number: None,
-            },
-            fulfillment: Fulfillment {
-                id: fulfillment_id.to_string(),
-                fulfillment_type: "Teleconsultation".to_string(),
-                provider_id: provider_id.to_string(),
-                agent: None,
-                start: TimeSlot {
-                    time: Time {
-                        timestamp: Utc::now(),
-                        label: None,
-                    },
-                    duration: Some(3600),
-                },
-                end: TimeSlot {
-                    time: Time {
-                        timestamp: Utc::now() + chrono::Duration::hours(1),
-                        label: None,
-                    },
-                    duration: None,
-                },
-                customer: None,
-                state: None,
-                tags: HashMap::new(),
-            },
-            quote: None,
-            payment: None,
-            state: "INITIALIZED".to_string(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        }
+        let now = Utc::now();
+        let fee = if order.state == OrderState::Confirmed {
+            self.cancellation_fee(&order, reason, now)?
+        } else {
+            None
+        };
+
+        let cancellation = Cancellation {
+            reason,
+            descriptor,
+            cancelled_by: OrderActor::Consumer,
+            fee,
+            cancelled_at: now,
+        };
+        let mut cancelled = order.clone();
+        let released = self
+            .apply_cancellation(&mut cancelled, cancellation)
+            .await?;
+        self.save_cancelled(&order, cancelled, released, origin)
+            .await
     }
 
-    // Helper function to create a test fulfillment
-    fn create_test_fulfillment(id: &str, provider_id: &str, state: &str) -> Fulfillment {
-        Fulfillment {
+    /// Handle provider's cancellation response (on_cancel).
+    ///
+    /// Orders the provider cancels on its own are cancelled without a fee.
+    /// The provider's refund record replaces the one initiated by the gateway.
+    /// Only the order's provider may cancel it.
+    pub async fn on_cancel(
+        &self,
+        order_id: &str,
+        provider_id: &str,
+        provider_order: Order,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let existing_order = self.storage.get_order(order_id).await?;
+
+        if existing_order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
+
+        if provider_order.state != OrderState::Cancelled {
+            return Err(ServiceError::Validation(format!(
+                "on_cancel must carry a cancelled order, got {}",
+                provider_order.state
+            )));
+        }
+
+        let mut order = existing_order.clone();
+        let mut released = None;
+        if existing_order.state != OrderState::Cancelled {
+            Self::check_cancellable(&existing_order)?;
+            let cancellation = provider_order.cancellation.unwrap_or(Cancellation {
+                reason: CancellationReason::ProviderUnavailable,
+                descriptor: None,
+                cancelled_by: OrderActor::Provider,
+                fee: None,
+                cancelled_at: Utc::now(),
+            });
+            released = self.apply_cancellation(&mut order, cancellation).await?;
+        }
+
+        if let (Some(payment), Some(refund)) = (
+            order.payment.as_mut(),
+            provider_order.payment.and_then(|payment| payment.refund),
+        ) {
+            payment.refund = Some(refund);
+        }
+
+        self.save_cancelled(&existing_order, order, released, origin)
+            .await
+    }
+
+    /// Save a cancelled order whose fulfillment was just cancelled, booking
+    /// the `released` fulfillment's slot again unless the order was stored
+    /// before the failure, e.g. while recording its events
+    async fn save_cancelled(
+        &self,
+        existing_order: &Order,
+        order: Order,
+        released: Option<Fulfillment>,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let saved = self.save_order(Some(existing_order), order, origin).await;
+
+        if let (Err(_), Some(fulfillment)) = (&saved, released) {
+            let stored = self.storage.get_order(&existing_order.id).await;
+            if !stored.is_ok_and(|stored| stored.state == OrderState::Cancelled) {
+                let fulfillment_id = fulfillment.id.clone();
+                if let Err(err) = self.storage.rebook_fulfillment(fulfillment).await {
+                    tracing::error!(
+                        "Failed to restore fulfillment {} of order {}: {}",
+                        fulfillment_id,
+                        existing_order.id,
+                        err
+                    );
+                }
+            }
+        }
+        saved
+    }
+
+    /// Check that a request comes from the consumer that initialized the order
+    fn check_consumer(order: &Order, origin: &EventOrigin) -> Result<(), ServiceError> {
+        if order.consumer_id != origin.subscriber_id {
+            return Err(ServiceError::Forbidden(format!(
+                "Order {} belongs to another consumer",
+                order.id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that an order is in a state that can still be cancelled
+    fn check_cancellable(order: &Order) -> Result<(), ServiceError> {
+        match order.state {
+            OrderState::Initialized | OrderState::Quoted | OrderState::Confirmed => Ok(()),
+            OrderState::Cancelled => Err(ServiceError::BusinessLogic(format!(
+                "Order {} is already cancelled",
+                order.id
+            ))),
+            state => Err(ServiceError::BusinessLogic(format!(
+                "Order {} is {} and can no longer be cancelled",
+                order.id, state
+            ))),
+        }
+    }
+
+    /// Apply the provider's cancellation policy to a confirmed order,
+    /// returning the fee to charge, if any
+    fn cancellation_fee(
+        &self,
+        order: &Order,
+        reason: CancellationReason,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Price>, ServiceError> {
+        let policy = self.cancellation.policy_for(&order.provider.id);
+        let until_start = order.fulfillment.start.time.timestamp - now;
+
+        if until_start < Duration::minutes(policy.cutoff_mins) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} can no longer be cancelled less than {} minutes before it starts",
+                order.id, policy.cutoff_mins
+            )));
+        }
+
+        if reason.waives_fee() {
+            return Ok(None);
+        }
+
+        let percent = policy
+            .fees
+            .iter()
+            .filter(|fee| until_start < Duration::hours(fee.within_hours))
+            .map(|fee| fee.percent)
+            .fold(0.0, f64::max);
+        let Some(quote) = order.quote.as_ref().filter(|_| percent > 0.0) else {
+            return Ok(None);
+        };
+
+        let total = parse_amount(&quote.price)?;
+        Ok(Some(Price {
+            currency: quote.price.currency.clone(),
+            value: round_amount(total * percent / 100.0).to_string(),
+            maximum_value: None,
+        }))
+    }
+
+    /// Cancel an order: initiate the refund of a settled payment less the
+    /// cancellation fee, mark it cancelled and, last, free its booked slot.
+    /// Returns the fulfillment as it was before it was cancelled, if one
+    /// was; the caller saves the order with `save_cancelled`.
+    async fn apply_cancellation(
+        &self,
+        order: &mut Order,
+        cancellation: Cancellation,
+    ) -> Result<Option<Fulfillment>, ServiceError> {
+        let quoted = order.quote.as_ref().map(|quote| quote.price.clone());
+        if let Some(payment) = order.payment.as_mut().filter(|p| p.status == "PAID") {
+            if let Some(paid) = payment.amount.clone().or(quoted) {
+                let fee = match &cancellation.fee {
+                    Some(fee) => parse_amount(fee)?,
+                    None => 0.0,
+                };
+                let refund = round_amount(parse_amount(&paid)? - fee);
+                if refund > 0.0 {
+                    payment.refund = Some(Refund {
+                        amount: Price {
+                            currency: paid.currency,
+                            value: refund.to_string(),
+                            maximum_value: None,
+                        },
+                        status: "INITIATED".to_string(),
+                        initiated_at: cancellation.cancelled_at,
+                    });
+                }
+            }
+        }
+
+        Self::transition(
+            order,
+            OrderState::Cancelled,
+            cancellation.cancelled_by,
+            format!("Cancelled: {}", cancellation.reason.code()),
+        )?;
+        let reason = cancellation.reason.code().to_string();
+        order.cancellation = Some(cancellation);
+
+        if order.fulfillment.id.is_empty() {
+            return Ok(None);
+        }
+        let fulfillment = match self
+            .fulfillment_service
+            .get_fulfillment(&order.fulfillment.id)
+            .await
+        {
+            Ok(fulfillment) => fulfillment,
+            // Nothing was booked for draft orders
+            Err(ServiceError::NotFound(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let cancelled = fulfillment
+            .state
+            .as_ref()
+            .is_some_and(|state| state.descriptor == FulfillmentState::Cancelled);
+        if cancelled {
+            return Ok(None);
+        }
+
+        let context = HashMap::from([("reason".to_string(), reason)]);
+        order.fulfillment = self
+            .fulfillment_service
+            .update_state(&fulfillment.id, FulfillmentState::Cancelled, Some(context))
+            .await?;
+        Ok(Some(fulfillment))
+    }
+
+    /// Get the tracking information of a confirmed order's fulfillment (track)
+    pub async fn track(&self, order_id: &str) -> Result<Tracking, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+
+        if matches!(order.state, OrderState::Initialized | OrderState::Quoted) {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} is not confirmed and cannot be tracked",
+                order_id
+            )));
+        }
+
+        let fulfillment = self.current_fulfillment(&order).await?;
+        self.fulfillment_service.get_tracking(&fulfillment).await
+    }
+
+    /// Handle provider's tracking update (on_track)
+    pub async fn on_track(
+        &self,
+        order_id: &str,
+        provider_id: &str,
+        tracking: Tracking,
+    ) -> Result<Tracking, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+
+        if order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
+
+        let fulfillment = self.current_fulfillment(&order).await?;
+        self.fulfillment_service
+            .record_tracking(&fulfillment, tracking)
+            .await
+    }
+
+    /// Get the support contact of an order's provider (support)
+    pub async fn support(&self, order_id: &str) -> Result<SupportContact, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+        let provider = self.storage.get_provider(&order.provider.id).await?;
+
+        provider
+            .support
+            .filter(|contact| !contact.is_empty())
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!(
+                    "Provider {} has no support contact",
+                    order.provider.id
+                ))
+            })
+    }
+
+    /// Handle provider's support response (on_support)
+    pub async fn on_support(
+        &self,
+        order_id: &str,
+        provider_id: &str,
+        contact: &SupportContact,
+    ) -> Result<(), ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+
+        if order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
+
+        if contact.is_empty() {
+            return Err(ServiceError::Validation(
+                "on_support must carry a phone, email or URL".to_string(),
+            ));
+        }
+
+        tracing::info!(
+            "Provider {} sent support contact for order {}",
+            provider_id,
+            order_id
+        );
+        Ok(())
+    }
+
+    /// Stored fulfillment of an order, or the order's own copy when the
+    /// fulfillment was booked directly with the provider
+    async fn current_fulfillment(&self, order: &Order) -> Result<Fulfillment, ServiceError> {
+        match self
+            .fulfillment_service
+            .get_fulfillment(&order.fulfillment.id)
+            .await
+        {
+            Ok(fulfillment) => Ok(fulfillment),
+            Err(ServiceError::NotFound(_)) => Ok(order.fulfillment.clone()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Get order status
+    pub async fn status(
+        &self,
+        order_id: &str,
+        origin: &EventOrigin,
+    ) -> Result<OrderStatus, ServiceError> {
+        // Get the order
+        let order = self.storage.get_order(order_id).await?;
+
+        // If the order has a fulfillment ID, check the fulfillment status
+        if !order.fulfillment.id.is_empty() {
+            let fulfillment_id = &order.fulfillment.id;
+            // Get fulfillment status from FulfillmentService
+            match self.fulfillment_service.get_fulfillment(fulfillment_id).await {
+                Ok(fulfillment) => {
+                    // If fulfillment has state, use it to update order status
+                    if let Some(state) = &fulfillment.state {
+                        // Map fulfillment state to order state
+                        let order_state = match state.descriptor {
+                            FulfillmentState::Scheduled => OrderState::Confirmed,
+                            FulfillmentState::Waiting | FulfillmentState::EnRoute => {
+                                OrderState::FulfillmentPending
+                            }
+                            FulfillmentState::InProgress | FulfillmentState::SampleCollected => {
+                                OrderState::InProgress
+                            }
+                            FulfillmentState::Completed => OrderState::Completed,
+                            FulfillmentState::Cancelled => OrderState::Cancelled,
+                            FulfillmentState::NoShow => OrderState::NoShow,
+                            FulfillmentState::Rescheduled => OrderState::Rescheduled,
+                        };
+
+                        // If state doesn't match the order's current state, update the order
+                        if order_state != order.state {
+                            let mut updated_order = order.clone();
+                            let reason = format!("Fulfillment is {}", state.descriptor);
+                            if let Err(err) = Self::transition(
+                                &mut updated_order,
+                                order_state,
+                                OrderActor::Gateway,
+                                reason,
+                            ) {
+                                // Report the order's own state rather than an illegal one
+                                tracing::warn!("Order {} not updated: {}", order_id, err);
+                                return Ok(OrderStatus {
+                                    state: order.state,
+                                    updated_at: order.updated_at,
+                                });
+                            }
+                            // Update the order in storage
+                            let _ = self.save_order(Some(&order), updated_order, origin).await?;
+                        }
+
+                        // Return the mapped status
+                        return Ok(OrderStatus {
+                            state: order_state,
+                            updated_at: state.updated_at,
+                        });
+                    }
+                }
+                Err(_) => {
+                    // If we can't get the fulfillment, just use the order's state
+                    // This could happen if the fulfillment was deleted or is invalid
+                }
+            }
+        }
+
+        // Return status information from the order if no fulfillment or fulfillment not found
+        let status = OrderStatus {
+            state: order.state,
+            updated_at: order.updated_at,
+        };
+
+        Ok(status)
+    }
+
+    /// Handle provider's status response; only the order's provider may
+    /// move it through its states
+    pub async fn on_status(
+        &self,
+        order_id: &str,
+        provider_id: &str,
+        status: OrderStatus,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        // Get the existing order
+        let existing_order = self.storage.get_order(order_id).await?;
+        let mut order = existing_order.clone();
+
+        if order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
+
+        // Update state with provider's status
+        Self::transition(
+            &mut order,
+            status.state,
+            OrderActor::Provider,
+            "Status update from provider",
+        )?;
+
+        // If there's a fulfillment ID associated with this order, update its state too
+        if !order.fulfillment.id.is_empty() {
+            let fulfillment = self
+                .fulfillment_service
+                .get_fulfillment(&order.fulfillment.id)
+                .await?;
+            let fulfillment_state =
+                Self::fulfillment_state_for(status.state, &fulfillment.fulfillment_type)?;
+
+            let current_state = fulfillment.state.as_ref().map(|state| state.descriptor);
+            order.fulfillment = if current_state == Some(fulfillment_state) {
+                fulfillment
+            } else {
+                let context = HashMap::from([
+                    ("source".to_string(), "order_status_update".to_string()),
+                    ("order_id".to_string(), order_id.to_string()),
+                ]);
+
+                self.fulfillment_service
+                    .update_state(&fulfillment.id, fulfillment_state, Some(context))
+                    .await?
+            };
+        }
+
+        self.save_order(Some(&existing_order), order, origin).await
+    }
+
+    /// Fulfillment state matching an order state, following the lifecycle of
+    /// the fulfillment type: home visits and lab pickups are EN_ROUTE while
+    /// pending, and a lab pickup is under way once the sample is collected
+    fn fulfillment_state_for(
+        state: OrderState,
+        fulfillment_type: &str,
+    ) -> Result<FulfillmentState, ServiceError> {
+        let travels = matches!(
+            fulfillment_type.to_lowercase().as_str(),
+            "home-visit" | "lab-pickup"
+        );
+
+        Ok(match state {
+            OrderState::Confirmed => FulfillmentState::Scheduled,
+            OrderState::FulfillmentPending if travels => FulfillmentState::EnRoute,
+            OrderState::FulfillmentPending => FulfillmentState::Waiting,
+            OrderState::InProgress if fulfillment_type.eq_ignore_ascii_case("lab-pickup") => {
+                FulfillmentState::SampleCollected
+            }
+            OrderState::InProgress => FulfillmentState::InProgress,
+            OrderState::Completed => FulfillmentState::Completed,
+            OrderState::Cancelled => FulfillmentState::Cancelled,
+            OrderState::NoShow => FulfillmentState::NoShow,
+            OrderState::Rescheduled => FulfillmentState::Rescheduled,
+            _ => {
+                return Err(ServiceError::Validation(format!(
+                    "Unsupported order state for fulfillment mapping: {}",
+                    state
+                )))
+            }
+        })
+    }
+
+    /// Chronological history of changes to an order and its fulfillment
+    pub async fn timeline(&self, order_id: &str) -> Result<OrderTimeline, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+        let events = self.storage.list_order_events(order_id).await?;
+
+        Ok(OrderTimeline {
+            order_id: order.id,
+            provider_id: order.provider.id,
+            events,
+        })
+    }
+
+    /// Store an order, creating it when there is no `previous` version, and
+    /// append the changes to its history
+    async fn save_order(
+        &self,
+        previous: Option<&Order>,
+        order: Order,
+        origin: &EventOrigin,
+    ) -> Result<Order, ServiceError> {
+        let saved = match previous {
+            Some(_) => self.storage.update_order(order).await?,
+            None => self.storage.create_order(order).await?,
+        };
+
+        self.record_events(previous, &saved, origin).await?;
+        Ok(saved)
+    }
+
+    /// Append the changes from `previous` to a stored order to its history
+    async fn record_events(
+        &self,
+        previous: Option<&Order>,
+        order: &Order,
+        origin: &EventOrigin,
+    ) -> Result<(), ServiceError> {
+        for event in order_events(previous, order, origin) {
+            self.storage.append_order_event(event).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Events recording the change from `previous` to `order`: one for the
+/// order, and one for its fulfillment when the fulfillment's state or slot
+/// changed
+fn order_events(previous: Option<&Order>, order: &Order, origin: &EventOrigin) -> Vec<OrderEvent> {
+    let event = |entity, entity_id: &str, previous_state, new_state| OrderEvent {
+        order_id: order.id.clone(),
+        entity,
+        entity_id: entity_id.to_string(),
+        actor: origin.subscriber_id.clone(),
+        action: origin.action.clone(),
+        previous_state,
+        new_state,
+        message_id: origin.message_id.clone(),
+        recorded_at: Utc::now(),
+    };
+
+    let mut events = vec![event(
+        EventEntity::Order,
+        &order.id,
+        previous.map(|previous| previous.state.code().to_string()),
+        Some(order.state.code().to_string()),
+    )];
+
+    let fulfillment_state = |fulfillment: &Fulfillment| {
+        fulfillment
+            .state
+            .as_ref()
+            .map(|state| state.descriptor.code().to_string())
+    };
+    let previous_fulfillment = previous.map(|previous| &previous.fulfillment);
+    let changed = previous_fulfillment.is_none_or(|previous_fulfillment| {
+        fulfillment_state(previous_fulfillment) != fulfillment_state(&order.fulfillment)
+            || previous_fulfillment.start.time.timestamp != order.fulfillment.start.time.timestamp
+    });
+    if changed && !order.fulfillment.id.is_empty() {
+        events.push(event(
+            EventEntity::Fulfillment,
+            &order.fulfillment.id,
+            previous_fulfillment.and_then(fulfillment_state),
+            fulfillment_state(&order.fulfillment),
+        ));
+    }
+
+    events
+}
+
+/// Parse the value of a price
+fn parse_amount(price: &Price) -> Result<f64, ServiceError> {
+    price
+        .value
+        .parse()
+        .map_err(|_| ServiceError::Validation(format!("Invalid amount '{}'", price.value)))
+}
+
+/// Snapshot of the part of an order changed by an update to `target`
+fn change_snapshot(order: &Order, target: &str) -> serde_json::Value {
+    match target {
+        "fulfillment" => json!(order.fulfillment),
+        "items" => json!({ "items": order.items, "quote": order.quote }),
+        _ => json!(order.billing),
+    }
+}
+
+/// Round an amount to two decimal places
+fn round_amount(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::billing::{Address, Billing};
+    use crate::models::fulfillment::{Agent, Customer, Fulfillment, Person, State, Time, TimeSlot};
+    use crate::models::order::ProviderSummary;
+    use crate::storage::memory::MemoryStorage;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    // Helper function to create a test order
+    fn create_test_order(id: &str, provider_id: &str, fulfillment_id: &str) -> Order {
+        Order {
+            id: id.to_string(),
+            consumer_id: "eua-1".to_string(),
+            provider: ProviderSummary {
+                id: provider_id.to_string(),
+                descriptor: "Test Provider".to_string(),
+                categories: vec!["Healthcare".to_string()],
+            },
+            items: Vec::new(),
+            billing: Billing {
+                name: "John Doe".to_string(),
+                organization: None,
+                address: Address {
+                    door: Some("101".to_string()),
+                    building: Some("Test Building".to_string()),
+                    street: Some("Test Street".to_string()),
+                    locality: Some("Test Locality".to_string()),
+                    city: "Test City".to_string(),
+                    state: "Test State".to_string(),
+                    country: "Test Country".to_string(),
+                    area_code: "12345".to_string(),
+                },
+                email: Some("john@example.com".to_string()),
+                phone: "1234567890".to_string(),
+                tax_number: None,
+            },
+            fulfillment: Fulfillment {
+                id: fulfillment_id.to_string(),
+                fulfillment_type: "Teleconsultation".to_string(),
+                provider_id: provider_id.to_string(),
+                agent: None,
+                start: TimeSlot {
+                    time: Time {
+                        timestamp: Utc::now(),
+                        label: None,
+                    },
+                    duration: Some(3600),
+                },
+                end: TimeSlot {
+                    time: Time {
+                        timestamp: Utc::now() + chrono::Duration::hours(1),
+                        label: None,
+                    },
+                    duration: None,
+                },
+                customer: None,
+                state: None,
+                tags: HashMap::new(),
+            },
+            quote: None,
+            payment: None,
+            state: OrderState::Initialized,
+            transitions: Vec::new(),
+            cancellation: None,
+            changes: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    // Helper function to create a test fulfillment
+    fn create_test_fulfillment(
+        id: &str,
+        provider_id: &str,
+        state: FulfillmentState,
+    ) -> Fulfillment {
+        Fulfillment {
             id: id.to_string(),
             fulfillment_type: "Teleconsultation".to_string(),
             provider_id: provider_id.to_string(),
@@ -301,95 +1432,1451 @@ mod tests {
                 },
                 duration: Some(3600),
             },
-            end: TimeSlot {
-                time: Time {
-                    timestamp: Utc::now() + chrono::Duration::hours(1),
-                    label: None,
+            end: TimeSlot {
+                time: Time {
+                    timestamp: Utc::now() + chrono::Duration::hours(1),
+                    label: None,
+                },
+                duration: None,
+            },
+            customer: Some(Customer {
+                person: Person {
+                    name: "John Doe".to_string(),
+                    image: None,
+                    gender: Some("male".to_string()),
+                    creds: None,
+                    tags: None,
+                },
+                contact: HashMap::from([
+                    ("phone".to_string(), "1234567890".to_string()),
+                    ("email".to_string(), "john@example.com".to_string()),
+                ]),
+            }),
+            state: Some(State {
+                descriptor: state,
+                updated_at: Utc::now(),
+            }),
+            tags: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_status_checking_flow() {
+        // Create memory storage
+        let storage = Arc::new(MemoryStorage::new());
+        
+        // Create a provider for testing
+        let provider_id = "provider-1";
+        let provider = crate::models::provider::Provider {
+            id: provider_id.to_string(),
+            descriptor: crate::models::provider::Descriptor {
+                name: "Test Provider".to_string(),
+                short_desc: None,
+                long_desc: None,
+                images: None,
+            },
+            categories: Vec::new(),
+            support: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let _ = storage.create_provider(provider).await.unwrap();
+        
+        // Create OrderService and FulfillmentService
+        let order_service = OrderService::new(storage.clone());
+        let fulfillment_service = FulfillmentService::new(storage.clone());
+        
+        // Create a fulfillment
+        let fulfillment_id = "fulfillment-1";
+        let fulfillment =
+            create_test_fulfillment(fulfillment_id, provider_id, FulfillmentState::Scheduled);
+        let created_fulfillment = fulfillment_service.create_fulfillment(fulfillment).await.unwrap();
+        
+        // Create an order that references the fulfillment
+        let order_id = "order-1";
+        let order = create_test_order(order_id, provider_id, fulfillment_id);
+        let created_order = order_service.create_order(order).await.unwrap();
+        
+        // Check initial order status
+        let initial_status = order_service
+            .status(order_id, &origin("status"))
+            .await
+            .unwrap();
+        assert_eq!(initial_status.state, OrderState::Confirmed); // Should map from SCHEDULED to CONFIRMED
+        
+        // Update fulfillment state to IN_PROGRESS
+        let updated_fulfillment = fulfillment_service
+            .update_state(fulfillment_id, FulfillmentState::InProgress, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            updated_fulfillment.state.unwrap().descriptor,
+            FulfillmentState::InProgress
+        );
+        
+        // Check that order status reflects the fulfillment status
+        let updated_status = order_service
+            .status(order_id, &origin("status"))
+            .await
+            .unwrap();
+        assert_eq!(updated_status.state, OrderState::InProgress);
+        
+        // Test on_status handler with a status update
+        let completed_status = OrderStatus {
+            state: OrderState::Completed,
+            updated_at: Utc::now(),
+        };
+        
+        let updated_order = order_service
+            .on_status(
+                order_id,
+                provider_id,
+                completed_status,
+                &origin("on_status"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated_order.state, OrderState::Completed);
+        
+        // Verify that fulfillment was also updated
+        let final_fulfillment = fulfillment_service.get_fulfillment(fulfillment_id).await.unwrap();
+        assert_eq!(
+            final_fulfillment.state.unwrap().descriptor,
+            FulfillmentState::Completed
+        );
+    }
+
+    // Helper function to create a provider with a one item catalog
+    async fn create_provider_with_catalog(storage: &Arc<MemoryStorage>, provider_id: &str) {
+        use crate::models::catalog::{Catalog, Item, Price};
+        use crate::models::provider::{Category, Descriptor, Provider};
+
+        let descriptor = |name: &str| Descriptor {
+            name: name.to_string(),
+            short_desc: None,
+            long_desc: None,
+            images: None,
+        };
+
+        storage
+            .create_provider(Provider {
+                id: provider_id.to_string(),
+                descriptor: descriptor("Test Provider"),
+                categories: Vec::new(),
+                support: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+
+        let catalog = Catalog {
+            descriptor: descriptor("Test Catalog"),
+            categories: vec![Category {
+                id: "cat-1".to_string(),
+                descriptor: descriptor("Cardiology"),
+                time: None,
+                tags: None,
+            }],
+            fulfillments: vec!["Teleconsultation".to_string()],
+            payments: vec!["ON-ORDER".to_string()],
+            locations: Vec::new(),
+            items: vec![Item {
+                id: "item-1".to_string(),
+                parent_item_id: None,
+                descriptor: descriptor("Consultation"),
+                price: Price {
+                    currency: "INR".to_string(),
+                    value: "500".to_string(),
+                    maximum_value: None,
+                },
+                category_id: "cat-1".to_string(),
+                fulfillment_id: "Teleconsultation".to_string(),
+                location_id: None,
+                time: None,
+                recommended: None,
+                tags: None,
+            }],
+            exp: None,
+        };
+        CatalogService::new(storage.clone())
+            .create_catalog(provider_id, catalog)
+            .await
+            .unwrap();
+    }
+
+    // Helper function to create an order for the catalog item
+    fn create_draft_order(provider_id: &str, item_id: &str, quantity: i32) -> Order {
+        use crate::models::catalog::{Item, Price};
+        use crate::models::order::OrderItem;
+        use crate::models::provider::Descriptor;
+
+        let mut order = create_test_order(&Uuid::new_v4().to_string(), provider_id, "");
+        order.items = vec![OrderItem {
+            id: item_id.to_string(),
+            quantity,
+            // Consumer supplied copy of the item, with a tampered price
+            item: Item {
+                id: item_id.to_string(),
+                parent_item_id: None,
+                descriptor: Descriptor {
+                    name: "Consultation".to_string(),
+                    short_desc: None,
+                    long_desc: None,
+                    images: None,
+                },
+                price: Price {
+                    currency: "INR".to_string(),
+                    value: "1".to_string(),
+                    maximum_value: None,
+                },
+                category_id: "cat-1".to_string(),
+                fulfillment_id: "Teleconsultation".to_string(),
+                location_id: None,
+                time: None,
+                recommended: None,
+                tags: None,
+            },
+        }];
+        order.state = OrderState::Initialized;
+        order
+    }
+
+    #[tokio::test]
+    async fn test_init_creates_draft_from_catalog() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-2").await;
+        let order_service = OrderService::new(storage.clone());
+
+        let draft = order_service
+            .init(
+                create_draft_order("provider-2", "item-1", 2),
+                &origin("init"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(draft.state, OrderState::Initialized);
+        assert!(draft.quote.is_none());
+        // Item details are taken from the catalog
+        assert_eq!(draft.items[0].item.price.value, "500");
+        assert_eq!(draft.items[0].quantity, 2);
+
+        // Unknown items are rejected
+        let result = order_service
+            .init(
+                create_draft_order("provider-2", "item-404", 1),
+                &origin("init"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_quote_attaches_quote_and_payment_terms() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-3").await;
+        let order_service = OrderService::new(storage.clone());
+
+        let draft = order_service
+            .init(
+                create_draft_order("provider-3", "item-1", 2),
+                &origin("init"),
+            )
+            .await
+            .unwrap();
+        let quoted = order_service
+            .quote(&draft.id, &origin("quote"))
+            .await
+            .unwrap();
+
+        assert_eq!(quoted.state, OrderState::Quoted);
+        let quote = quoted.quote.unwrap();
+        assert_eq!(quote.price.value, "1000");
+        assert_eq!(quote.breakup[0].title, "Consultation x 2");
+
+        let payment = quoted.payment.unwrap();
+        assert_eq!(payment.payment_type, "ON-ORDER");
+        assert_eq!(payment.status, "DUE");
+        assert_eq!(payment.amount.unwrap().value, "1000");
+
+        // A quoted order cannot be quoted again
+        let result = order_service.quote(&draft.id, &origin("quote")).await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_on_init_from_other_provider_is_rejected() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-3b").await;
+        let order_service = OrderService::new(storage.clone());
+
+        let draft = order_service
+            .init(
+                create_draft_order("provider-3b", "item-1", 1),
+                &origin("init"),
+            )
+            .await
+            .unwrap();
+        let quoted = create_quoted_order(&order_service, "provider-3b").await;
+
+        // The body names the order's provider, but another provider sent it
+        let result = order_service
+            .on_init(&draft.id, "provider-x", quoted, &origin("on_init"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let stored = storage.get_order(&draft.id).await.unwrap();
+        assert_eq!(stored.state, OrderState::Initialized);
+    }
+
+    // Helper function to create a quoted order for a weekday morning slot
+    async fn create_quoted_order(order_service: &OrderService, provider_id: &str) -> Order {
+        use chrono::{Datelike, Duration};
+
+        let mut order = create_draft_order(provider_id, "item-1", 1);
+        let days = 7 - Utc::now().weekday().num_days_from_monday() as i64;
+        let start = (Utc::now() + Duration::days(days))
+            .date_naive()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+            .and_utc();
+        order.fulfillment.start.time.timestamp = start;
+        order.fulfillment.end.time.timestamp = start + Duration::hours(1);
+
+        let draft = order_service.init(order, &origin("init")).await.unwrap();
+        order_service
+            .quote(&draft.id, &origin("quote"))
+            .await
+            .unwrap()
+    }
+
+    // Helper function to settle the payment terms of a quoted order
+    fn paid(order: &Order) -> Payment {
+        Payment {
+            status: "PAID".to_string(),
+            ..order.payment.clone().unwrap()
+        }
+    }
+
+    // Helper function to describe the message behind a service call; callbacks
+    // come from the provider, requests from the consumer
+    fn origin(action: &str) -> EventOrigin {
+        let subscriber_id = if action.starts_with("on_") {
+            "hsp-1"
+        } else {
+            "eua-1"
+        };
+        EventOrigin::new(subscriber_id, action, Uuid::new_v4().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_confirm_books_fulfillment() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-4").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-4").await;
+
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+
+        assert_eq!(confirmed.state, OrderState::Confirmed);
+        assert_eq!(confirmed.payment.unwrap().status, "PAID");
+        assert!(!confirmed.fulfillment.id.is_empty());
+        let booked = storage
+            .get_fulfillment(&confirmed.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            booked.state.unwrap().descriptor,
+            FulfillmentState::Scheduled
+        );
+
+        // A confirmed order cannot be confirmed again
+        let result = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_confirm_by_other_consumer_is_forbidden() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-4b").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-4b").await;
+
+        let result = order_service
+            .confirm(
+                &quoted.id,
+                paid(&quoted),
+                &EventOrigin::new("eua-2", "confirm", "message-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+        assert_eq!(
+            storage.get_order(&quoted.id).await.unwrap().state,
+            OrderState::Quoted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confirm_rejects_double_booking() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-5").await;
+        let order_service = Arc::new(OrderService::new(storage.clone()));
+        let first = create_quoted_order(&order_service, "provider-5").await;
+        let second = create_quoted_order(&order_service, "provider-5").await;
+
+        // Both orders request the same slot concurrently
+        let (first_origin, second_origin) = (origin("confirm"), origin("confirm"));
+        let (a, b) = tokio::join!(
+            order_service.confirm(&first.id, paid(&first), &first_origin),
+            order_service.confirm(&second.id, paid(&second), &second_origin)
+        );
+        assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+
+        let rejected = if a.is_ok() { &second } else { &first };
+        assert_eq!(
+            order_service.get_order(&rejected.id).await.unwrap().state,
+            OrderState::Quoted
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confirm_rejects_expired_quote_and_unacceptable_payment() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-6").await;
+        let order_service = OrderService::new(storage.clone());
+        let mut quoted = create_quoted_order(&order_service, "provider-6").await;
+
+        // Payment not yet made for an ON-ORDER order
+        let result = order_service
+            .confirm(
+                &quoted.id,
+                quoted.payment.clone().unwrap(),
+                &origin("confirm"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        // Payment of a different amount
+        let mut payment = paid(&quoted);
+        payment.amount.as_mut().unwrap().value = "1".to_string();
+        let result = order_service
+            .confirm(&quoted.id, payment, &origin("confirm"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Payment of a different type
+        let mut payment = paid(&quoted);
+        payment.payment_type = "ON-DELIVERY".to_string();
+        let result = order_service
+            .confirm(&quoted.id, payment, &origin("confirm"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Expired quote
+        quoted.quote.as_mut().unwrap().valid_until =
+            Some(Utc::now() - chrono::Duration::minutes(1));
+        let quoted = storage.update_order(quoted).await.unwrap();
+        let result = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_on_confirm_reconciles_provider_order() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-7").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-7").await;
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+
+        // The provider may not change the ordered quantities
+        let mut provider_order = confirmed.clone();
+        provider_order.items[0].quantity = 3;
+        let result = order_service
+            .on_confirm(
+                &confirmed.id,
+                "provider-7",
+                provider_order,
+                &origin("on_confirm"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Only the order's provider may confirm it, whatever the body says
+        let result = order_service
+            .on_confirm(
+                &confirmed.id,
+                "provider-x",
+                confirmed.clone(),
+                &origin("on_confirm"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // The provider's fulfillment details are adopted
+        let mut provider_order = confirmed.clone();
+        provider_order.fulfillment.agent = Some(Agent {
+            id: "agent-2".to_string(),
+            name: "Dr. Rao".to_string(),
+            gender: None,
+            image: None,
+            tags: HashMap::new(),
+        });
+        let reconciled = order_service
+            .on_confirm(
+                &confirmed.id,
+                "provider-7",
+                provider_order,
+                &origin("on_confirm"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(reconciled.state, OrderState::Confirmed);
+        assert_eq!(reconciled.fulfillment.agent.unwrap().id, "agent-2");
+    }
+
+    #[tokio::test]
+    async fn test_on_update_routes_fulfillment_changes() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-21").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-21").await;
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+        let booked_at = confirmed.fulfillment.start.time.timestamp;
+
+        // A moved slot is rebooked; the provider cannot rewrite the billing
+        let mut provider_order = confirmed.clone();
+        provider_order.fulfillment.start.time.timestamp = booked_at + chrono::Duration::hours(4);
+        provider_order.billing.name = "Someone Else".to_string();
+        let updated = order_service
+            .on_update(
+                &confirmed.id,
+                "provider-21",
+                provider_order,
+                &origin("on_update"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.billing.name, confirmed.billing.name);
+
+        // Only the order's provider may respond, whatever the body says
+        let result = order_service
+            .on_update(
+                &confirmed.id,
+                "provider-x",
+                updated.clone(),
+                &origin("on_update"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let fulfillment = storage
+            .get_fulfillment(&confirmed.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            fulfillment.start.time.timestamp,
+            booked_at + chrono::Duration::hours(4)
+        );
+        assert_eq!(fulfillment.tags["rescheduled_from"], booked_at.to_rfc3339());
+        assert_eq!(
+            updated.fulfillment.start.time.timestamp,
+            fulfillment.start.time.timestamp
+        );
+
+        // States must follow the fulfillment lifecycle
+        let mut provider_order = updated.clone();
+        provider_order.fulfillment.state = Some(State {
+            descriptor: FulfillmentState::Completed,
+            updated_at: Utc::now(),
+        });
+        let result = order_service
+            .on_update(
+                &confirmed.id,
+                "provider-21",
+                provider_order,
+                &origin("on_update"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        // and the provider cannot swap in another fulfillment
+        let mut provider_order = updated.clone();
+        provider_order.fulfillment.id = "fulfillment-other".to_string();
+        let result = order_service
+            .on_update(
+                &confirmed.id,
+                "provider-21",
+                provider_order,
+                &origin("on_update"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let stored = storage
+            .get_fulfillment(&confirmed.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored.state.unwrap().descriptor,
+            FulfillmentState::Scheduled
+        );
+    }
+
+    #[tokio::test]
+    async fn test_on_confirm_books_unconfirmed_order() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-22").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-22").await;
+
+        let mut provider_order = quoted.clone();
+        provider_order.fulfillment.id = "fulfillment-hsp".to_string();
+        provider_order.state = OrderState::Confirmed;
+        let confirmed = order_service
+            .on_confirm(
+                &quoted.id,
+                "provider-22",
+                provider_order,
+                &origin("on_confirm"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(confirmed.state, OrderState::Confirmed);
+        let booked = storage
+            .get_fulfillment(&confirmed.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            booked.state.unwrap().descriptor,
+            FulfillmentState::Scheduled
+        );
+    }
+
+    // Helper function to create a service with a cancellation policy for a provider
+    fn create_service_with_policy(
+        storage: Arc<MemoryStorage>,
+        provider_id: &str,
+        cutoff_mins: i64,
+        percent: f64,
+    ) -> OrderService {
+        use crate::config::{CancellationFee, CancellationPolicy};
+
+        let policy = CancellationPolicy {
+            cutoff_mins,
+            // The booked slot is always less than eight days away
+            fees: vec![CancellationFee {
+                within_hours: 8 * 24,
+                percent,
+            }],
+        };
+        let config = CancellationConfig {
+            providers: HashMap::from([(provider_id.to_string(), policy)]),
+            ..CancellationConfig::default()
+        };
+        OrderService::with_cancellation_config(storage, config)
+    }
+
+    #[tokio::test]
+    async fn test_cancel_charges_fee_and_frees_slot() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-8").await;
+        let order_service = create_service_with_policy(storage.clone(), "provider-8", 0, 20.0);
+        let quoted = create_quoted_order(&order_service, "provider-8").await;
+        order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+
+        // Only the consumer that initialized the order may cancel it
+        let result = order_service
+            .cancel(
+                &quoted.id,
+                CancellationReason::ScheduleConflict,
+                None,
+                &EventOrigin::new("eua-2", "cancel", "message-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+
+        let cancelled = order_service
+            .cancel(
+                &quoted.id,
+                CancellationReason::ScheduleConflict,
+                Some("Travelling".to_string()),
+                &origin("cancel"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(cancelled.state, OrderState::Cancelled);
+        let cancellation = cancelled.cancellation.unwrap();
+        assert_eq!(cancellation.reason, CancellationReason::ScheduleConflict);
+        assert_eq!(cancellation.cancelled_by, OrderActor::Consumer);
+        assert_eq!(cancellation.fee.unwrap().value, "100");
+
+        let refund = cancelled.payment.unwrap().refund.unwrap();
+        assert_eq!(refund.amount.value, "400");
+        assert_eq!(refund.status, "INITIATED");
+
+        // The fulfillment is cancelled and its slot can be booked again
+        let fulfillment = storage
+            .get_fulfillment(&cancelled.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            fulfillment.state.unwrap().descriptor,
+            FulfillmentState::Cancelled
+        );
+        assert_eq!(fulfillment.tags["state_change_reason"], "SCHEDULE_CONFLICT");
+
+        let other = create_quoted_order(&order_service, "provider-8").await;
+        assert!(order_service
+            .confirm(&other.id, paid(&other), &origin("confirm"))
+            .await
+            .is_ok());
+
+        // Cancelled orders cannot be cancelled again
+        let result = order_service
+            .cancel(
+                &quoted.id,
+                CancellationReason::Other,
+                None,
+                &origin("cancel"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_applies_cutoff_and_fee_waiver() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-9").await;
+
+        // Cancellations are refused inside the cutoff window
+        let order_service =
+            create_service_with_policy(storage.clone(), "provider-9", 8 * 24 * 60, 20.0);
+        let quoted = create_quoted_order(&order_service, "provider-9").await;
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+        let result = order_service
+            .cancel(
+                &confirmed.id,
+                CancellationReason::CustomerRequest,
+                None,
+                &origin("cancel"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        // Provider caused cancellations are refunded in full
+        let order_service = create_service_with_policy(storage.clone(), "provider-9", 0, 20.0);
+        let cancelled = order_service
+            .cancel(
+                &confirmed.id,
+                CancellationReason::ProviderUnavailable,
+                None,
+                &origin("cancel"),
+            )
+            .await
+            .unwrap();
+        assert!(cancelled.cancellation.unwrap().fee.is_none());
+        assert_eq!(
+            cancelled.payment.unwrap().refund.unwrap().amount.value,
+            "500"
+        );
+
+        // Draft orders are cancelled without a refund
+        let quoted = create_quoted_order(&order_service, "provider-9").await;
+        let cancelled = order_service
+            .cancel(
+                &quoted.id,
+                CancellationReason::DuplicateOrder,
+                None,
+                &origin("cancel"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(cancelled.state, OrderState::Cancelled);
+        assert!(cancelled.payment.unwrap().refund.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_on_cancel_handles_provider_cancellation() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-10").await;
+        let order_service = create_service_with_policy(storage.clone(), "provider-10", 0, 20.0);
+        let quoted = create_quoted_order(&order_service, "provider-10").await;
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+
+        // Only cancelled orders are accepted
+        let result = order_service
+            .on_cancel(
+                &confirmed.id,
+                "provider-10",
+                confirmed.clone(),
+                &origin("on_cancel"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Only the order's provider may cancel it, whatever the body says
+        let mut provider_order = confirmed.clone();
+        provider_order.state = OrderState::Cancelled;
+        let result = order_service
+            .on_cancel(
+                &confirmed.id,
+                "provider-x",
+                provider_order.clone(),
+                &origin("on_cancel"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // The provider cancels on its own, without a fee
+        let cancelled = order_service
+            .on_cancel(
+                &confirmed.id,
+                "provider-10",
+                provider_order.clone(),
+                &origin("on_cancel"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(cancelled.state, OrderState::Cancelled);
+        let cancellation = cancelled.cancellation.unwrap();
+        assert_eq!(cancellation.reason, CancellationReason::ProviderUnavailable);
+        assert_eq!(cancellation.cancelled_by, OrderActor::Provider);
+        assert_eq!(
+            cancelled.payment.unwrap().refund.unwrap().amount.value,
+            "500"
+        );
+
+        // The cancellation is recorded once
+        let events = storage.list_order_events(&confirmed.id).await.unwrap();
+        let cancellations: Vec<_> = events
+            .iter()
+            .filter(|event| event.entity == EventEntity::Order && event.action == "on_cancel")
+            .collect();
+        assert_eq!(cancellations.len(), 1);
+        assert_eq!(
+            cancellations[0].previous_state.as_deref(),
+            Some("CONFIRMED")
+        );
+
+        // The provider's refund record is adopted
+        let mut payment = provider_order.payment.clone().unwrap();
+        payment.refund = Some(Refund {
+            amount: payment.amount.clone().unwrap(),
+            status: "COMPLETED".to_string(),
+            initiated_at: Utc::now(),
+        });
+        provider_order.payment = Some(payment);
+        let reconciled = order_service
+            .on_cancel(
+                &confirmed.id,
+                "provider-10",
+                provider_order,
+                &origin("on_cancel"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            reconciled.payment.unwrap().refund.unwrap().status,
+            "COMPLETED"
+        );
+    }
+
+    // Helper function to build a new start slot for a fulfillment
+    fn start_at(timestamp: chrono::DateTime<Utc>) -> TimeSlot {
+        TimeSlot {
+            time: Time {
+                timestamp,
+                label: None,
+            },
+            duration: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_reschedules_confirmed_order() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-11").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-11").await;
+        let confirmed = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+        let booked_at = confirmed.fulfillment.start.time.timestamp;
+
+        let update = OrderUpdate::Fulfillment {
+            start: start_at(booked_at + chrono::Duration::hours(4)),
+        };
+        let rescheduled = order_service
+            .update(&quoted.id, update, &origin("update"))
+            .await
+            .unwrap();
+
+        let fulfillment = storage
+            .get_fulfillment(&rescheduled.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            fulfillment.start.time.timestamp,
+            booked_at + chrono::Duration::hours(4)
+        );
+        assert_eq!(
+            fulfillment.state.unwrap().descriptor,
+            FulfillmentState::Scheduled
+        );
+        assert_eq!(fulfillment.tags["rescheduled_from"], booked_at.to_rfc3339());
+
+        let change = &rescheduled.changes[0];
+        assert_eq!((change.version, change.target.as_str()), (1, "fulfillment"));
+        let previous_start = &change.previous["start"]["time"]["timestamp"];
+        assert_eq!(previous_start, &json!(booked_at));
+
+        // The old slot is free for other orders
+        let other = create_quoted_order(&order_service, "provider-11").await;
+        assert!(order_service
+            .confirm(&other.id, paid(&other), &origin("confirm"))
+            .await
+            .is_ok());
+
+        // Overlapping its own slot is fine, overlapping another booking is not
+        let update = OrderUpdate::Fulfillment {
+            start: start_at(booked_at + chrono::Duration::minutes(270)),
+        };
+        let rescheduled = order_service
+            .update(&quoted.id, update, &origin("update"))
+            .await
+            .unwrap();
+        assert_eq!(rescheduled.changes.len(), 2);
+
+        let update = OrderUpdate::Fulfillment {
+            start: start_at(booked_at),
+        };
+        let result = order_service
+            .update(&quoted.id, update, &origin("update"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_update_items_and_billing() {
+        use crate::models::catalog::SelectedItem;
+        use crate::models::order::OrderUpdateRequest;
+
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-12").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-12").await;
+
+        let request: OrderUpdateRequest = serde_json::from_value(json!({
+            "order_id": quoted.id,
+            "update_target": "items",
+            "items": [{"id": "item-1", "quantity": 3}]
+        }))
+        .unwrap();
+        let updated = order_service
+            .update(&request.order_id, request.update, &origin("update"))
+            .await
+            .unwrap();
+
+        // Changed items are re-quoted
+        assert_eq!(updated.items[0].quantity, 3);
+        assert_eq!(updated.quote.as_ref().unwrap().price.value, "1500");
+        let amount = updated.payment.as_ref().unwrap().amount.clone().unwrap();
+        assert_eq!(amount.value, "1500");
+        let previous_quote = &updated.changes[0].previous["quote"];
+        assert_eq!(previous_quote["price"]["value"], "500");
+
+        let mut billing = quoted.billing.clone();
+        billing.name = "Jane Doe".to_string();
+        let updated = order_service
+            .update(
+                &quoted.id,
+                OrderUpdate::Billing {
+                    billing: Box::new(billing),
                 },
-                duration: None,
-            },
-            customer: Some(Customer {
-                person: Person {
-                    name: "John Doe".to_string(),
-                    image: None,
-                    gender: Some("male".to_string()),
-                    creds: None,
-                    tags: None,
+                &origin("update"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(updated.billing.name, "Jane Doe");
+        let change = &updated.changes[1];
+        assert_eq!((change.version, change.target.as_str()), (2, "billing"));
+
+        let mut billing = quoted.billing.clone();
+        billing.phone = String::new();
+        let result = order_service
+            .update(
+                &quoted.id,
+                OrderUpdate::Billing {
+                    billing: Box::new(billing),
                 },
-                contact: HashMap::from([
-                    ("phone".to_string(), "1234567890".to_string()),
-                    ("email".to_string(), "john@example.com".to_string()),
-                ]),
-            }),
-            state: Some(State {
-                descriptor: state.to_string(),
-                updated_at: Utc::now(),
-            }),
-            tags: HashMap::new(),
+                &origin("update"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Items of a paid order cannot change
+        order_service
+            .confirm(&updated.id, paid(&updated), &origin("confirm"))
+            .await
+            .unwrap();
+        let update = OrderUpdate::Items {
+            items: vec![SelectedItem {
+                id: "item-1".to_string(),
+                quantity: 1,
+            }],
+        };
+        let result = order_service
+            .update(&quoted.id, update, &origin("update"))
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_updates_claim_distinct_versions() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-12b").await;
+        let order_service = Arc::new(OrderService::new(storage.clone()));
+        let quoted = create_quoted_order(&order_service, "provider-12b").await;
+
+        let billing_update = |name: &str| {
+            let mut billing = quoted.billing.clone();
+            billing.name = name.to_string();
+            OrderUpdate::Billing {
+                billing: Box::new(billing),
+            }
+        };
+
+        // Only the consumer that initialized the order may update it
+        let result = order_service
+            .update(
+                &quoted.id,
+                billing_update("Mallory"),
+                &EventOrigin::new("eua-2", "update", "message-1"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+
+        let handles: Vec<_> = (0..5)
+            .map(|n| {
+                let order_service = order_service.clone();
+                let (order_id, update) =
+                    (quoted.id.clone(), billing_update(&format!("Name {}", n)));
+                tokio::spawn(async move {
+                    order_service
+                        .update(&order_id, update, &origin("update"))
+                        .await
+                })
+            })
+            .collect();
+
+        let mut versions = Vec::new();
+        for handle in handles {
+            match handle.await.unwrap() {
+                Ok(order) => versions.push(order.changes.last().unwrap().version),
+                Err(ServiceError::BusinessLogic(_)) => {}
+                Err(err) => panic!("Unexpected error for concurrent update: {}", err),
+            }
         }
+
+        // Every stored change has its own version, and no accepted update is lost
+        versions.sort();
+        let stored = storage.get_order(&quoted.id).await.unwrap();
+        let stored_versions: Vec<_> = stored.changes.iter().map(|c| c.version).collect();
+        assert_eq!(versions, stored_versions);
+        assert_eq!(
+            stored_versions,
+            (1..=versions.len() as u32).collect::<Vec<_>>()
+        );
     }
 
     #[tokio::test]
-    async fn test_status_checking_flow() {
-        // Create memory storage
+    async fn test_track_records_provider_updates() {
+        use crate::models::tracking::{LocationUpdate, TrackingStatus};
+
         let storage = Arc::new(MemoryStorage::new());
-        
-        // Create a provider for testing
-        let provider_id = "provider-1";
-        let provider = crate::models::provider::Provider {
-            id: provider_id.to_string(),
-            descriptor: crate::models::provider::Descriptor {
-                name: "Test Provider".to_string(),
-                short_desc: None,
-                long_desc: None,
-                images: None,
-            },
-            categories: Vec::new(),
-            created_at: Utc::now(),
+        create_provider_with_catalog(&storage, "provider-14").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-14").await;
+
+        // Draft and quoted orders have nothing to track yet
+        let result = order_service.track(&quoted.id).await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let order = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+        let tracking = order_service.track(&order.id).await.unwrap();
+        assert_eq!(tracking.fulfillment_id, order.fulfillment.id);
+        assert_eq!(tracking.status, TrackingStatus::Inactive);
+
+        let update = |gps: &str, url: Option<&str>| Tracking {
+            fulfillment_id: order.fulfillment.id.clone(),
+            url: url.map(str::to_string),
+            status: TrackingStatus::Active,
+            locations: vec![LocationUpdate {
+                gps: gps.to_string(),
+                timestamp: Utc::now(),
+            }],
             updated_at: Utc::now(),
         };
-        let _ = storage.create_provider(provider).await.unwrap();
-        
-        // Create OrderService and FulfillmentService
+
+        order_service
+            .on_track(
+                &order.id,
+                "provider-14",
+                update("12.9716,77.5946", Some("https://hsp.example.com/track/1")),
+            )
+            .await
+            .unwrap();
+        order_service
+            .on_track(&order.id, "provider-14", update("12.9720,77.5950", None))
+            .await
+            .unwrap();
+
+        let tracking = order_service.track(&order.id).await.unwrap();
+        assert_eq!(tracking.status, TrackingStatus::Active);
+        assert_eq!(
+            tracking.url.as_deref(),
+            Some("https://hsp.example.com/track/1")
+        );
+        assert_eq!(tracking.locations.len(), 2);
+        assert_eq!(tracking.locations[1].gps, "12.9720,77.5950");
+
+        // Updates must come from the order's provider and carry valid data
+        let result = order_service
+            .on_track(&order.id, "provider-15", update("12.9,77.5", None))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let result = order_service
+            .on_track(&order.id, "provider-14", update("somewhere", None))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // Tracking ends with the fulfillment
+        order_service
+            .cancel(
+                &order.id,
+                CancellationReason::CustomerRequest,
+                None,
+                &origin("cancel"),
+            )
+            .await
+            .unwrap();
+        let tracking = order_service.track(&order.id).await.unwrap();
+        assert_eq!(tracking.status, TrackingStatus::Inactive);
+        let result = order_service
+            .on_track(&order.id, "provider-14", update("12.9,77.5", None))
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_support_returns_provider_contact() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-16").await;
         let order_service = OrderService::new(storage.clone());
-        let fulfillment_service = FulfillmentService::new(storage.clone());
-        
-        // Create a fulfillment
-        let fulfillment_id = "fulfillment-1";
-        let fulfillment = create_test_fulfillment(fulfillment_id, provider_id, "SCHEDULED");
-        let created_fulfillment = fulfillment_service.create_fulfillment(fulfillment).await.unwrap();
-        
-        // Create an order that references the fulfillment
-        let order_id = "order-1";
-        let order = create_test_order(order_id, provider_id, fulfillment_id);
-        let created_order = order_service.create_order(order).await.unwrap();
-        
-        // Check initial order status
-        let initial_status = order_service.status(order_id).await.unwrap();
-        assert_eq!(initial_status.state, "CONFIRMED"); // Should map from SCHEDULED to CONFIRMED
-        
-        // Update fulfillment state to IN_PROGRESS
-        let updated_fulfillment = fulfillment_service
-            .update_state(fulfillment_id, "IN_PROGRESS", None)
+        let order = create_quoted_order(&order_service, "provider-16").await;
+
+        let result = order_service.support(&order.id).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+
+        let contact = SupportContact {
+            phone: Some("+91-80-12345678".to_string()),
+            email: Some("support@hsp.example.com".to_string()),
+            url: None,
+        };
+        let mut provider = storage.get_provider("provider-16").await.unwrap();
+        provider.support = Some(contact.clone());
+        storage.update_provider(provider).await.unwrap();
+
+        assert_eq!(order_service.support(&order.id).await.unwrap(), contact);
+
+        assert!(order_service
+            .on_support(&order.id, "provider-16", &contact)
+            .await
+            .is_ok());
+        let result = order_service
+            .on_support(&order.id, "provider-16", &SupportContact::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let result = order_service
+            .on_support(&order.id, "provider-17", &contact)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_order_state_machine_rejects_illegal_transitions() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-18").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-18").await;
+        let order = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
             .await
             .unwrap();
-        assert_eq!(updated_fulfillment.state.unwrap().descriptor, "IN_PROGRESS");
-        
-        // Check that order status reflects the fulfillment status
-        let updated_status = order_service.status(order_id).await.unwrap();
-        assert_eq!(updated_status.state, "IN_PROGRESS");
-        
-        // Test on_status handler with a status update
-        let completed_status = OrderStatus {
-            state: "COMPLETED".to_string(),
+
+        // Every transition records who made it and why
+        let transitions: Vec<(&str, &str, OrderActor)> = order
+            .transitions
+            .iter()
+            .map(|t| (t.from.code(), t.to.code(), t.actor))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                ("INITIALIZED", "QUOTED", OrderActor::Provider),
+                ("QUOTED", "CONFIRMED", OrderActor::Consumer),
+            ]
+        );
+
+        let status = |state| OrderStatus {
+            state,
             updated_at: Utc::now(),
         };
-        
-        let updated_order = order_service.on_status(order_id, completed_status).await.unwrap();
-        assert_eq!(updated_order.state, "COMPLETED");
-        
-        // Verify that fulfillment was also updated
-        let final_fulfillment = fulfillment_service.get_fulfillment(fulfillment_id).await.unwrap();
-        assert_eq!(final_fulfillment.state.unwrap().descriptor, "COMPLETED");
+        for state in [
+            OrderState::Initialized,
+            OrderState::Quoted,
+            OrderState::Completed,
+        ] {
+            let result = order_service
+                .on_status(
+                    &order.id,
+                    "provider-18",
+                    status(state),
+                    &origin("on_status"),
+                )
+                .await;
+            assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+        }
+
+        // Only the order's provider may change its state
+        let result = order_service
+            .on_status(
+                &order.id,
+                "provider-99",
+                status(OrderState::InProgress),
+                &origin("on_status"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert_eq!(
+            order_service.get_order(&order.id).await.unwrap().state,
+            OrderState::Confirmed
+        );
+
+        let order = order_service
+            .on_status(
+                &order.id,
+                "provider-18",
+                status(OrderState::InProgress),
+                &origin("on_status"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(order.state, OrderState::InProgress);
+        let last = order.transitions.last().unwrap();
+        assert_eq!(last.actor, OrderActor::Provider);
+        assert_eq!(last.reason, "Status update from provider");
+
+        // Terminal states cannot be left
+        let order = order_service
+            .on_status(
+                &order.id,
+                "provider-18",
+                status(OrderState::Completed),
+                &origin("on_status"),
+            )
+            .await
+            .unwrap();
+        let result = order_service
+            .on_status(
+                &order.id,
+                "provider-18",
+                status(OrderState::Initialized),
+                &origin("on_status"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+        assert_eq!(
+            order_service.get_order(&order.id).await.unwrap().state,
+            OrderState::Completed
+        );
+
+        // Unknown states are rejected when the message is parsed
+        let parsed = serde_json::from_value::<OrderStatus>(json!({
+            "state": "WHATEVER",
+            "updated_at": Utc::now(),
+        }));
+        assert!(parsed.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_on_status_follows_fulfillment_lifecycle() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-20").await;
+        let order_service = OrderService::new(storage.clone());
+        let status = |state| OrderStatus {
+            state,
+            updated_at: Utc::now(),
+        };
+
+        let quoted = create_quoted_order(&order_service, "provider-20").await;
+        let order = order_service
+            .confirm(&quoted.id, paid(&quoted), &origin("confirm"))
+            .await
+            .unwrap();
+        let mut fulfillment = storage
+            .get_fulfillment(&order.fulfillment.id)
+            .await
+            .unwrap();
+        fulfillment.fulfillment_type = "home-visit".to_string();
+        storage.update_fulfillment(fulfillment).await.unwrap();
+
+        // A home visit cannot start before the doctor sets off
+        let result = order_service
+            .on_status(
+                &order.id,
+                "provider-20",
+                status(OrderState::InProgress),
+                &origin("on_status"),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+        assert_eq!(
+            order_service.get_order(&order.id).await.unwrap().state,
+            OrderState::Confirmed
+        );
+
+        // While pending, the doctor is on the way rather than in a waiting room
+        let order = order_service
+            .on_status(
+                &order.id,
+                "provider-20",
+                status(OrderState::FulfillmentPending),
+                &origin("on_status"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(order.state, OrderState::FulfillmentPending);
+        assert_eq!(
+            order.fulfillment.state.unwrap().descriptor,
+            FulfillmentState::EnRoute
+        );
+        let stored = storage
+            .get_fulfillment(&order.fulfillment.id)
+            .await
+            .unwrap();
+        assert_eq!(stored.state.unwrap().descriptor, FulfillmentState::EnRoute);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_records_order_and_fulfillment_changes() {
+        let storage = Arc::new(MemoryStorage::new());
+        create_provider_with_catalog(&storage, "provider-19").await;
+        let order_service = OrderService::new(storage.clone());
+        let quoted = create_quoted_order(&order_service, "provider-19").await;
+        let confirm = origin("confirm");
+        order_service
+            .confirm(&quoted.id, paid(&quoted), &confirm)
+            .await
+            .unwrap();
+        let order = order_service
+            .on_status(
+                &quoted.id,
+                "provider-19",
+                OrderStatus {
+                    state: OrderState::InProgress,
+                    updated_at: Utc::now(),
+                },
+                &origin("on_status"),
+            )
+            .await
+            .unwrap();
+
+        let timeline = order_service.timeline(&order.id).await.unwrap();
+        assert_eq!(timeline.provider_id, "provider-19");
+        let events: Vec<_> = timeline
+            .events
+            .iter()
+            .map(|event| {
+                (
+                    event.entity,
+                    event.action.as_str(),
+                    event.previous_state.as_deref(),
+                    event.new_state.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (EventEntity::Order, "init", None, Some("INITIALIZED")),
+                (
+                    EventEntity::Order,
+                    "quote",
+                    Some("INITIALIZED"),
+                    Some("QUOTED")
+                ),
+                (
+                    EventEntity::Order,
+                    "confirm",
+                    Some("QUOTED"),
+                    Some("CONFIRMED")
+                ),
+                (EventEntity::Fulfillment, "confirm", None, Some("SCHEDULED")),
+                (
+                    EventEntity::Order,
+                    "on_status",
+                    Some("CONFIRMED"),
+                    Some("IN_PROGRESS")
+                ),
+                (
+                    EventEntity::Fulfillment,
+                    "on_status",
+                    Some("SCHEDULED"),
+                    Some("IN_PROGRESS")
+                ),
+            ]
+        );
+
+        // Events carry the sender and message that caused them
+        let booked = &timeline.events[3];
+        assert_eq!(booked.entity_id, order.fulfillment.id);
+        assert_eq!(booked.actor, "eua-1");
+        assert_eq!(booked.message_id, confirm.message_id);
+        assert_eq!(timeline.events[4].actor, "hsp-1");
+        assert!(timeline.involves("eua-1"));
+        assert!(timeline.involves("provider-19"));
+        assert!(!timeline.involves("eua-2"));
+
+        let result = order_service.timeline("order-404").await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
     }
 }
@@ -433,6 +433,7 @@ mod tests {
                 images: None,
             },
             categories: Vec::new(),
+            support: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
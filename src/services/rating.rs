@@ -0,0 +1,468 @@
+use super::error::ServiceError;
+use super::fulfillment::FulfillmentService;
+use crate::models::fulfillment::FulfillmentState;
+use crate::models::history::EventEntity;
+use crate::models::order::Order;
+use crate::models::rating::{AggregateRating, Rating, RatingCategory, RatingResponse};
+use crate::storage::{Storage, StorageError};
+use std::sync::Arc;
+
+/// Score of entities without ratings, midway on the 1 to 5 scale
+pub const NEUTRAL_SCORE: f64 = 3.0;
+
+/// Weight of the neutral score in ranking scores, in number of ratings, so
+/// that a few ratings cannot dominate the ranking
+const PRIOR_WEIGHT: f64 = 5.0;
+
+/// Rating service collecting patient feedback on completed orders
+pub struct RatingService {
+    /// Storage implementation injected via constructor
+    storage: Arc<dyn Storage>,
+    /// Fulfillment service for checking that orders were fulfilled
+    fulfillment_service: FulfillmentService,
+}
+
+impl RatingService {
+    /// Create a new rating service with storage dependency
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self {
+            fulfillment_service: FulfillmentService::new(storage.clone()),
+            storage,
+        }
+    }
+
+    /// Rate a completed order and the fulfillment, agent and provider
+    /// involved in it (rating), returning the updated aggregates.
+    ///
+    /// Only the consumer that placed the order may rate it, and each entity
+    /// can be rated once per order.
+    pub async fn rate(
+        &self,
+        order_id: &str,
+        ratings: Vec<Rating>,
+        rater_id: &str,
+    ) -> Result<Vec<AggregateRating>, ServiceError> {
+        let order = self.storage.get_order(order_id).await?;
+
+        if self.consumer_of(order_id).await?.as_deref() != Some(rater_id) {
+            return Err(ServiceError::Validation(format!(
+                "Order {} was not placed by {}",
+                order_id, rater_id
+            )));
+        }
+
+        if !self.is_completed(&order).await? {
+            return Err(ServiceError::BusinessLogic(format!(
+                "Order {} can only be rated once its fulfillment is COMPLETED",
+                order_id
+            )));
+        }
+
+        if ratings.is_empty() {
+            return Err(ServiceError::Validation(
+                "At least one rating is required".to_string(),
+            ));
+        }
+
+        for (index, rating) in ratings.iter().enumerate() {
+            if !(1..=5).contains(&rating.value) {
+                return Err(ServiceError::Validation(format!(
+                    "Rating value must be between 1 and 5, got {}",
+                    rating.value
+                )));
+            }
+
+            if !rates_order_entity(&order, rating) {
+                return Err(ServiceError::Validation(format!(
+                    "{} {} is not part of order {}",
+                    rating.rating_category.code(),
+                    rating.id,
+                    order_id
+                )));
+            }
+
+            if ratings[..index].iter().any(|r| same_target(r, rating)) {
+                return Err(ServiceError::Validation(format!(
+                    "{} {} is rated more than once",
+                    rating.rating_category.code(),
+                    rating.id
+                )));
+            }
+        }
+
+        match self.storage.record_ratings(order_id, ratings).await {
+            Ok(aggregates) => Ok(aggregates),
+            Err(StorageError::Duplicate(msg)) => Err(ServiceError::BusinessLogic(msg)),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Handle provider's rating response (on_rating)
+    pub async fn on_rating(
+        &self,
+        provider_id: &str,
+        response: &RatingResponse,
+    ) -> Result<(), ServiceError> {
+        let order = self.storage.get_order(&response.order_id).await?;
+
+        if order.provider.id != provider_id {
+            return Err(ServiceError::Validation("Provider ID mismatch".to_string()));
+        }
+
+        tracing::info!(
+            "Provider {} acknowledged {} ratings for order {}",
+            provider_id,
+            response.ratings.len(),
+            response.order_id
+        );
+        Ok(())
+    }
+
+    /// Aggregated rating of an entity
+    pub async fn aggregate(
+        &self,
+        category: RatingCategory,
+        id: &str,
+    ) -> Result<AggregateRating, ServiceError> {
+        Ok(self.storage.get_rating_aggregate(category, id).await?)
+    }
+
+    /// Ranking score of a provider: its average rating, weighted towards
+    /// `NEUTRAL_SCORE` while it has few ratings
+    pub async fn provider_score(&self, provider_id: &str) -> Result<f64, ServiceError> {
+        let score = match self
+            .storage
+            .get_rating_aggregate(RatingCategory::Provider, provider_id)
+            .await
+        {
+            Ok(aggregate) => {
+                let count = aggregate.count as f64;
+                (aggregate.average * count + NEUTRAL_SCORE * PRIOR_WEIGHT) / (count + PRIOR_WEIGHT)
+            }
+            Err(StorageError::NotFound(_)) => NEUTRAL_SCORE,
+            Err(err) => return Err(err.into()),
+        };
+        Ok(score)
+    }
+
+    /// Subscriber that placed an order, as recorded in its history
+    async fn consumer_of(&self, order_id: &str) -> Result<Option<String>, ServiceError> {
+        let events = self.storage.list_order_events(order_id).await?;
+        Ok(events
+            .into_iter()
+            .find(|event| event.entity == EventEntity::Order && event.previous_state.is_none())
+            .map(|event| event.actor))
+    }
+
+    /// Whether the order's stored fulfillment has been completed; the state
+    /// reported in the order itself comes from the provider and is not trusted
+    async fn is_completed(&self, order: &Order) -> Result<bool, ServiceError> {
+        let state = match self
+            .fulfillment_service
+            .get_fulfillment(&order.fulfillment.id)
+            .await
+        {
+            Ok(fulfillment) => fulfillment.state,
+            Err(ServiceError::NotFound(_)) => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(state.is_some_and(|state| state.descriptor == FulfillmentState::Completed))
+    }
+}
+
+/// Whether a rating refers to the order itself or an entity involved in it
+fn rates_order_entity(order: &Order, rating: &Rating) -> bool {
+    match rating.rating_category {
+        RatingCategory::Order => rating.id == order.id,
+        RatingCategory::Fulfillment => rating.id == order.fulfillment.id,
+        RatingCategory::Agent => order
+            .fulfillment
+            .agent
+            .as_ref()
+            .is_some_and(|agent| agent.id == rating.id),
+        RatingCategory::Provider => rating.id == order.provider.id,
+    }
+}
+
+fn same_target(a: &Rating, b: &Rating) -> bool {
+    a.rating_category == b.rating_category && a.id == b.id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::billing::{Address, Billing};
+    use crate::models::fulfillment::{Agent, Fulfillment, State, Time, TimeSlot};
+    use crate::models::history::OrderEvent;
+    use crate::models::order::{OrderState, ProviderSummary};
+    use crate::storage::memory::MemoryStorage;
+    use chrono::Utc;
+    use std::collections::HashMap;
+
+    fn create_fulfillment(state: FulfillmentState) -> Fulfillment {
+        Fulfillment {
+            id: "fulfillment-1".to_string(),
+            fulfillment_type: "Teleconsultation".to_string(),
+            provider_id: "provider-1".to_string(),
+            agent: Some(Agent {
+                id: "agent-1".to_string(),
+                name: "Dr. Smith".to_string(),
+                gender: None,
+                image: None,
+                tags: HashMap::new(),
+            }),
+            start: TimeSlot {
+                time: Time {
+                    timestamp: Utc::now(),
+                    label: None,
+                },
+                duration: Some(3600),
+            },
+            end: TimeSlot {
+                time: Time {
+                    timestamp: Utc::now() + chrono::Duration::hours(1),
+                    label: None,
+                },
+                duration: None,
+            },
+            customer: None,
+            state: Some(State {
+                descriptor: state,
+                updated_at: Utc::now(),
+            }),
+            tags: HashMap::new(),
+        }
+    }
+
+    async fn create_order(
+        storage: &Arc<dyn Storage>,
+        order_id: &str,
+        state: FulfillmentState,
+    ) -> Order {
+        let fulfillment = create_fulfillment(state);
+        let order = Order {
+            id: order_id.to_string(),
+            consumer_id: "eua-1".to_string(),
+            provider: ProviderSummary {
+                id: "provider-1".to_string(),
+                descriptor: "Test Provider".to_string(),
+                categories: Vec::new(),
+            },
+            items: Vec::new(),
+            billing: Billing {
+                name: "John Doe".to_string(),
+                organization: None,
+                address: Address {
+                    door: None,
+                    building: None,
+                    street: None,
+                    locality: None,
+                    city: "Bangalore".to_string(),
+                    state: "Karnataka".to_string(),
+                    country: "IND".to_string(),
+                    area_code: "560001".to_string(),
+                },
+                email: None,
+                phone: "1234567890".to_string(),
+                tax_number: None,
+            },
+            fulfillment: fulfillment.clone(),
+            quote: None,
+            payment: None,
+            state: OrderState::Confirmed,
+            transitions: Vec::new(),
+            cancellation: None,
+            changes: Vec::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        if storage.get_fulfillment(&fulfillment.id).await.is_err() {
+            storage.create_fulfillment(fulfillment).await.unwrap();
+        }
+        storage
+            .append_order_event(OrderEvent {
+                order_id: order_id.to_string(),
+                entity: EventEntity::Order,
+                entity_id: order_id.to_string(),
+                actor: "eua-1".to_string(),
+                action: "init".to_string(),
+                previous_state: None,
+                new_state: Some("INITIALIZED".to_string()),
+                message_id: format!("message-{}", order_id),
+                recorded_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        storage.create_order(order).await.unwrap()
+    }
+
+    fn rating(category: RatingCategory, id: &str, value: u8) -> Rating {
+        Rating {
+            rating_category: category,
+            id: id.to_string(),
+            value,
+            feedback: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_requires_completed_fulfillment() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = RatingService::new(storage.clone());
+        create_order(&storage, "order-1", FulfillmentState::Scheduled).await;
+
+        let result = service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Order, "order-1", 5)],
+                "eua-1",
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rate_validates_ratings() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = RatingService::new(storage.clone());
+        create_order(&storage, "order-1", FulfillmentState::Completed).await;
+
+        for ratings in [
+            vec![],
+            vec![rating(RatingCategory::Order, "order-1", 0)],
+            vec![rating(RatingCategory::Order, "order-1", 6)],
+            vec![rating(RatingCategory::Provider, "provider-2", 4)],
+            vec![rating(RatingCategory::Agent, "agent-2", 4)],
+            vec![
+                rating(RatingCategory::Agent, "agent-1", 4),
+                rating(RatingCategory::Agent, "agent-1", 5),
+            ],
+        ] {
+            let result = service.rate("order-1", ratings, "eua-1").await;
+            assert!(matches!(result, Err(ServiceError::Validation(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_aggregates_ratings_once_per_order() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = RatingService::new(storage.clone());
+        create_order(&storage, "order-1", FulfillmentState::Completed).await;
+        create_order(&storage, "order-2", FulfillmentState::Completed).await;
+
+        let aggregates = service
+            .rate(
+                "order-1",
+                vec![
+                    rating(RatingCategory::Provider, "provider-1", 5),
+                    rating(RatingCategory::Agent, "agent-1", 4),
+                ],
+                "eua-1",
+            )
+            .await
+            .unwrap();
+        assert_eq!(aggregates.len(), 2);
+        assert_eq!(aggregates[0].count, 1);
+        assert_eq!(aggregates[0].average, 5.0);
+
+        // The same provider can only be rated once for an order
+        let result = service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Provider, "provider-1", 1)],
+                "eua-1",
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        service
+            .rate(
+                "order-2",
+                vec![rating(RatingCategory::Provider, "provider-1", 2)],
+                "eua-1",
+            )
+            .await
+            .unwrap();
+
+        let aggregate = service
+            .aggregate(RatingCategory::Provider, "provider-1")
+            .await
+            .unwrap();
+        assert_eq!(aggregate.count, 2);
+        assert_eq!(aggregate.average, 3.5);
+
+        let agent = service
+            .aggregate(RatingCategory::Agent, "agent-1")
+            .await
+            .unwrap();
+        assert_eq!(agent.count, 1);
+        let result = service.aggregate(RatingCategory::Order, "order-2").await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_rate_requires_consumer_and_stored_completion() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = RatingService::new(storage.clone());
+        let mut order = create_order(&storage, "order-1", FulfillmentState::Scheduled).await;
+
+        // The state reported in the order is not trusted
+        order.fulfillment = create_fulfillment(FulfillmentState::Completed);
+        storage.update_order(order).await.unwrap();
+        let result = service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Order, "order-1", 5)],
+                "eua-1",
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        // Only the consumer that placed the order may rate it
+        let completed = create_fulfillment(FulfillmentState::Completed);
+        storage.update_fulfillment(completed).await.unwrap();
+        let result = service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Order, "order-1", 5)],
+                "eua-2",
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Order, "order-1", 5)],
+                "eua-1",
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_provider_score_weights_towards_neutral() {
+        let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+        let service = RatingService::new(storage.clone());
+        create_order(&storage, "order-1", FulfillmentState::Completed).await;
+
+        assert_eq!(
+            service.provider_score("provider-1").await.unwrap(),
+            NEUTRAL_SCORE
+        );
+
+        service
+            .rate(
+                "order-1",
+                vec![rating(RatingCategory::Provider, "provider-1", 5)],
+                "eua-1",
+            )
+            .await
+            .unwrap();
+
+        let score = service.provider_score("provider-1").await.unwrap();
+        assert!(score > NEUTRAL_SCORE && score < 5.0);
+    }
+}
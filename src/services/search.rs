@@ -1,15 +1,31 @@
 use chrono::{DateTime, Utc};
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 use uuid::Uuid;
 
+use super::callback::CallbackDispatcher;
 use super::error::ServiceError;
+use super::network_registry::NetworkRegistryService;
 use super::provider::ProviderService;
-use crate::models::catalog::{SearchRequest, SearchResponse};
+use super::rating::RatingService;
+use crate::auth::signer::RequestSigner;
+use crate::config::GatewayConfig;
+use crate::models::ack::{AckResponse, AckStatus};
+use crate::models::catalog::{Item, SearchRequest, SearchResponse};
+use crate::models::context::{Context, Envelope};
+use crate::models::network_registry::{LookupRequest, Participant};
+use crate::storage::search::{merge_into, provider_of, tag_provider};
 use crate::storage::Storage;
 
+/// Prefix for transaction store keys holding search transactions
+pub const SEARCH_PREFIX: &str = "search:";
+
 /// Search metadata for tracking search transactions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMetadata {
@@ -25,8 +41,31 @@ pub struct SearchMetadata {
     /// List of providers to which the search was forwarded
     pub forwarded_to: Vec<String>,
 
-    /// Responses received from providers
+    /// Providers the search was sent to; only their responses are accepted
+    #[serde(default)]
+    pub targeted: Vec<String>,
+
+    /// Responses received from providers. They are stored apart, one per
+    /// provider, and loaded by `get_search_transaction`.
+    #[serde(skip)]
     pub responses: HashMap<String, SearchResponse>,
+
+    /// Context of the EUA request the responses are relayed to
+    #[serde(default)]
+    pub context: Option<Context>,
+
+    /// Time at which the search window closes
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// How provider responses are relayed to the EUA
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RelayMode {
+    /// Relay each provider's catalog as soon as it arrives
+    Individual,
+    /// Relay a single merged catalog once the search window closes
+    Merged,
 }
 
 /// Search service for handling healthcare service discovery
@@ -35,6 +74,20 @@ pub struct SearchService {
     storage: Arc<dyn Storage>,
     /// Provider service for filtering providers
     provider_service: ProviderService,
+    /// Rating service supplying provider scores for ranking results
+    ratings: RatingService,
+    /// Registry used to find the HSPs a search is forwarded to
+    registry: NetworkRegistryService,
+    /// HTTP client for forwarding searches
+    http_client: Client,
+    /// Signer attaching the gateway's signature to forwarded searches
+    signer: Arc<RequestSigner>,
+    /// Dispatcher relaying results to the EUA
+    dispatcher: Arc<CallbackDispatcher>,
+    /// Identity HSPs send their on_search responses to
+    gateway: GatewayConfig,
+    /// Bounds the number of in-flight forwarded searches
+    forward_permits: Arc<Semaphore>,
     /// Configuration parameters
     config: SearchServiceConfig,
 }
@@ -47,8 +100,12 @@ pub struct SearchServiceConfig {
     pub max_providers_per_search: usize,
     /// Minimum providers that must respond for valid results
     pub min_providers_for_results: usize,
-    /// Maximum number of concurrent searches
+    /// Maximum number of concurrent searches forwarded to providers
     pub concurrent_search_limit: usize,
+    /// Timeout for forwarding a search to a single provider (in seconds)
+    pub forward_timeout: u64,
+    /// How provider responses are relayed to the EUA
+    pub relay_mode: RelayMode,
 }
 
 impl Default for SearchServiceConfig {
@@ -58,32 +115,99 @@ impl Default for SearchServiceConfig {
             max_providers_per_search: 10,
             min_providers_for_results: 1,
             concurrent_search_limit: 100,
+            forward_timeout: 5,
+            relay_mode: RelayMode::Merged,
         }
     }
 }
 
 impl SearchService {
     /// Create a new search service with storage dependency
-    pub fn new(storage: Arc<dyn Storage>) -> Self {
-        let provider_service = ProviderService::new(storage.clone());
-        Self {
+    pub fn new(
+        storage: Arc<dyn Storage>,
+        signer: Arc<RequestSigner>,
+        dispatcher: Arc<CallbackDispatcher>,
+        gateway: GatewayConfig,
+    ) -> Result<Self, ServiceError> {
+        Self::with_config(
             storage,
-            provider_service,
-            config: SearchServiceConfig::default(),
-        }
+            signer,
+            dispatcher,
+            gateway,
+            SearchServiceConfig::default(),
+        )
     }
 
-    /// Create a new search service with custom configuration
-    pub fn with_config(storage: Arc<dyn Storage>, config: SearchServiceConfig) -> Self {
+    /// Create a new search service with custom configuration, failing if
+    /// the HTTP client cannot be built with the configured timeout
+    pub fn with_config(
+        storage: Arc<dyn Storage>,
+        signer: Arc<RequestSigner>,
+        dispatcher: Arc<CallbackDispatcher>,
+        gateway: GatewayConfig,
+        config: SearchServiceConfig,
+    ) -> Result<Self, ServiceError> {
         let provider_service = ProviderService::new(storage.clone());
-        Self {
+        let ratings = RatingService::new(storage.clone());
+        let registry = NetworkRegistryService::new(storage.clone())?;
+        let http_client = Client::builder()
+            .timeout(Duration::from_secs(config.forward_timeout))
+            .build()
+            .map_err(|e| {
+                ServiceError::Internal(format!("Failed to build search HTTP client: {}", e))
+            })?;
+
+        Ok(Self {
             storage,
             provider_service,
+            ratings,
+            registry,
+            http_client,
+            signer,
+            dispatcher,
+            gateway,
+            forward_permits: Arc::new(Semaphore::new(config.concurrent_search_limit.max(1))),
             config,
+        })
+    }
+
+    /// Broadcast a search to the HSPs registered for its domain and relay
+    /// their catalogs to the EUA.
+    ///
+    /// Responses are collected until `search_timeout` elapses. When no HSP is
+    /// registered for the domain the search is answered from the local
+    /// catalog instead.
+    pub async fn search_network(
+        &self,
+        context: Context,
+        request: SearchRequest,
+    ) -> Result<(), ServiceError> {
+        match self.forward_search(&context, &request).await {
+            Ok(Some(expires_at)) => {
+                let window = (expires_at - Utc::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(window).await;
+                self.complete_search(&context.transaction_id).await
+            }
+            Ok(None) => {
+                let outcome = self.search(request).await;
+                self.dispatcher.send(&context, "on_search", outcome).await?;
+                Ok(())
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Search for transaction {} failed: {}",
+                    context.transaction_id,
+                    err
+                );
+                self.dispatcher
+                    .send::<SearchResponse>(&context, "on_search", Err(err))
+                    .await?;
+                Ok(())
+            }
         }
     }
 
-    /// Process a search request
+    /// Process a search request against the locally stored catalogs
     pub async fn search(&self, request: SearchRequest) -> Result<SearchResponse, ServiceError> {
         // Validate search request
         self.validate_search_request(&request)?;
@@ -97,7 +221,10 @@ impl SearchService {
             timestamp: Utc::now(),
             request: request.clone(),
             forwarded_to: Vec::new(),
+            targeted: Vec::new(),
             responses: HashMap::new(),
+            context: None,
+            expires_at: None,
         };
 
         // Track this search transaction
@@ -113,35 +240,81 @@ impl SearchService {
             ));
         }
 
-        // For now, since we don't have actual provider forwarding logic,
-        // we'll just use the storage's search_catalog method
-        let response = self.storage.search_catalog(request).await?;
-
-        // In a complete implementation, we would:
-        // 1. Forward the search to each provider (limited by max_providers_per_search)
-        // 2. Wait for responses or until timeout
-        // 3. Aggregate and filter results
-        // 4. Update the transaction record with responses
+        // Answer from the catalogs stored by the gateway; network searches
+        // are fanned out to HSPs by search_network
+        let mut response = self.storage.search_catalog(request).await?;
+        self.rank_items(&mut response.catalog.items).await?;
 
         Ok(response)
     }
 
-    /// Forward search results back to the requesting EUA
+    /// Record a provider's search results for its transaction, relaying them
+    /// to the EUA straight away when responses are relayed individually.
+    ///
+    /// Responses for unknown transactions, from providers the search was not
+    /// sent to, or arriving after the search window has closed, are rejected.
     pub async fn on_search(
         &self,
-        provider_id: &str,
-        _response: SearchResponse,
+        context: &Context,
+        response: SearchResponse,
     ) -> Result<(), ServiceError> {
-        // Validate the provider exists
-        self.provider_service.get_provider(provider_id).await?;
+        let provider_id = context.provider_id.clone().unwrap_or_default();
+
+        let metadata = self
+            .open_search_transaction(&context.transaction_id)
+            .await?;
+        if !metadata.targeted.contains(&provider_id) {
+            return Err(ServiceError::Validation(format!(
+                "Search {} was not sent to {}",
+                context.transaction_id, provider_id
+            )));
+        }
+        let request_context = metadata.context.ok_or_else(|| {
+            ServiceError::Internal("Search transaction has no request context".to_string())
+        })?;
+
+        // Each provider's response is a record of its own, so concurrent
+        // responses are all kept
+        self.storage
+            .record_search_response(
+                &context.transaction_id,
+                &request_context.message_id,
+                &provider_id,
+                response.clone(),
+            )
+            .await?;
+
+        tracing::info!(
+            "Collected on_search from {} for transaction {}",
+            provider_id,
+            context.transaction_id
+        );
+
+        if self.config.relay_mode == RelayMode::Individual {
+            // Relay under the responding provider's identity
+            let envelope = Envelope {
+                context: Context {
+                    provider_id: context.provider_id.clone(),
+                    provider_uri: context.provider_uri.clone(),
+                    ..self
+                        .dispatcher
+                        .response_context(&request_context, "on_search")
+                },
+                message: serde_json::to_value(response).map_err(|e| {
+                    ServiceError::Internal(format!("Failed to serialize search results: {}", e))
+                })?,
+                error: None,
+            };
 
-        // In a real implementation, this would:
-        // 1. Find the transaction associated with this search
-        // 2. Update the transaction with this provider's response
-        // 3. Check if we have all expected responses or hit the timeout
-        // 4. Merge and forward final results if appropriate
+            // Delivery failures are retried and dead-lettered by the dispatcher
+            let dispatcher = self.dispatcher.clone();
+            tokio::spawn(async move {
+                let _ = dispatcher
+                    .dispatch(&request_context.consumer_uri, &envelope)
+                    .await;
+            });
+        }
 
-        // For now, just a placeholder
         Ok(())
     }
 
@@ -156,7 +329,7 @@ impl SearchService {
 
         // Record the transaction in storage
         self.storage
-            .record_transaction(transaction_id, data)
+            .record_transaction(&format!("{}{}", SEARCH_PREFIX, transaction_id), data)
             .await?;
 
         Ok(())
@@ -168,16 +341,257 @@ impl SearchService {
         transaction_id: &str,
     ) -> Result<SearchMetadata, ServiceError> {
         // Get the transaction data from storage
-        let data = self.storage.get_transaction(transaction_id).await?;
+        let data = self
+            .storage
+            .get_transaction(&format!("{}{}", SEARCH_PREFIX, transaction_id))
+            .await?;
 
         // Convert JSON to SearchMetadata
-        let metadata: SearchMetadata = serde_json::from_value(data).map_err(|e| {
+        let mut metadata: SearchMetadata = serde_json::from_value(data).map_err(|e| {
             ServiceError::Internal(format!("Failed to deserialize search metadata: {}", e))
         })?;
 
+        // Network searches collect responses under their request message
+        if let Some(context) = &metadata.context {
+            metadata.responses = self
+                .storage
+                .list_search_responses(transaction_id, &context.message_id)
+                .await?
+                .into_iter()
+                .collect();
+        }
+
         Ok(metadata)
     }
 
+    /// Retrieve a search transaction that is still collecting responses
+    async fn open_search_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> Result<SearchMetadata, ServiceError> {
+        let metadata = match self.get_search_transaction(transaction_id).await {
+            Err(ServiceError::NotFound(_)) => {
+                return Err(ServiceError::NotFound(format!(
+                    "Unknown search transaction {}",
+                    transaction_id
+                )))
+            }
+            result => result?,
+        };
+
+        // Only network searches collect responses, until their window closes
+        match metadata.expires_at {
+            Some(expires_at) if expires_at > Utc::now() => Ok(metadata),
+            Some(_) => Err(ServiceError::BusinessLogic(format!(
+                "Search window for transaction {} has closed",
+                transaction_id
+            ))),
+            None => Err(ServiceError::NotFound(format!(
+                "Unknown search transaction {}",
+                transaction_id
+            ))),
+        }
+    }
+
+    /// Record the search transaction and forward it to the matching HSPs,
+    /// returning when the search window closes, or `None` if no HSP matched
+    async fn forward_search(
+        &self,
+        context: &Context,
+        request: &SearchRequest,
+    ) -> Result<Option<DateTime<Utc>>, ServiceError> {
+        self.validate_search_request(request)?;
+
+        let subscribers = self.identify_subscribers(context).await?;
+        if subscribers.is_empty() {
+            return Ok(None);
+        }
+
+        // Record the transaction first so early responses can be collected
+        let expires_at = Utc::now() + chrono::Duration::seconds(self.config.search_timeout as i64);
+        let metadata = SearchMetadata {
+            transaction_id: context.transaction_id.clone(),
+            timestamp: Utc::now(),
+            request: request.clone(),
+            forwarded_to: Vec::new(),
+            targeted: subscribers
+                .iter()
+                .map(|subscriber| subscriber.subscriber_id.clone())
+                .collect(),
+            responses: HashMap::new(),
+            context: Some(context.clone()),
+            expires_at: Some(expires_at),
+        };
+        self.check_transaction_available(context).await?;
+        self.track_search_transaction(&context.transaction_id, metadata)
+            .await?;
+
+        let forwarded = self.broadcast(context, request, subscribers).await?;
+        if forwarded.is_empty() {
+            return Err(ServiceError::ExternalService(
+                "No provider accepted the search".to_string(),
+            ));
+        }
+
+        // Responses are stored apart, so this update cannot drop any
+        let mut metadata = self.get_search_transaction(&context.transaction_id).await?;
+        metadata.forwarded_to = forwarded;
+        self.track_search_transaction(&context.transaction_id, metadata)
+            .await?;
+
+        Ok(Some(expires_at))
+    }
+
+    /// Check that a consumer may start a network search under its
+    /// transaction ID: transactions of other consumers, and searches still
+    /// collecting responses, cannot be taken over
+    async fn check_transaction_available(&self, context: &Context) -> Result<(), ServiceError> {
+        let existing = match self.get_search_transaction(&context.transaction_id).await {
+            Ok(existing) => existing,
+            Err(ServiceError::NotFound(_)) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+
+        let owner = existing
+            .context
+            .as_ref()
+            .map(|owner| owner.consumer_id.as_str());
+        if owner != Some(context.consumer_id.as_str()) {
+            return Err(ServiceError::Validation(format!(
+                "Transaction {} belongs to another consumer",
+                context.transaction_id
+            )));
+        }
+        if existing
+            .expires_at
+            .is_some_and(|expires_at| expires_at > Utc::now())
+        {
+            return Err(ServiceError::BusinessLogic(format!(
+                "A search for transaction {} is still collecting responses",
+                context.transaction_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Forward a signed search to each subscriber, with at most
+    /// `concurrent_search_limit` requests in flight, returning the IDs of the
+    /// subscribers that acknowledged it
+    async fn broadcast(
+        &self,
+        context: &Context,
+        request: &SearchRequest,
+        subscribers: Vec<Participant>,
+    ) -> Result<Vec<String>, ServiceError> {
+        // HSPs respond to the gateway, which relays to the EUA
+        let envelope = Envelope {
+            context: Context {
+                consumer_id: self.gateway.subscriber_id.clone(),
+                consumer_uri: self.gateway.subscriber_url.clone(),
+                ..context.clone()
+            },
+            message: request.clone(),
+            error: None,
+        };
+        let body = serde_json::to_vec(&envelope)
+            .map_err(|e| ServiceError::Internal(format!("Failed to serialize search: {}", e)))?;
+
+        let mut tasks = JoinSet::new();
+        for subscriber in subscribers {
+            let permits = self.forward_permits.clone();
+            let client = self.http_client.clone();
+            let signer = self.signer.clone();
+            let body = body.clone();
+
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let url = format!("{}/search", subscriber.url.trim_end_matches('/'));
+                let result = forward(&client, &signer, &url, body).await;
+                (subscriber.subscriber_id, url, result)
+            });
+        }
+
+        let mut forwarded = Vec::new();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((subscriber_id, _, Ok(()))) => forwarded.push(subscriber_id),
+                Ok((subscriber_id, url, Err(err))) => {
+                    tracing::warn!(
+                        "Failed to forward search {} to {} at {}: {}",
+                        context.transaction_id,
+                        subscriber_id,
+                        url,
+                        err
+                    );
+                }
+                Err(err) => tracing::error!("Search forwarding task failed: {}", err),
+            }
+        }
+
+        Ok(forwarded)
+    }
+
+    /// Close the search window, relaying the merged results to the EUA
+    async fn complete_search(&self, transaction_id: &str) -> Result<(), ServiceError> {
+        let metadata = self.get_search_transaction(transaction_id).await?;
+        let context = metadata.context.clone().ok_or_else(|| {
+            ServiceError::Internal("Search transaction has no request context".to_string())
+        })?;
+
+        tracing::info!(
+            "Search window for transaction {} closed with {} of {} responses",
+            transaction_id,
+            metadata.responses.len(),
+            metadata.forwarded_to.len()
+        );
+
+        let outcome = match self.config.relay_mode {
+            RelayMode::Merged
+                if metadata.responses.len() < self.config.min_providers_for_results =>
+            {
+                Err(ServiceError::NotFound(format!(
+                    "Only {} of the required {} providers responded",
+                    metadata.responses.len(),
+                    self.config.min_providers_for_results
+                )))
+            }
+            RelayMode::Merged => self
+                .rank_responses(&metadata.responses)
+                .await
+                .and_then(|ranked| self.merge_search_results(ranked)),
+            // Results have already been relayed as they arrived
+            RelayMode::Individual if !metadata.responses.is_empty() => return Ok(()),
+            RelayMode::Individual => Err(ServiceError::NotFound(
+                "No provider responded to the search".to_string(),
+            )),
+        };
+
+        self.dispatcher.send(&context, "on_search", outcome).await?;
+        Ok(())
+    }
+
+    /// Find the active HSPs registered for the search domain; suspended and
+    /// deregistered participants are left out by the registry lookup
+    async fn identify_subscribers(
+        &self,
+        context: &Context,
+    ) -> Result<Vec<Participant>, ServiceError> {
+        let lookup = self
+            .registry
+            .lookup_participants(LookupRequest {
+                subscriber_id: None,
+                domain: Some(context.domain.clone()),
+                participant_type: Some("HSP".to_string()),
+            })
+            .await?;
+
+        Ok(lookup
+            .participants
+            .into_iter()
+            .take(self.config.max_providers_per_search)
+            .collect())
+    }
+
     /// Identify providers relevant to the search criteria
     async fn identify_relevant_providers(
         &self,
@@ -218,77 +632,289 @@ impl SearchService {
         Ok(())
     }
 
-    /// Merge search results from multiple providers
-    fn merge_search_results(
+    /// Score each of the given providers by its ratings
+    async fn provider_scores<'a>(
         &self,
-        responses: &HashMap<String, SearchResponse>,
-    ) -> Result<SearchResponse, ServiceError> {
-        if responses.is_empty() {
-            return Err(ServiceError::NotFound(
-                "No search results found".to_string(),
-            ));
+        provider_ids: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HashMap<&'a str, f64>, ServiceError> {
+        let mut scores = HashMap::new();
+        for provider_id in provider_ids {
+            if !scores.contains_key(provider_id) {
+                let score = self.ratings.provider_score(provider_id).await?;
+                scores.insert(provider_id, score);
+            }
         }
+        Ok(scores)
+    }
 
-        // Start with the first response as a base
-        let mut merged_response = responses.values().next().unwrap().clone();
-
-        // Merge in all other responses
-        for (_, response) in responses.iter().skip(1) {
-            // Merge items
-            merged_response
-                .catalog
-                .items
-                .extend(response.catalog.items.clone());
-
-            // Merge categories (avoiding duplicates)
-            for category in &response.catalog.categories {
-                if !merged_response
-                    .catalog
-                    .categories
-                    .iter()
-                    .any(|c| c.id == category.id)
-                {
-                    merged_response.catalog.categories.push(category.clone());
-                }
-            }
+    /// Order items from better rated providers first, keeping the existing
+    /// order among items of equally rated providers
+    async fn rank_items(&self, items: &mut [Item]) -> Result<(), ServiceError> {
+        let provider_ids: Vec<String> = items
+            .iter()
+            .map(|item| provider_of(&item.tags).to_string())
+            .collect();
+        let scores = self
+            .provider_scores(provider_ids.iter().map(String::as_str))
+            .await?;
 
-            // Merge fulfillments (avoiding duplicates)
-            for fulfillment in &response.catalog.fulfillments {
-                if !merged_response.catalog.fulfillments.contains(fulfillment) {
-                    merged_response
-                        .catalog
-                        .fulfillments
-                        .push(fulfillment.clone());
-                }
-            }
+        items.sort_by(|a, b| scores[provider_of(&b.tags)].total_cmp(&scores[provider_of(&a.tags)]));
+        Ok(())
+    }
 
-            // Merge locations (avoiding duplicates)
-            for location in &response.catalog.locations {
-                if !merged_response
-                    .catalog
-                    .locations
-                    .iter()
-                    .any(|l| l.id == location.id)
-                {
-                    merged_response.catalog.locations.push(location.clone());
-                }
+    /// Order provider responses by the providers' rating scores, best first
+    async fn rank_responses<'a>(
+        &self,
+        responses: &'a HashMap<String, SearchResponse>,
+    ) -> Result<Vec<(&'a str, &'a SearchResponse)>, ServiceError> {
+        let scores = self
+            .provider_scores(responses.keys().map(String::as_str))
+            .await?;
+
+        let mut ranked: Vec<(&str, &SearchResponse)> = responses
+            .iter()
+            .map(|(provider_id, response)| (provider_id.as_str(), response))
+            .collect();
+        ranked.sort_by(|(a, _), (b, _)| scores[b].total_cmp(&scores[a]).then_with(|| a.cmp(b)));
+        Ok(ranked)
+    }
+
+    /// Merge search results from multiple providers, in ranking order.
+    ///
+    /// Items, categories and locations are tagged with the provider that
+    /// returned them, so records of different providers sharing an ID are
+    /// all kept.
+    fn merge_search_results(
+        &self,
+        responses: Vec<(&str, &SearchResponse)>,
+    ) -> Result<SearchResponse, ServiceError> {
+        let mut merged: Option<SearchResponse> = None;
+
+        for (provider_id, response) in responses {
+            let mut catalog = response.catalog.clone();
+            tag_provider(&mut catalog, provider_id);
+
+            match merged.as_mut() {
+                None => merged = Some(SearchResponse { catalog }),
+                Some(merged) => merge_into(&mut merged.catalog, catalog),
             }
         }
 
-        Ok(merged_response)
+        merged.ok_or_else(|| ServiceError::NotFound("No search results found".to_string()))
+    }
+}
+
+/// Post a signed search to a provider, succeeding only if it is acknowledged
+async fn forward(
+    client: &Client,
+    signer: &RequestSigner,
+    url: &str,
+    body: Vec<u8>,
+) -> Result<(), String> {
+    let request = signer
+        .sign_request(client.post(url), body)
+        .map_err(|e| e.to_string())?;
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = response.status();
+    let ack: AckResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid acknowledgement (status {}): {}", status, e))?;
+
+    match (ack.message.ack.status, ack.error) {
+        (AckStatus::Ack, _) if status.is_success() => Ok(()),
+        (_, Some(error)) => Err(format!("Search rejected: {} {}", error.code, error.message)),
+        _ => Err(format!("Search rejected with status {}", status)),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::header::{SignatureHeader, AUTHORIZATION};
+    use crate::config::CallbackConfig;
+    use crate::models::ack::{ProtocolError, POLICY_ERROR};
+    use crate::models::network_registry::{ParticipantStatus, Subscriber};
     use crate::storage::memory::MemoryStorage;
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use serde_json::Value;
     use std::collections::HashMap;
+    use std::net::TcpListener;
+
+    /// Requests received by a stub HSP or EUA
+    #[derive(Default)]
+    struct StubParticipant {
+        /// Whether requests are NACKed
+        reject: bool,
+        /// Bodies of received requests
+        received: std::sync::Mutex<Vec<Value>>,
+        /// Authorization headers of received requests
+        authorizations: std::sync::Mutex<Vec<String>>,
+    }
+
+    async fn stub_endpoint(
+        req: HttpRequest,
+        body: web::Json<Value>,
+        state: web::Data<StubParticipant>,
+    ) -> HttpResponse {
+        if let Some(authorization) = req.headers().get(AUTHORIZATION) {
+            let authorization = authorization.to_str().unwrap().to_string();
+            state.authorizations.lock().unwrap().push(authorization);
+        }
+        state.received.lock().unwrap().push(body.into_inner());
+
+        if state.reject {
+            let error = ProtocolError::new(POLICY_ERROR, "NOT_SERVICEABLE", "Busy".to_string());
+            return HttpResponse::BadRequest().json(AckResponse::nack(error));
+        }
+        HttpResponse::Ok().json(AckResponse::ack())
+    }
+
+    // Start a local participant acknowledging (or rejecting) every request
+    fn start_stub(reject: bool) -> (String, web::Data<StubParticipant>) {
+        let state = web::Data::new(StubParticipant {
+            reject,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/{action}", web::post().to(stub_endpoint))
+        })
+        .listen(listener)
+        .unwrap()
+        .workers(1)
+        .run();
+        actix_rt::spawn(server);
+
+        (format!("http://127.0.0.1:{}", port), state)
+    }
+
+    fn received(stub: &StubParticipant) -> Vec<Value> {
+        stub.received.lock().unwrap().clone()
+    }
+
+    async fn wait_for<F: Fn() -> bool>(condition: F) {
+        for _ in 0..100 {
+            if condition() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Timed out waiting for condition");
+    }
+
+    fn create_service(
+        storage: Arc<dyn Storage>,
+        config: SearchServiceConfig,
+    ) -> Arc<SearchService> {
+        let gateway = GatewayConfig {
+            allow_ephemeral_signing_key: true,
+            ..GatewayConfig::default()
+        };
+        let signer = Arc::new(RequestSigner::from_config(&gateway).unwrap());
+        let dispatcher = Arc::new(
+            CallbackDispatcher::new(
+                storage.clone(),
+                signer.clone(),
+                gateway.clone(),
+                CallbackConfig {
+                    timeout_secs: 2,
+                    max_retries: 0,
+                    retry_backoff_ms: 10,
+                    max_retry_backoff_ms: 100,
+                },
+            )
+            .unwrap(),
+        );
+        Arc::new(SearchService::with_config(storage, signer, dispatcher, gateway, config).unwrap())
+    }
+
+    async fn register_hsp(
+        storage: &Arc<dyn Storage>,
+        id: &str,
+        url: &str,
+        status: ParticipantStatus,
+    ) {
+        storage
+            .register_subscriber(Subscriber {
+                id: id.to_string(),
+                type_field: "HSP".to_string(),
+                domain: "nic2004:85111".to_string(),
+                city: None,
+                country: None,
+                url: url.to_string(),
+                status,
+                public_key: String::new(),
+                encryption_public_key: None,
+                keys: Vec::new(),
+                domain_verification: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+    }
+
+    fn search_context(consumer_uri: &str) -> Context {
+        Context::new(
+            "nic2004:85111".to_string(),
+            "IND".to_string(),
+            "std:080".to_string(),
+            "search".to_string(),
+            "0.7.1".to_string(),
+            "eua-1".to_string(),
+            consumer_uri.to_string(),
+        )
+    }
+
+    fn search_request() -> SearchRequest {
+        SearchRequest {
+            query: HashMap::from([("specialty".to_string(), vec!["Cardiology".to_string()])]),
+            item: None,
+            fulfillment: None,
+            payment: None,
+            location: None,
+        }
+    }
+
+    fn catalog_response(item_id: &str) -> SearchResponse {
+        serde_json::from_value(json!({
+            "catalog": {
+                "descriptor": {"name": item_id},
+                "categories": [],
+                "fulfillments": ["teleconsultation"],
+                "payments": [],
+                "locations": [],
+                "items": [{
+                    "id": item_id,
+                    "descriptor": {"name": "Consultation"},
+                    "price": {"currency": "INR", "value": "500"},
+                    "category_id": "cardiology",
+                    "fulfillment_id": "teleconsultation"
+                }]
+            }
+        }))
+        .unwrap()
+    }
+
+    // The on_search callback an HSP sends for a forwarded search
+    fn on_search_context(forwarded: &Value, provider_id: &str, provider_uri: &str) -> Context {
+        let context: Context = serde_json::from_value(forwarded["context"].clone()).unwrap();
+        context.create_response_context(
+            "on_search".to_string(),
+            provider_id.to_string(),
+            provider_uri.to_string(),
+        )
+    }
 
     #[tokio::test]
     async fn test_search_with_empty_query() {
-        let storage = Arc::new(MemoryStorage::new());
-        let service = SearchService::new(storage);
+        let service = create_service(MemoryStorage::empty(), SearchServiceConfig::default());
 
         let request = SearchRequest {
             query: HashMap::new(),
@@ -310,8 +936,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_track_search_transaction() {
-        let storage = Arc::new(MemoryStorage::new());
-        let service = SearchService::new(storage);
+        let service = create_service(MemoryStorage::empty(), SearchServiceConfig::default());
 
         // Create a test search request
         let request = SearchRequest {
@@ -329,7 +954,10 @@ mod tests {
             timestamp: Utc::now(),
             request: request.clone(),
             forwarded_to: Vec::new(),
+            targeted: Vec::new(),
             responses: HashMap::new(),
+            context: None,
+            expires_at: None,
         };
 
         // Track the transaction
@@ -350,4 +978,405 @@ mod tests {
         let specialty = &retrieved_metadata.request.query.get("specialty").unwrap()[0];
         assert_eq!(specialty, "Cardiology");
     }
+
+    #[actix_rt::test]
+    async fn test_search_network_merges_hsp_responses() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let (eua_uri, eua) = start_stub(false);
+        let (cardio_uri, cardio) = start_stub(false);
+        let (ortho_uri, ortho) = start_stub(false);
+        let (busy_uri, busy) = start_stub(true);
+        let (suspended_uri, suspended) = start_stub(false);
+        let (pending_uri, pending) = start_stub(false);
+        register_hsp(
+            &storage,
+            "hsp-cardio",
+            &cardio_uri,
+            ParticipantStatus::Active,
+        )
+        .await;
+        register_hsp(&storage, "hsp-ortho", &ortho_uri, ParticipantStatus::Active).await;
+        register_hsp(&storage, "hsp-busy", &busy_uri, ParticipantStatus::Active).await;
+        register_hsp(
+            &storage,
+            "hsp-suspended",
+            &suspended_uri,
+            ParticipantStatus::Suspended,
+        )
+        .await;
+        // Not activated by an administrator yet
+        register_hsp(
+            &storage,
+            "hsp-pending",
+            &pending_uri,
+            ParticipantStatus::Subscribed,
+        )
+        .await;
+
+        let service = create_service(
+            storage,
+            SearchServiceConfig {
+                search_timeout: 1,
+                concurrent_search_limit: 1,
+                ..SearchServiceConfig::default()
+            },
+        );
+        let context = search_context(&eua_uri);
+        let task = actix_rt::spawn({
+            let service = service.clone();
+            let context = context.clone();
+            async move { service.search_network(context, search_request()).await }
+        });
+
+        wait_for(|| received(&cardio).len() == 1 && received(&ortho).len() == 1).await;
+        assert_eq!(received(&busy).len(), 1);
+        assert!(received(&suspended).is_empty());
+        assert!(received(&pending).is_empty());
+
+        // HSPs receive a signed search asking them to answer the gateway
+        let forwarded = received(&cardio)[0].clone();
+        assert_eq!(
+            forwarded["context"]["transaction_id"],
+            context.transaction_id
+        );
+        assert_eq!(
+            forwarded["context"]["consumer_uri"],
+            GatewayConfig::default().subscriber_url
+        );
+        let header = SignatureHeader::parse(&cardio.authorizations.lock().unwrap()[0]).unwrap();
+        assert_eq!(header.subscriber_id, "uhi-gateway");
+
+        for (provider_id, uri, item_id) in [
+            ("hsp-cardio", &cardio_uri, "ecg"),
+            ("hsp-ortho", &ortho_uri, "x-ray"),
+        ] {
+            let callback = on_search_context(&forwarded, provider_id, uri);
+            service
+                .on_search(&callback, catalog_response(item_id))
+                .await
+                .unwrap();
+        }
+        // Nothing is relayed before the search window closes
+        assert!(received(&eua).is_empty());
+
+        task.await.unwrap().unwrap();
+
+        let metadata = service
+            .get_search_transaction(&context.transaction_id)
+            .await
+            .unwrap();
+        let mut forwarded_to = metadata.forwarded_to.clone();
+        forwarded_to.sort();
+        assert_eq!(forwarded_to, vec!["hsp-cardio", "hsp-ortho"]);
+        assert_eq!(metadata.responses.len(), 2);
+
+        let relayed = received(&eua);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(relayed[0]["context"]["action"], "on_search");
+        assert_eq!(
+            relayed[0]["context"]["transaction_id"],
+            context.transaction_id
+        );
+        assert_eq!(
+            relayed[0]["message"]["catalog"]["items"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_search_network_relays_responses_individually() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let (eua_uri, eua) = start_stub(false);
+        let (hsp_uri, hsp) = start_stub(false);
+        register_hsp(&storage, "hsp-cardio", &hsp_uri, ParticipantStatus::Active).await;
+
+        let service = create_service(
+            storage,
+            SearchServiceConfig {
+                search_timeout: 1,
+                relay_mode: RelayMode::Individual,
+                ..SearchServiceConfig::default()
+            },
+        );
+        let task = actix_rt::spawn({
+            let service = service.clone();
+            let context = search_context(&eua_uri);
+            async move { service.search_network(context, search_request()).await }
+        });
+
+        wait_for(|| received(&hsp).len() == 1).await;
+        let callback = on_search_context(&received(&hsp)[0], "hsp-cardio", &hsp_uri);
+        service
+            .on_search(&callback, catalog_response("ecg"))
+            .await
+            .unwrap();
+
+        // Relayed straight away under the provider's identity
+        wait_for(|| received(&eua).len() == 1).await;
+        let relayed = received(&eua);
+        assert_eq!(relayed[0]["context"]["provider_id"], "hsp-cardio");
+        assert_eq!(relayed[0]["context"]["provider_uri"], hsp_uri);
+        assert_eq!(relayed[0]["message"]["catalog"]["items"][0]["id"], "ecg");
+
+        task.await.unwrap().unwrap();
+        assert_eq!(received(&eua).len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_search_network_reports_missing_responses() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let (eua_uri, eua) = start_stub(false);
+        let (hsp_uri, _hsp) = start_stub(false);
+        register_hsp(&storage, "hsp-cardio", &hsp_uri, ParticipantStatus::Active).await;
+
+        let service = create_service(
+            storage,
+            SearchServiceConfig {
+                search_timeout: 1,
+                ..SearchServiceConfig::default()
+            },
+        );
+        service
+            .search_network(search_context(&eua_uri), search_request())
+            .await
+            .unwrap();
+
+        let relayed = received(&eua);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(relayed[0]["error"]["code"], "NOT_FOUND");
+    }
+
+    #[actix_rt::test]
+    async fn test_on_search_rejects_unknown_and_late_responses() {
+        let service = create_service(MemoryStorage::empty(), SearchServiceConfig::default());
+        let mut context = search_context("https://eua.example.com/api/v1");
+        context.provider_id = Some("hsp-cardio".to_string());
+
+        let result = service.on_search(&context, catalog_response("ecg")).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+
+        // A search whose window has already closed
+        let metadata = SearchMetadata {
+            transaction_id: context.transaction_id.clone(),
+            timestamp: Utc::now(),
+            request: search_request(),
+            forwarded_to: vec!["hsp-cardio".to_string()],
+            targeted: vec!["hsp-cardio".to_string()],
+            responses: HashMap::new(),
+            context: Some(context.clone()),
+            expires_at: Some(Utc::now() - chrono::Duration::seconds(1)),
+        };
+        service
+            .track_search_transaction(&context.transaction_id, metadata)
+            .await
+            .unwrap();
+
+        let result = service.on_search(&context, catalog_response("ecg")).await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let metadata = service
+            .get_search_transaction(&context.transaction_id)
+            .await
+            .unwrap();
+        assert!(metadata.responses.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_on_search_collects_response_for_open_transaction() {
+        let service = create_service(MemoryStorage::empty(), SearchServiceConfig::default());
+        let request_context = search_context("https://eua.example.com/api/v1");
+        let metadata = SearchMetadata {
+            transaction_id: request_context.transaction_id.clone(),
+            timestamp: Utc::now(),
+            request: search_request(),
+            forwarded_to: vec!["hsp-cardio".to_string()],
+            targeted: vec!["hsp-cardio".to_string()],
+            responses: HashMap::new(),
+            context: Some(request_context.clone()),
+            expires_at: Some(Utc::now() + chrono::Duration::seconds(30)),
+        };
+        service
+            .track_search_transaction(&request_context.transaction_id, metadata)
+            .await
+            .unwrap();
+
+        let callback = request_context.create_response_context(
+            "on_search".to_string(),
+            "hsp-cardio".to_string(),
+            "https://hsp.example.com/api/v1".to_string(),
+        );
+        service
+            .on_search(&callback, catalog_response("ecg"))
+            .await
+            .unwrap();
+
+        let metadata = service
+            .get_search_transaction(&request_context.transaction_id)
+            .await
+            .unwrap();
+        let response = &metadata.responses["hsp-cardio"];
+        assert_eq!(response.catalog.items[0].id, "ecg");
+
+        // Subscribers the search was not sent to cannot answer it
+        let callback = request_context.create_response_context(
+            "on_search".to_string(),
+            "hsp-rogue".to_string(),
+            "https://rogue.example.com/api/v1".to_string(),
+        );
+        let result = service.on_search(&callback, catalog_response("fake")).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let metadata = service
+            .get_search_transaction(&request_context.transaction_id)
+            .await
+            .unwrap();
+        assert_eq!(metadata.responses.len(), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_search_transactions_cannot_be_taken_over() {
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        let (hsp_uri, hsp) = start_stub(false);
+        register_hsp(&storage, "hsp-cardio", &hsp_uri, ParticipantStatus::Active).await;
+        let service = create_service(
+            storage.clone(),
+            SearchServiceConfig {
+                search_timeout: 30,
+                ..SearchServiceConfig::default()
+            },
+        );
+
+        let context = search_context("https://eua.example.com/api/v1");
+        service
+            .forward_search(&context, &search_request())
+            .await
+            .unwrap();
+        wait_for(|| received(&hsp).len() == 1).await;
+
+        // Searches are stored apart from other records of the transaction
+        assert!(storage
+            .get_transaction(&format!("{}{}", SEARCH_PREFIX, context.transaction_id))
+            .await
+            .is_ok());
+
+        // Another consumer cannot reuse the transaction ID
+        let hijack = Context {
+            consumer_id: "eua-2".to_string(),
+            consumer_uri: "https://eua-2.example.com/api/v1".to_string(),
+            ..context.clone()
+        };
+        let result = service.forward_search(&hijack, &search_request()).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        // nor can the same consumer restart a search still collecting responses
+        let result = service.forward_search(&context, &search_request()).await;
+        assert!(matches!(result, Err(ServiceError::BusinessLogic(_))));
+
+        let metadata = service
+            .get_search_transaction(&context.transaction_id)
+            .await
+            .unwrap();
+        assert_eq!(metadata.context.unwrap().consumer_id, "eua-1");
+        assert_eq!(received(&hsp).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_merged_results_are_ranked_by_provider_rating() {
+        use crate::models::rating::{Rating, RatingCategory};
+
+        let storage: Arc<dyn Storage> = MemoryStorage::empty();
+        for order in 0..20 {
+            let rating = Rating {
+                rating_category: RatingCategory::Provider,
+                id: "hsp-ortho".to_string(),
+                value: if order < 16 { 5 } else { 4 },
+                feedback: None,
+            };
+            storage
+                .record_ratings(&format!("order-{}", order), vec![rating])
+                .await
+                .unwrap();
+        }
+        let service = create_service(storage, SearchServiceConfig::default());
+
+        let responses = HashMap::from([
+            ("hsp-cardio".to_string(), catalog_response("ecg")),
+            ("hsp-ortho".to_string(), catalog_response("x-ray")),
+            ("hsp-derma".to_string(), catalog_response("biopsy")),
+        ]);
+        let ranked = service.rank_responses(&responses).await.unwrap();
+        let merged = service.merge_search_results(ranked).unwrap();
+
+        // Rated providers come first, unrated ones follow in ID order
+        let items: Vec<&str> = merged
+            .catalog
+            .items
+            .iter()
+            .map(|item| item.id.as_str())
+            .collect();
+        assert_eq!(items, vec!["x-ray", "ecg", "biopsy"]);
+    }
+
+    #[tokio::test]
+    async fn test_merged_results_keep_records_of_each_provider() {
+        let service = create_service(MemoryStorage::empty(), SearchServiceConfig::default());
+
+        // Both HSPs use the same category and location IDs for different records
+        let response = |item_id: &str, city: &str| {
+            let mut response = catalog_response(item_id);
+            response.catalog = serde_json::from_value(json!({
+                "descriptor": {"name": item_id},
+                "categories": [{"id": "cardiology", "descriptor": {"name": city}}],
+                "fulfillments": ["teleconsultation"],
+                "payments": [],
+                "locations": [{
+                    "id": "loc-1",
+                    "descriptor": {"name": city},
+                    "gps": "12.9716,77.5946",
+                    "city": city
+                }],
+                "items": response.catalog.items
+            }))
+            .unwrap();
+            response
+        };
+        let responses = HashMap::from([
+            ("hsp-blr".to_string(), response("ecg", "Bangalore")),
+            ("hsp-mys".to_string(), response("echo", "Mysore")),
+        ]);
+        let ranked = service.rank_responses(&responses).await.unwrap();
+        let merged = service.merge_search_results(ranked).unwrap().catalog;
+
+        let items: Vec<_> = merged
+            .items
+            .iter()
+            .map(|item| (provider_of(&item.tags), item.id.as_str()))
+            .collect();
+        assert_eq!(items, vec![("hsp-blr", "ecg"), ("hsp-mys", "echo")]);
+        let categories: Vec<_> = merged
+            .categories
+            .iter()
+            .map(|category| {
+                (
+                    provider_of(&category.tags),
+                    category.descriptor.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            categories,
+            vec![("hsp-blr", "Bangalore"), ("hsp-mys", "Mysore")]
+        );
+        let locations: Vec<_> = merged
+            .locations
+            .iter()
+            .map(|location| (provider_of(&location.tags), location.city.as_deref()))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![("hsp-blr", Some("Bangalore")), ("hsp-mys", Some("Mysore"))]
+        );
+    }
 }
@@ -0,0 +1,36 @@
+//! Slot booking checks shared by all storage backends.
+//!
+//! Backends hold a per-provider lock while they load the provider's
+//! fulfillments, hand them to `ensure_slot_free` and write the booking, so
+//! that concurrent bookings cannot overlap.
+
+use crate::models::fulfillment::Fulfillment;
+use crate::storage::{StorageError, StorageResult};
+
+/// Check that no other fulfillment among `booked` holds a slot overlapping
+/// the slot of `fulfillment`
+///
+/// # Parameters
+/// * `fulfillment` - Fulfillment being booked or rebooked
+/// * `booked` - Fulfillments of the same provider; `fulfillment` itself is ignored
+///
+/// # Returns
+/// * `StorageError::Conflict` naming the first overlapping fulfillment
+pub fn ensure_slot_free<'a>(
+    fulfillment: &Fulfillment,
+    booked: impl IntoIterator<Item = &'a Fulfillment>,
+) -> StorageResult<()> {
+    let (start, end) = fulfillment.slot();
+
+    match booked
+        .into_iter()
+        .filter(|other| other.id != fulfillment.id)
+        .find(|other| other.overlaps(start, end))
+    {
+        Some(other) => Err(StorageError::Conflict(format!(
+            "Slot of fulfillment {} overlaps fulfillment {} of provider {}",
+            fulfillment.id, other.id, fulfillment.provider_id
+        ))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,1701 @@
+//! Backend-agnostic conformance suite for the `Storage` trait.
+//!
+//! Every case takes an `Arc<dyn Storage>` so any backend can be validated by
+//! invoking `storage_conformance_tests!` with an async factory function that
+//! returns `Option<Arc<dyn Storage>>`, or an `Option` of any other
+//! `StorageFixture` that must outlive the case, e.g. to clean up after it.
+//! Returning `None` skips the suite, which lets backends that need external
+//! infrastructure opt out when it is absent.
+
+use crate::models::billing::{Address, Billing};
+use crate::models::catalog::{Catalog, Item, Price, SearchRequest, SearchResponse};
+use crate::models::fulfillment::{Fulfillment, FulfillmentState, State, Time, TimeSlot};
+use crate::models::history::{EventEntity, OrderEvent};
+use crate::models::idempotency::{ProcessedMessage, RecordedResponse};
+use crate::models::network_registry::{
+    DomainVerification, DomainVerificationMethod, KeyPurpose, NetworkRegistryLookup,
+    ParticipantStatus, SignatureAlgorithm, Subscriber, SubscriberKey, SubscriberStatusChange,
+};
+use crate::models::order::{Order, OrderChange, OrderState, ProviderSummary};
+use crate::models::provider::{Category, Descriptor, Location, Provider};
+use crate::models::rating::{Rating, RatingCategory};
+use crate::models::tracking::{LocationUpdate, Tracking, TrackingStatus};
+use crate::storage::search::PROVIDER_ID_TAG;
+use crate::storage::{Storage, StorageError};
+use chrono::{Duration, Utc};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Generate one `#[tokio::test]` per conformance case for the given factory
+macro_rules! storage_conformance_tests {
+    ($factory:ident) => {
+        $crate::storage::conformance::storage_conformance_tests!(
+            @cases $factory;
+            provider_create_and_get,
+            provider_create_duplicate,
+            provider_get_missing,
+            provider_update,
+            provider_update_missing,
+            provider_delete,
+            provider_delete_missing,
+            provider_list,
+            catalog_create_and_get,
+            catalog_create_requires_provider,
+            catalog_create_duplicate,
+            catalog_get_missing,
+            catalog_update,
+            catalog_update_missing,
+            catalog_search_without_catalogs,
+            catalog_search_returns_catalog,
+            catalog_search_filters_across_providers,
+            catalog_search_without_matches,
+            order_create_and_get,
+            order_create_duplicate,
+            order_get_missing,
+            order_update,
+            order_update_missing,
+            order_record_change,
+            order_record_change_missing,
+            order_list_by_provider,
+            order_list_by_customer,
+            fulfillment_create_and_get,
+            fulfillment_create_duplicate,
+            fulfillment_get_missing,
+            fulfillment_update,
+            fulfillment_update_missing,
+            fulfillment_list_by_provider,
+            fulfillment_delete,
+            fulfillment_delete_missing,
+            fulfillment_booking_rejects_overlaps,
+            fulfillment_booking_frees_cancelled_slots,
+            fulfillment_rebooking_rejects_overlaps,
+            tracking_save_and_get,
+            tracking_save_overwrites,
+            tracking_get_missing,
+            subscriber_register_and_get,
+            subscriber_register_duplicate,
+            subscriber_get_missing,
+            subscriber_update,
+            subscriber_update_missing,
+            subscriber_lookup,
+            subscriber_lookup_missing,
+            subscriber_list,
+            transaction_record_and_get,
+            transaction_record_overwrites,
+            transaction_get_missing,
+            search_responses_record_and_list,
+            search_responses_record_replaces,
+            order_events_append_and_list,
+            order_events_missing_order,
+            subscriber_status_changes_append_and_list,
+            subscriber_status_changes_missing,
+            processed_message_create_and_get,
+            processed_message_create_duplicate,
+            processed_message_replaces_expired,
+            processed_message_update,
+            processed_message_delete,
+            processed_message_missing,
+            ratings_record_and_aggregate,
+            ratings_recorded_once_per_order,
+            rating_aggregate_missing,
+            concurrent_distinct_creates,
+            concurrent_duplicate_creates,
+            concurrent_transaction_writes,
+            concurrent_search_responses,
+            concurrent_overlapping_bookings,
+            concurrent_order_changes,
+            concurrent_ratings,
+            sequential_updates_last_write_wins,
+        );
+    };
+    (@cases $factory:ident; $($case:ident),* $(,)?) => {
+        mod conformance {
+            $(
+                #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+                async fn $case() {
+                    if let Some(fixture) = super::$factory().await {
+                        let storage =
+                            $crate::storage::conformance::StorageFixture::storage(&fixture);
+                        $crate::storage::conformance::$case(storage).await;
+                    }
+                }
+            )*
+        }
+    };
+}
+
+pub(crate) use storage_conformance_tests;
+
+/// Storage under test, kept alive until the conformance case has finished
+pub trait StorageFixture {
+    fn storage(&self) -> Arc<dyn Storage>;
+}
+
+impl StorageFixture for Arc<dyn Storage> {
+    fn storage(&self) -> Arc<dyn Storage> {
+        Arc::clone(self)
+    }
+}
+
+fn descriptor(name: &str) -> Descriptor {
+    Descriptor {
+        name: name.to_string(),
+        short_desc: None,
+        long_desc: None,
+        images: None,
+    }
+}
+
+fn provider(id: &str) -> Provider {
+    Provider {
+        id: id.to_string(),
+        descriptor: descriptor("Conformance Provider"),
+        categories: vec![Category {
+            id: "cat-1".to_string(),
+            descriptor: descriptor("Cardiology"),
+            time: None,
+            tags: None,
+        }],
+        support: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn catalog(name: &str) -> Catalog {
+    Catalog {
+        descriptor: descriptor(name),
+        categories: vec![Category {
+            id: "cat-1".to_string(),
+            descriptor: descriptor("Cardiology"),
+            time: None,
+            tags: None,
+        }],
+        fulfillments: vec!["teleconsultation".to_string()],
+        payments: vec!["ON-ORDER".to_string()],
+        locations: vec![Location {
+            id: "loc-1".to_string(),
+            descriptor: descriptor("Main Clinic"),
+            gps: "12.9716,77.5946".to_string(),
+            address: None,
+            city: Some("Bangalore".to_string()),
+            state: None,
+            country: Some("IND".to_string()),
+            area_code: None,
+            tags: None,
+        }],
+        items: vec![Item {
+            id: "item-1".to_string(),
+            parent_item_id: None,
+            descriptor: descriptor("Consultation"),
+            price: Price {
+                currency: "INR".to_string(),
+                value: "500".to_string(),
+                maximum_value: None,
+            },
+            category_id: "cat-1".to_string(),
+            fulfillment_id: "teleconsultation".to_string(),
+            location_id: Some("loc-1".to_string()),
+            time: None,
+            recommended: None,
+            tags: None,
+        }],
+        exp: None,
+    }
+}
+
+fn fulfillment(id: &str, provider_id: &str) -> Fulfillment {
+    let start = Utc::now() + Duration::days(1);
+    Fulfillment {
+        id: id.to_string(),
+        fulfillment_type: "teleconsultation".to_string(),
+        provider_id: provider_id.to_string(),
+        agent: None,
+        start: TimeSlot {
+            time: Time {
+                timestamp: start,
+                label: Some("start".to_string()),
+            },
+            duration: Some(1800),
+        },
+        end: TimeSlot {
+            time: Time {
+                timestamp: start + Duration::minutes(30),
+                label: Some("end".to_string()),
+            },
+            duration: None,
+        },
+        customer: None,
+        state: None,
+        tags: HashMap::new(),
+    }
+}
+
+fn order(id: &str, provider_id: &str, customer: &str) -> Order {
+    Order {
+        id: id.to_string(),
+        consumer_id: "eua-1".to_string(),
+        provider: ProviderSummary {
+            id: provider_id.to_string(),
+            descriptor: "Conformance Provider".to_string(),
+            categories: Vec::new(),
+        },
+        items: Vec::new(),
+        billing: Billing {
+            name: customer.to_string(),
+            organization: None,
+            address: Address {
+                door: None,
+                building: None,
+                street: None,
+                locality: None,
+                city: "Bangalore".to_string(),
+                state: "Karnataka".to_string(),
+                country: "IND".to_string(),
+                area_code: "560001".to_string(),
+            },
+            email: None,
+            phone: "9999999999".to_string(),
+            tax_number: None,
+        },
+        fulfillment: fulfillment(&format!("{}-fulfillment", id), provider_id),
+        quote: None,
+        payment: None,
+        state: OrderState::Initialized,
+        transitions: Vec::new(),
+        cancellation: None,
+        changes: Vec::new(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn subscriber(id: &str, type_field: &str, domain: &str) -> Subscriber {
+    Subscriber {
+        id: id.to_string(),
+        type_field: type_field.to_string(),
+        domain: domain.to_string(),
+        city: None,
+        country: None,
+        url: format!("https://{}/api", domain),
+        status: ParticipantStatus::Active,
+        public_key: "dGVzdC1wdWJsaWMta2V5".to_string(),
+        encryption_public_key: None,
+        keys: Vec::new(),
+        domain_verification: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+fn unique_id(prefix: &str) -> String {
+    format!("{}-{}", prefix, Uuid::new_v4())
+}
+
+/// IDs of listed records in list order. Lists are ordered by ID, so cases
+/// create records in reverse ID order to check it.
+fn ids<T>(items: &[T], id: impl Fn(&T) -> String) -> Vec<String> {
+    items.iter().map(id).collect()
+}
+
+// Provider operations
+
+pub async fn provider_create_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("provider");
+    let created = storage.create_provider(provider(&id)).await.unwrap();
+    assert_eq!(created.id, id);
+
+    let retrieved = storage.get_provider(&id).await.unwrap();
+    assert_eq!(retrieved.id, id);
+    assert_eq!(retrieved.descriptor.name, "Conformance Provider");
+    assert_eq!(retrieved.categories.len(), 1);
+}
+
+pub async fn provider_create_duplicate(storage: Arc<dyn Storage>) {
+    let id = unique_id("provider");
+    storage.create_provider(provider(&id)).await.unwrap();
+
+    let result = storage.create_provider(provider(&id)).await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn provider_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_provider(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn provider_update(storage: Arc<dyn Storage>) {
+    let id = unique_id("provider");
+    let mut record = storage.create_provider(provider(&id)).await.unwrap();
+
+    record.descriptor.name = "Updated Provider".to_string();
+    let updated = storage.update_provider(record).await.unwrap();
+    assert_eq!(updated.descriptor.name, "Updated Provider");
+
+    let retrieved = storage.get_provider(&id).await.unwrap();
+    assert_eq!(retrieved.descriptor.name, "Updated Provider");
+}
+
+pub async fn provider_update_missing(storage: Arc<dyn Storage>) {
+    let result = storage.update_provider(provider(&unique_id("missing"))).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn provider_delete(storage: Arc<dyn Storage>) {
+    let id = unique_id("provider");
+    storage.create_provider(provider(&id)).await.unwrap();
+    storage.delete_provider(&id).await.unwrap();
+
+    let result = storage.get_provider(&id).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn provider_delete_missing(storage: Arc<dyn Storage>) {
+    let result = storage.delete_provider(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn provider_list(storage: Arc<dyn Storage>) {
+    assert!(storage.list_providers().await.unwrap().is_empty());
+
+    let mut expected = vec![unique_id("provider"), unique_id("provider")];
+    expected.sort();
+    for id in expected.iter().rev() {
+        storage.create_provider(provider(id)).await.unwrap();
+    }
+
+    let providers = storage.list_providers().await.unwrap();
+    assert_eq!(ids(&providers, |p| p.id.clone()), expected);
+}
+
+// Catalog operations
+
+pub async fn catalog_create_and_get(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+
+    let created = storage
+        .create_catalog(&provider_id, catalog("Conformance Catalog"))
+        .await
+        .unwrap();
+    assert_eq!(created.descriptor.name, "Conformance Catalog");
+
+    let retrieved = storage.get_catalog(&provider_id).await.unwrap();
+    assert_eq!(retrieved.descriptor.name, "Conformance Catalog");
+    assert_eq!(retrieved.items.len(), 1);
+    assert_eq!(retrieved.items[0].id, "item-1");
+}
+
+pub async fn catalog_create_requires_provider(storage: Arc<dyn Storage>) {
+    let result = storage
+        .create_catalog(&unique_id("missing"), catalog("Orphan Catalog"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn catalog_create_duplicate(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+    storage
+        .create_catalog(&provider_id, catalog("First Catalog"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .create_catalog(&provider_id, catalog("Second Catalog"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn catalog_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_catalog(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn catalog_update(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+    storage
+        .create_catalog(&provider_id, catalog("Original Catalog"))
+        .await
+        .unwrap();
+
+    let updated = storage
+        .update_catalog(&provider_id, catalog("Updated Catalog"))
+        .await
+        .unwrap();
+    assert_eq!(updated.descriptor.name, "Updated Catalog");
+
+    let retrieved = storage.get_catalog(&provider_id).await.unwrap();
+    assert_eq!(retrieved.descriptor.name, "Updated Catalog");
+}
+
+pub async fn catalog_update_missing(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+
+    let result = storage
+        .update_catalog(&provider_id, catalog("Missing Catalog"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn catalog_search_without_catalogs(storage: Arc<dyn Storage>) {
+    let request = SearchRequest {
+        query: HashMap::from([("name".to_string(), vec!["Consultation".to_string()])]),
+        item: None,
+        fulfillment: None,
+        payment: None,
+        location: None,
+    };
+
+    let result = storage.search_catalog(request).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn catalog_search_returns_catalog(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+    storage
+        .create_catalog(&provider_id, catalog("Searchable Catalog"))
+        .await
+        .unwrap();
+
+    let request = SearchRequest {
+        query: HashMap::from([("name".to_string(), vec!["Consultation".to_string()])]),
+        item: None,
+        fulfillment: None,
+        payment: None,
+        location: None,
+    };
+
+    let response = storage.search_catalog(request).await.unwrap();
+    assert!(response.catalog.items.iter().any(|item| item.id == "item-1"));
+}
+
+pub async fn catalog_search_filters_across_providers(storage: Arc<dyn Storage>) {
+    let mut provider_ids = vec![unique_id("provider"), unique_id("provider")];
+    provider_ids.sort();
+    for provider_id in &provider_ids {
+        storage.create_provider(provider(provider_id)).await.unwrap();
+        let mut record = catalog("Searchable Catalog");
+        record.items.push(Item {
+            id: "item-2".to_string(),
+            descriptor: descriptor("Physiotherapy Session"),
+            fulfillment_id: "home-visit".to_string(),
+            ..record.items[0].clone()
+        });
+        storage.create_catalog(provider_id, record).await.unwrap();
+    }
+
+    let request = SearchRequest {
+        query: HashMap::from([("name".to_string(), vec!["physio".to_string()])]),
+        item: None,
+        fulfillment: Some("home-visit".to_string()),
+        payment: None,
+        location: None,
+    };
+
+    let response = storage.search_catalog(request).await.unwrap();
+    let matched: Vec<(String, String)> = response
+        .catalog
+        .items
+        .iter()
+        .map(|item| {
+            let provider_id = item.tags.as_ref().unwrap()[PROVIDER_ID_TAG].clone();
+            (provider_id, item.id.clone())
+        })
+        .collect();
+    assert_eq!(
+        matched,
+        provider_ids
+            .iter()
+            .map(|id| (id.clone(), "item-2".to_string()))
+            .collect::<Vec<_>>()
+    );
+}
+
+pub async fn catalog_search_without_matches(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    storage.create_provider(provider(&provider_id)).await.unwrap();
+    storage
+        .create_catalog(&provider_id, catalog("Searchable Catalog"))
+        .await
+        .unwrap();
+
+    let request = SearchRequest {
+        query: HashMap::from([("name".to_string(), vec!["Consultation".to_string()])]),
+        item: None,
+        fulfillment: None,
+        payment: Some("ON-DELIVERY".to_string()),
+        location: None,
+    };
+
+    let result = storage.search_catalog(request).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Order operations
+
+pub async fn order_create_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let created = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+    assert_eq!(created.id, id);
+
+    let retrieved = storage.get_order(&id).await.unwrap();
+    assert_eq!(retrieved.id, id);
+    assert_eq!(retrieved.provider.id, "provider-1");
+    assert_eq!(retrieved.billing.name, "customer-1");
+    assert_eq!(retrieved.state, OrderState::Initialized);
+}
+
+pub async fn order_create_duplicate(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn order_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_order(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn order_update(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let mut record = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+
+    record.state = OrderState::Confirmed;
+    let updated = storage.update_order(record).await.unwrap();
+    assert_eq!(updated.state, OrderState::Confirmed);
+
+    let retrieved = storage.get_order(&id).await.unwrap();
+    assert_eq!(retrieved.state, OrderState::Confirmed);
+}
+
+pub async fn order_update_missing(storage: Arc<dyn Storage>) {
+    let result = storage
+        .update_order(order(&unique_id("missing"), "provider-1", "customer-1"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+/// `order` with one more change, recording the given target
+fn changed(mut order: Order, target: &str) -> Order {
+    order.changes.push(OrderChange {
+        version: order.changes.len() as u32 + 1,
+        target: target.to_string(),
+        previous: json!({}),
+        current: json!({ "target": target }),
+        changed_at: Utc::now(),
+    });
+    order
+}
+
+pub async fn order_record_change(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let record = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+
+    let first = storage
+        .record_order_change(changed(record.clone(), "billing"))
+        .await
+        .unwrap();
+    let second = storage
+        .record_order_change(changed(first, "items"))
+        .await
+        .unwrap();
+    assert_eq!(second.changes.len(), 2);
+
+    // A change based on a stale copy of the order is refused
+    let result = storage
+        .record_order_change(changed(record, "fulfillment"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Conflict(_))));
+
+    let retrieved = storage.get_order(&id).await.unwrap();
+    let targets: Vec<_> = retrieved
+        .changes
+        .iter()
+        .map(|change| change.target.as_str())
+        .collect();
+    assert_eq!(targets, ["billing", "items"]);
+}
+
+pub async fn order_record_change_missing(storage: Arc<dyn Storage>) {
+    let record = order(&unique_id("missing"), "provider-1", "customer-1");
+    let result = storage
+        .record_order_change(changed(record, "billing"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn order_list_by_provider(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let mut expected = vec![unique_id("order"), unique_id("order")];
+    expected.sort();
+    for id in expected.iter().rev() {
+        storage
+            .create_order(order(id, &provider_id, "customer-1"))
+            .await
+            .unwrap();
+    }
+    storage
+        .create_order(order(&unique_id("order"), "other-provider", "customer-1"))
+        .await
+        .unwrap();
+
+    let orders = storage.list_orders_by_provider(&provider_id).await.unwrap();
+    assert_eq!(ids(&orders, |o| o.id.clone()), expected);
+
+    let none = storage
+        .list_orders_by_provider(&unique_id("missing"))
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+pub async fn order_list_by_customer(storage: Arc<dyn Storage>) {
+    let customer = unique_id("customer");
+    let mut expected = vec![unique_id("order"), unique_id("order")];
+    expected.sort();
+    for id in expected.iter().rev() {
+        storage
+            .create_order(order(id, "provider-1", &customer))
+            .await
+            .unwrap();
+    }
+    storage
+        .create_order(order(&unique_id("order"), "provider-1", "someone-else"))
+        .await
+        .unwrap();
+
+    let orders = storage.list_orders_by_customer(&customer).await.unwrap();
+    assert_eq!(ids(&orders, |o| o.id.clone()), expected);
+}
+
+// Fulfillment operations
+
+pub async fn fulfillment_create_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    let created = storage
+        .create_fulfillment(fulfillment(&id, "provider-1"))
+        .await
+        .unwrap();
+    assert_eq!(created.id, id);
+
+    let retrieved = storage.get_fulfillment(&id).await.unwrap();
+    assert_eq!(retrieved.id, id);
+    assert_eq!(retrieved.provider_id, "provider-1");
+    assert_eq!(retrieved.start.duration, Some(1800));
+}
+
+pub async fn fulfillment_create_duplicate(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    storage
+        .create_fulfillment(fulfillment(&id, "provider-1"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .create_fulfillment(fulfillment(&id, "provider-1"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn fulfillment_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_fulfillment(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn fulfillment_update(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    let mut record = storage
+        .create_fulfillment(fulfillment(&id, "provider-1"))
+        .await
+        .unwrap();
+
+    record
+        .tags
+        .insert("note".to_string(), "bring reports".to_string());
+    storage.update_fulfillment(record).await.unwrap();
+
+    let retrieved = storage.get_fulfillment(&id).await.unwrap();
+    assert_eq!(
+        retrieved.tags.get("note").map(String::as_str),
+        Some("bring reports")
+    );
+}
+
+pub async fn fulfillment_update_missing(storage: Arc<dyn Storage>) {
+    let result = storage
+        .update_fulfillment(fulfillment(&unique_id("missing"), "provider-1"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn fulfillment_list_by_provider(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let mut expected = vec![unique_id("fulfillment"), unique_id("fulfillment")];
+    expected.sort();
+    for id in expected.iter().rev() {
+        storage
+            .create_fulfillment(fulfillment(id, &provider_id))
+            .await
+            .unwrap();
+    }
+    storage
+        .create_fulfillment(fulfillment(&unique_id("fulfillment"), "other-provider"))
+        .await
+        .unwrap();
+
+    let fulfillments = storage
+        .list_fulfillments_by_provider(&provider_id)
+        .await
+        .unwrap();
+    assert_eq!(ids(&fulfillments, |f| f.id.clone()), expected);
+}
+
+pub async fn fulfillment_delete(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    storage
+        .create_fulfillment(fulfillment(&id, "provider-1"))
+        .await
+        .unwrap();
+    storage.delete_fulfillment(&id).await.unwrap();
+
+    let result = storage.get_fulfillment(&id).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn fulfillment_delete_missing(storage: Arc<dyn Storage>) {
+    let result = storage.delete_fulfillment(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Slot bookings
+
+pub async fn fulfillment_booking_rejects_overlaps(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let booked = fulfillment(&unique_id("fulfillment"), &provider_id);
+    storage.book_fulfillment(booked.clone()).await.unwrap();
+
+    // Overlapping the second half of the booked slot
+    let mut overlapping = fulfillment(&unique_id("fulfillment"), &provider_id);
+    overlapping.start.time.timestamp = booked.start.time.timestamp + Duration::minutes(15);
+    let result = storage.book_fulfillment(overlapping.clone()).await;
+    assert!(matches!(result, Err(StorageError::Conflict(_))));
+    let result = storage.get_fulfillment(&overlapping.id).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+
+    // Back-to-back slots and other providers' slots do not conflict
+    let mut next = fulfillment(&unique_id("fulfillment"), &provider_id);
+    next.start.time.timestamp = booked.start.time.timestamp + Duration::minutes(30);
+    storage.book_fulfillment(next).await.unwrap();
+    let other = fulfillment(&unique_id("fulfillment"), &unique_id("provider"));
+    storage.book_fulfillment(other).await.unwrap();
+
+    let result = storage.book_fulfillment(booked).await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn fulfillment_booking_frees_cancelled_slots(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let mut cancelled = fulfillment(&unique_id("fulfillment"), &provider_id);
+    cancelled.state = Some(State {
+        descriptor: FulfillmentState::Cancelled,
+        updated_at: Utc::now(),
+    });
+    storage.book_fulfillment(cancelled).await.unwrap();
+
+    let rebooked = fulfillment(&unique_id("fulfillment"), &provider_id);
+    storage.book_fulfillment(rebooked).await.unwrap();
+}
+
+pub async fn fulfillment_rebooking_rejects_overlaps(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let first = fulfillment(&unique_id("fulfillment"), &provider_id);
+    storage.book_fulfillment(first.clone()).await.unwrap();
+    let mut second = fulfillment(&unique_id("fulfillment"), &provider_id);
+    second.start.time.timestamp = first.start.time.timestamp + Duration::hours(1);
+    storage.book_fulfillment(second.clone()).await.unwrap();
+
+    // A fulfillment may move within its own slot
+    let mut moved = first.clone();
+    moved.start.time.timestamp = first.start.time.timestamp + Duration::minutes(10);
+    storage.rebook_fulfillment(moved.clone()).await.unwrap();
+    let retrieved = storage.get_fulfillment(&first.id).await.unwrap();
+    assert_eq!(retrieved.start.time.timestamp, moved.start.time.timestamp);
+
+    // but not onto another fulfillment's slot
+    let mut clash = first.clone();
+    clash.start.time.timestamp = second.start.time.timestamp;
+    let result = storage.rebook_fulfillment(clash).await;
+    assert!(matches!(result, Err(StorageError::Conflict(_))));
+    let retrieved = storage.get_fulfillment(&first.id).await.unwrap();
+    assert_eq!(retrieved.start.time.timestamp, moved.start.time.timestamp);
+
+    let result = storage
+        .rebook_fulfillment(fulfillment(&unique_id("missing"), &provider_id))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Fulfillment tracking
+
+fn tracking(fulfillment_id: &str, status: TrackingStatus) -> Tracking {
+    Tracking {
+        fulfillment_id: fulfillment_id.to_string(),
+        url: Some("https://track.example.com/agent".to_string()),
+        status,
+        locations: vec![LocationUpdate {
+            gps: "12.9716,77.5946".to_string(),
+            timestamp: Utc::now(),
+        }],
+        updated_at: Utc::now(),
+    }
+}
+
+pub async fn tracking_save_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    let saved = storage
+        .save_tracking(tracking(&id, TrackingStatus::Active))
+        .await
+        .unwrap();
+    assert_eq!(saved.fulfillment_id, id);
+
+    let retrieved = storage.get_tracking(&id).await.unwrap();
+    assert_eq!(retrieved.fulfillment_id, id);
+    assert_eq!(retrieved.status, TrackingStatus::Active);
+    assert_eq!(retrieved.url, saved.url);
+    assert_eq!(retrieved.locations.len(), 1);
+    assert_eq!(retrieved.locations[0].gps, "12.9716,77.5946");
+}
+
+pub async fn tracking_save_overwrites(storage: Arc<dyn Storage>) {
+    let id = unique_id("fulfillment");
+    storage
+        .save_tracking(tracking(&id, TrackingStatus::Active))
+        .await
+        .unwrap();
+
+    let mut update = tracking(&id, TrackingStatus::Inactive);
+    update.url = None;
+    update.locations.clear();
+    storage.save_tracking(update).await.unwrap();
+
+    let retrieved = storage.get_tracking(&id).await.unwrap();
+    assert_eq!(retrieved.status, TrackingStatus::Inactive);
+    assert!(retrieved.url.is_none());
+    assert!(retrieved.locations.is_empty());
+}
+
+pub async fn tracking_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_tracking(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Network registry operations
+
+pub async fn subscriber_register_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("subscriber");
+    let registered = storage
+        .register_subscriber(subscriber(&id, "HSP", "hsp.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(registered.id, id);
+
+    let retrieved = storage.get_subscriber(&id).await.unwrap();
+    assert_eq!(retrieved.id, id);
+    assert_eq!(retrieved.type_field, "HSP");
+    assert_eq!(retrieved.domain, "hsp.example.com");
+}
+
+pub async fn subscriber_register_duplicate(storage: Arc<dyn Storage>) {
+    let id = unique_id("subscriber");
+    storage
+        .register_subscriber(subscriber(&id, "HSP", "hsp.example.com"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .register_subscriber(subscriber(&id, "HSP", "hsp.example.com"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+}
+
+pub async fn subscriber_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_subscriber(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn subscriber_update(storage: Arc<dyn Storage>) {
+    let id = unique_id("subscriber");
+    let mut subscriber = subscriber(&id, "HSP", "hsp.example.com");
+    subscriber.status = ParticipantStatus::Initiated;
+    storage.register_subscriber(subscriber.clone()).await.unwrap();
+
+    subscriber.status = ParticipantStatus::Subscribed;
+    subscriber.encryption_public_key = Some("ZW5jcnlwdGlvbi1rZXk=".to_string());
+    let key = SubscriberKey {
+        unique_key_id: "key-2".to_string(),
+        purpose: KeyPurpose::Signing,
+        public_key: "c2lnbmluZy1rZXk=".to_string(),
+        algorithm: SignatureAlgorithm::EcdsaP256Sha256,
+        valid_from: Utc::now(),
+        valid_until: None,
+    };
+    subscriber.keys.push(key.clone());
+    let verification = DomainVerification {
+        request_id: unique_id("request"),
+        host: "hsp.example.com".to_string(),
+        issued_at: Utc::now(),
+        verified_at: Some(Utc::now()),
+        method: Some(DomainVerificationMethod::DnsTxt),
+    };
+    subscriber.domain_verification = Some(verification.clone());
+    storage.update_subscriber(subscriber).await.unwrap();
+
+    let retrieved = storage.get_subscriber(&id).await.unwrap();
+    assert_eq!(retrieved.status, ParticipantStatus::Subscribed);
+    assert_eq!(
+        retrieved.encryption_public_key.as_deref(),
+        Some("ZW5jcnlwdGlvbi1rZXk=")
+    );
+    assert_eq!(retrieved.keys, vec![key]);
+    assert_eq!(retrieved.domain_verification, Some(verification));
+}
+
+pub async fn subscriber_update_missing(storage: Arc<dyn Storage>) {
+    let result = storage
+        .update_subscriber(subscriber(&unique_id("missing"), "HSP", "hsp.example.com"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn subscriber_lookup(storage: Arc<dyn Storage>) {
+    let hsp_id = unique_id("subscriber");
+    storage
+        .register_subscriber(subscriber(&hsp_id, "HSP", "hsp.example.com"))
+        .await
+        .unwrap();
+    storage
+        .register_subscriber(subscriber(&unique_id("subscriber"), "EUA", "hsp.example.com"))
+        .await
+        .unwrap();
+
+    let found = storage
+        .lookup_subscriber(NetworkRegistryLookup {
+            type_field: "HSP".to_string(),
+            domain: "hsp.example.com".to_string(),
+            city: None,
+            country: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(found.id, hsp_id);
+}
+
+pub async fn subscriber_lookup_missing(storage: Arc<dyn Storage>) {
+    storage
+        .register_subscriber(subscriber(&unique_id("subscriber"), "HSP", "hsp.example.com"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .lookup_subscriber(NetworkRegistryLookup {
+            type_field: "GATEWAY".to_string(),
+            domain: "hsp.example.com".to_string(),
+            city: None,
+            country: None,
+        })
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn subscriber_list(storage: Arc<dyn Storage>) {
+    assert!(storage.list_subscribers().await.unwrap().is_empty());
+
+    let mut expected = vec![unique_id("subscriber"), unique_id("subscriber")];
+    expected.sort();
+    for id in expected.iter().rev() {
+        storage
+            .register_subscriber(subscriber(id, "HSP", "hsp.example.com"))
+            .await
+            .unwrap();
+    }
+
+    let subscribers = storage.list_subscribers().await.unwrap();
+    assert_eq!(ids(&subscribers, |s| s.id.clone()), expected);
+}
+
+// Transaction tracking
+
+pub async fn transaction_record_and_get(storage: Arc<dyn Storage>) {
+    let id = unique_id("transaction");
+    let data = json!({ "status": "SELECTED", "items": ["item-1"] });
+    storage.record_transaction(&id, data.clone()).await.unwrap();
+
+    let retrieved = storage.get_transaction(&id).await.unwrap();
+    assert_eq!(retrieved, data);
+}
+
+pub async fn transaction_record_overwrites(storage: Arc<dyn Storage>) {
+    let id = unique_id("transaction");
+    storage
+        .record_transaction(&id, json!({ "status": "SELECTED" }))
+        .await
+        .unwrap();
+    storage
+        .record_transaction(&id, json!({ "status": "QUOTED" }))
+        .await
+        .unwrap();
+
+    let retrieved = storage.get_transaction(&id).await.unwrap();
+    assert_eq!(retrieved, json!({ "status": "QUOTED" }));
+}
+
+pub async fn transaction_get_missing(storage: Arc<dyn Storage>) {
+    let result = storage.get_transaction(&unique_id("missing")).await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Search responses
+
+fn search_response(name: &str) -> SearchResponse {
+    SearchResponse {
+        catalog: catalog(name),
+    }
+}
+
+pub async fn search_responses_record_and_list(storage: Arc<dyn Storage>) {
+    let transaction_id = unique_id("transaction");
+    let mut providers = vec![unique_id("provider"), unique_id("provider")];
+    providers.sort();
+    for provider_id in providers.iter().rev() {
+        storage
+            .record_search_response(
+                &transaction_id,
+                "message-1",
+                provider_id,
+                search_response(provider_id),
+            )
+            .await
+            .unwrap();
+    }
+    // Responses to another search of the transaction are kept apart
+    storage
+        .record_search_response(
+            &transaction_id,
+            "message-2",
+            &providers[0],
+            search_response("Later Search"),
+        )
+        .await
+        .unwrap();
+
+    let responses = storage
+        .list_search_responses(&transaction_id, "message-1")
+        .await
+        .unwrap();
+    assert_eq!(
+        ids(&responses, |(provider_id, _)| provider_id.clone()),
+        providers
+    );
+    for (provider_id, response) in &responses {
+        assert_eq!(&response.catalog.descriptor.name, provider_id);
+    }
+
+    let none = storage
+        .list_search_responses(&unique_id("missing"), "message-1")
+        .await
+        .unwrap();
+    assert!(none.is_empty());
+}
+
+pub async fn search_responses_record_replaces(storage: Arc<dyn Storage>) {
+    let transaction_id = unique_id("transaction");
+    for name in ["First Catalog", "Second Catalog"] {
+        storage
+            .record_search_response(&transaction_id, "message-1", "hsp-1", search_response(name))
+            .await
+            .unwrap();
+    }
+
+    let responses = storage
+        .list_search_responses(&transaction_id, "message-1")
+        .await
+        .unwrap();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].1.catalog.descriptor.name, "Second Catalog");
+}
+
+// Order history
+
+fn order_event(order_id: &str, action: &str, new_state: &str) -> OrderEvent {
+    OrderEvent {
+        order_id: order_id.to_string(),
+        entity: EventEntity::Order,
+        entity_id: order_id.to_string(),
+        actor: "eua-1".to_string(),
+        action: action.to_string(),
+        previous_state: None,
+        new_state: Some(new_state.to_string()),
+        message_id: unique_id("message"),
+        recorded_at: Utc::now(),
+    }
+}
+
+pub async fn order_events_append_and_list(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let other = unique_id("order");
+    let expected = vec![
+        order_event(&id, "init", "INITIALIZED"),
+        order_event(&id, "on_init", "QUOTED"),
+        order_event(&id, "confirm", "CONFIRMED"),
+    ];
+    for event in &expected {
+        storage.append_order_event(event.clone()).await.unwrap();
+    }
+    storage
+        .append_order_event(order_event(&other, "init", "INITIALIZED"))
+        .await
+        .unwrap();
+
+    // Events come back in the order they were appended
+    let events = storage.list_order_events(&id).await.unwrap();
+    assert_eq!(events, expected);
+}
+
+pub async fn order_events_missing_order(storage: Arc<dyn Storage>) {
+    let events = storage
+        .list_order_events(&unique_id("missing"))
+        .await
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+// Subscriber status audit trail
+
+fn status_change(
+    subscriber_id: &str,
+    from: ParticipantStatus,
+    to: ParticipantStatus,
+) -> SubscriberStatusChange {
+    SubscriberStatusChange {
+        subscriber_id: subscriber_id.to_string(),
+        from,
+        to,
+        reason: format!("Moved to {}", to),
+        changed_by: Some("registry-admin".to_string()),
+        changed_at: Utc::now(),
+    }
+}
+
+pub async fn subscriber_status_changes_append_and_list(storage: Arc<dyn Storage>) {
+    let id = unique_id("subscriber");
+    let other = unique_id("subscriber");
+    let expected = vec![
+        status_change(
+            &id,
+            ParticipantStatus::Subscribed,
+            ParticipantStatus::Active,
+        ),
+        status_change(&id, ParticipantStatus::Active, ParticipantStatus::Suspended),
+        status_change(&id, ParticipantStatus::Suspended, ParticipantStatus::Active),
+    ];
+    for change in &expected {
+        storage
+            .append_subscriber_status_change(change.clone())
+            .await
+            .unwrap();
+    }
+    storage
+        .append_subscriber_status_change(status_change(
+            &other,
+            ParticipantStatus::Active,
+            ParticipantStatus::Inactive,
+        ))
+        .await
+        .unwrap();
+
+    // Changes come back in the order they were appended
+    let changes = storage.list_subscriber_status_changes(&id).await.unwrap();
+    assert_eq!(changes, expected);
+}
+
+pub async fn subscriber_status_changes_missing(storage: Arc<dyn Storage>) {
+    let changes = storage
+        .list_subscriber_status_changes(&unique_id("missing"))
+        .await
+        .unwrap();
+    assert!(changes.is_empty());
+}
+
+// Message deduplication
+
+fn processed_message(subscriber_id: &str, message_id: &str, action: &str) -> ProcessedMessage {
+    ProcessedMessage {
+        subscriber_id: subscriber_id.to_string(),
+        message_id: message_id.to_string(),
+        action: action.to_string(),
+        response: None,
+        received_at: Utc::now(),
+        expires_at: Utc::now() + Duration::hours(1),
+    }
+}
+
+pub async fn processed_message_create_and_get(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("message");
+    let created = storage
+        .create_processed_message(processed_message("eua-1", &message_id, "init"))
+        .await
+        .unwrap();
+
+    let retrieved = storage
+        .get_processed_message("eua-1", &message_id, "init")
+        .await
+        .unwrap();
+    assert_eq!(retrieved, created);
+}
+
+pub async fn processed_message_create_duplicate(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("message");
+    storage
+        .create_processed_message(processed_message("eua-1", &message_id, "init"))
+        .await
+        .unwrap();
+
+    let result = storage
+        .create_processed_message(processed_message("eua-1", &message_id, "init"))
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+
+    // The key covers sender, message ID and action
+    for (subscriber_id, action) in [("eua-2", "init"), ("eua-1", "confirm")] {
+        storage
+            .create_processed_message(processed_message(subscriber_id, &message_id, action))
+            .await
+            .unwrap();
+    }
+}
+
+pub async fn processed_message_replaces_expired(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("message");
+    let mut expired = processed_message("eua-1", &message_id, "init");
+    expired.received_at = Utc::now() - Duration::hours(2);
+    expired.expires_at = Utc::now() - Duration::hours(1);
+    storage.create_processed_message(expired).await.unwrap();
+
+    let replacement = storage
+        .create_processed_message(processed_message("eua-1", &message_id, "init"))
+        .await
+        .unwrap();
+    let retrieved = storage
+        .get_processed_message("eua-1", &message_id, "init")
+        .await
+        .unwrap();
+    assert_eq!(retrieved, replacement);
+}
+
+pub async fn processed_message_update(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("message");
+    let mut message = storage
+        .create_processed_message(processed_message("hsp-1", &message_id, "on_status"))
+        .await
+        .unwrap();
+
+    message.response = Some(RecordedResponse {
+        status: 200,
+        body: json!({ "message": { "ack": { "status": "ACK" } } }),
+    });
+    storage
+        .update_processed_message(message.clone())
+        .await
+        .unwrap();
+
+    let retrieved = storage
+        .get_processed_message("hsp-1", &message_id, "on_status")
+        .await
+        .unwrap();
+    assert_eq!(retrieved, message);
+}
+
+pub async fn processed_message_delete(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("message");
+    storage
+        .create_processed_message(processed_message("eua-1", &message_id, "cancel"))
+        .await
+        .unwrap();
+
+    storage
+        .delete_processed_message("eua-1", &message_id, "cancel")
+        .await
+        .unwrap();
+    let result = storage
+        .get_processed_message("eua-1", &message_id, "cancel")
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+
+    // A deleted message can be claimed again
+    storage
+        .create_processed_message(processed_message("eua-1", &message_id, "cancel"))
+        .await
+        .unwrap();
+}
+
+pub async fn processed_message_missing(storage: Arc<dyn Storage>) {
+    let message_id = unique_id("missing");
+
+    let result = storage
+        .get_processed_message("eua-1", &message_id, "init")
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+
+    let result = storage
+        .update_processed_message(processed_message("eua-1", &message_id, "init"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+
+    let result = storage
+        .delete_processed_message("eua-1", &message_id, "init")
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Ratings
+
+fn rating(category: RatingCategory, id: &str, value: u8) -> Rating {
+    Rating {
+        rating_category: category,
+        id: id.to_string(),
+        value,
+        feedback: None,
+    }
+}
+
+pub async fn ratings_record_and_aggregate(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let agent_id = unique_id("agent");
+
+    let aggregates = storage
+        .record_ratings(
+            &unique_id("order"),
+            vec![
+                rating(RatingCategory::Provider, &provider_id, 5),
+                rating(RatingCategory::Agent, &agent_id, 4),
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(aggregates.len(), 2);
+    assert_eq!(aggregates[0].id, provider_id);
+    assert_eq!(aggregates[0].count, 1);
+    assert_eq!(aggregates[0].average, 5.0);
+    assert_eq!(aggregates[1].rating_category, RatingCategory::Agent);
+
+    let aggregates = storage
+        .record_ratings(
+            &unique_id("order"),
+            vec![rating(RatingCategory::Provider, &provider_id, 2)],
+        )
+        .await
+        .unwrap();
+    assert_eq!(aggregates[0].count, 2);
+    assert_eq!(aggregates[0].average, 3.5);
+
+    let aggregate = storage
+        .get_rating_aggregate(RatingCategory::Provider, &provider_id)
+        .await
+        .unwrap();
+    assert_eq!(aggregate.count, 2);
+    assert_eq!(aggregate.average, 3.5);
+
+    // Aggregates are kept per category
+    let result = storage
+        .get_rating_aggregate(RatingCategory::Agent, &provider_id)
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+pub async fn ratings_recorded_once_per_order(storage: Arc<dyn Storage>) {
+    let order_id = unique_id("order");
+    let provider_id = unique_id("provider");
+    let agent_id = unique_id("agent");
+
+    storage
+        .record_ratings(
+            &order_id,
+            vec![rating(RatingCategory::Provider, &provider_id, 5)],
+        )
+        .await
+        .unwrap();
+
+    // A batch rating an already rated entity is rejected as a whole
+    let result = storage
+        .record_ratings(
+            &order_id,
+            vec![
+                rating(RatingCategory::Agent, &agent_id, 3),
+                rating(RatingCategory::Provider, &provider_id, 1),
+            ],
+        )
+        .await;
+    assert!(matches!(result, Err(StorageError::Duplicate(_))));
+
+    let result = storage
+        .get_rating_aggregate(RatingCategory::Agent, &agent_id)
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+    let aggregate = storage
+        .get_rating_aggregate(RatingCategory::Provider, &provider_id)
+        .await
+        .unwrap();
+    assert_eq!(aggregate.count, 1);
+    assert_eq!(aggregate.average, 5.0);
+}
+
+pub async fn rating_aggregate_missing(storage: Arc<dyn Storage>) {
+    let result = storage
+        .get_rating_aggregate(RatingCategory::Provider, &unique_id("missing"))
+        .await;
+    assert!(matches!(result, Err(StorageError::NotFound(_))));
+}
+
+// Concurrency and ordering guarantees
+
+pub async fn concurrent_distinct_creates(storage: Arc<dyn Storage>) {
+    let mut expected: Vec<String> = (0..20).map(|_| unique_id("provider")).collect();
+
+    let handles: Vec<_> = expected
+        .iter()
+        .cloned()
+        .map(|id| {
+            let storage = storage.clone();
+            tokio::spawn(async move { storage.create_provider(provider(&id)).await })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+    expected.sort();
+
+    let providers = storage.list_providers().await.unwrap();
+    assert_eq!(ids(&providers, |p| p.id.clone()), expected);
+}
+
+pub async fn concurrent_duplicate_creates(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+
+    let handles: Vec<_> = (0..10)
+        .map(|_| {
+            let storage = storage.clone();
+            let id = id.clone();
+            tokio::spawn(
+                async move { storage.create_order(order(&id, "provider-1", "customer-1")).await },
+            )
+        })
+        .collect();
+
+    let mut created = 0;
+    for handle in handles {
+        match handle.await.unwrap() {
+            Ok(_) => created += 1,
+            Err(StorageError::Duplicate(_)) => {}
+            Err(err) => panic!("Unexpected error for concurrent create: {}", err),
+        }
+    }
+
+    // Exactly one writer wins, every other writer sees a duplicate
+    assert_eq!(created, 1);
+    assert_eq!(storage.get_order(&id).await.unwrap().id, id);
+}
+
+pub async fn concurrent_transaction_writes(storage: Arc<dyn Storage>) {
+    let ids: Vec<String> = (0..20).map(|_| unique_id("transaction")).collect();
+
+    let handles: Vec<_> = ids
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(index, id)| {
+            let storage = storage.clone();
+            tokio::spawn(async move {
+                storage
+                    .record_transaction(&id, json!({ "index": index }))
+                    .await
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    for (index, id) in ids.iter().enumerate() {
+        let data = storage.get_transaction(id).await.unwrap();
+        assert_eq!(data, json!({ "index": index }));
+    }
+}
+
+pub async fn concurrent_search_responses(storage: Arc<dyn Storage>) {
+    let transaction_id = unique_id("transaction");
+    let mut providers: Vec<String> = (0..20).map(|_| unique_id("provider")).collect();
+
+    let handles: Vec<_> = providers
+        .iter()
+        .cloned()
+        .map(|provider_id| {
+            let storage = storage.clone();
+            let transaction_id = transaction_id.clone();
+            tokio::spawn(async move {
+                storage
+                    .record_search_response(
+                        &transaction_id,
+                        "message-1",
+                        &provider_id,
+                        search_response(&provider_id),
+                    )
+                    .await
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+    providers.sort();
+
+    // Every provider's response is kept
+    let responses = storage
+        .list_search_responses(&transaction_id, "message-1")
+        .await
+        .unwrap();
+    assert_eq!(
+        ids(&responses, |(provider_id, _)| provider_id.clone()),
+        providers
+    );
+}
+
+pub async fn concurrent_overlapping_bookings(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+    let start = Utc::now() + Duration::days(2);
+
+    let handles: Vec<_> = (0..10)
+        .map(|offset| {
+            let storage = storage.clone();
+            let mut booking = fulfillment(&unique_id("fulfillment"), &provider_id);
+            booking.start.time.timestamp = start + Duration::minutes(offset);
+            tokio::spawn(async move { storage.book_fulfillment(booking).await })
+        })
+        .collect();
+
+    let mut booked = 0;
+    for handle in handles {
+        match handle.await.unwrap() {
+            Ok(_) => booked += 1,
+            Err(StorageError::Conflict(_)) => {}
+            Err(err) => panic!("Unexpected error for concurrent booking: {}", err),
+        }
+    }
+
+    // Every slot overlaps every other, so exactly one booking wins
+    assert_eq!(booked, 1);
+    let fulfillments = storage
+        .list_fulfillments_by_provider(&provider_id)
+        .await
+        .unwrap();
+    assert_eq!(fulfillments.len(), 1);
+}
+
+pub async fn concurrent_order_changes(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let record = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+
+    let handles: Vec<_> = (0..10)
+        .map(|n| {
+            let storage = storage.clone();
+            let change = changed(record.clone(), &format!("change-{}", n));
+            tokio::spawn(async move { storage.record_order_change(change).await })
+        })
+        .collect();
+
+    let mut recorded = 0;
+    for handle in handles {
+        match handle.await.unwrap() {
+            Ok(_) => recorded += 1,
+            Err(StorageError::Conflict(_)) => {}
+            Err(err) => panic!("Unexpected error for concurrent change: {}", err),
+        }
+    }
+
+    // Every change claims version 1, so exactly one is recorded
+    assert_eq!(recorded, 1);
+    let retrieved = storage.get_order(&id).await.unwrap();
+    assert_eq!(retrieved.changes.len(), 1);
+    assert_eq!(retrieved.changes[0].version, 1);
+}
+
+pub async fn concurrent_ratings(storage: Arc<dyn Storage>) {
+    let provider_id = unique_id("provider");
+
+    let handles: Vec<_> = (0..20u8)
+        .map(|index| {
+            let storage = storage.clone();
+            let value = index % 5 + 1;
+            let ratings = vec![rating(RatingCategory::Provider, &provider_id, value)];
+            tokio::spawn(async move { storage.record_ratings(&unique_id("order"), ratings).await })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    // No rating is lost to a concurrent update of the aggregate
+    let aggregate = storage
+        .get_rating_aggregate(RatingCategory::Provider, &provider_id)
+        .await
+        .unwrap();
+    assert_eq!(aggregate.count, 20);
+    assert_eq!(aggregate.average, 3.0);
+}
+
+pub async fn sequential_updates_last_write_wins(storage: Arc<dyn Storage>) {
+    let id = unique_id("order");
+    let mut record = storage
+        .create_order(order(&id, "provider-1", "customer-1"))
+        .await
+        .unwrap();
+
+    // Each completed write must be visible to the next read
+    for state in [
+        OrderState::Quoted,
+        OrderState::Confirmed,
+        OrderState::InProgress,
+        OrderState::Completed,
+    ] {
+        record.state = state;
+        storage.update_order(record.clone()).await.unwrap();
+        assert_eq!(storage.get_order(&id).await.unwrap().state, state);
+    }
+}
@@ -1,17 +1,23 @@
 use async_trait::async_trait;
-use chrono::Utc;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
 use crate::models::{
     catalog::{Catalog, Item, SearchRequest, SearchResponse},
     fulfillment::Fulfillment,
-    network_registry::{NetworkRegistryLookup, Subscriber},
+    history::OrderEvent,
+    idempotency::ProcessedMessage,
+    network_registry::{NetworkRegistryLookup, Subscriber, SubscriberStatusChange},
     order::Order,
     provider::Provider,
+    rating::{AggregateRating, Rating, RatingCategory},
+    tracking::Tracking,
 };
 
+use crate::storage::booking::ensure_slot_free;
+use crate::storage::search::search_catalogs;
 use crate::storage::{Storage, StorageError, StorageResult};
 
 #[cfg(test)]
@@ -22,9 +28,54 @@ pub struct MemoryStorage {
     providers: RwLock<HashMap<String, Provider>>,
     catalogs: RwLock<HashMap<String, Catalog>>,
     orders: RwLock<HashMap<String, Order>>,
+    order_events: RwLock<HashMap<String, Vec<OrderEvent>>>,
     fulfillments: RwLock<HashMap<String, Fulfillment>>,
+    tracking: RwLock<HashMap<String, Tracking>>,
     subscribers: RwLock<HashMap<String, Subscriber>>,
+    subscriber_status_changes: RwLock<HashMap<String, Vec<SubscriberStatusChange>>>,
     transactions: RwLock<HashMap<String, serde_json::Value>>,
+    search_responses: RwLock<HashMap<SearchKey, BTreeMap<String, SearchResponse>>>,
+    processed_messages: RwLock<HashMap<MessageKey, ProcessedMessage>>,
+    order_ratings: RwLock<HashMap<OrderRatingKey, Rating>>,
+    rating_totals: RwLock<HashMap<RatedEntityKey, RatingTotal>>,
+}
+
+/// Key of a processed message: subscriber ID, message ID and action
+type MessageKey = (String, String, String);
+
+fn message_key(subscriber_id: &str, message_id: &str, action: &str) -> MessageKey {
+    (
+        subscriber_id.to_string(),
+        message_id.to_string(),
+        action.to_string(),
+    )
+}
+
+/// Key of a network search: transaction ID and message ID
+type SearchKey = (String, String);
+
+/// Key of a rated entity: rating category code and entity ID
+type RatedEntityKey = (&'static str, String);
+
+/// Key of a rating given for an order: order ID and rated entity
+type OrderRatingKey = (String, RatedEntityKey);
+
+fn rated_entity_key(category: RatingCategory, id: &str) -> RatedEntityKey {
+    (category.code(), id.to_string())
+}
+
+/// Listed records in ID order, as the `Storage` contract requires
+fn sorted_by_id<T>(mut records: Vec<T>, id: impl Fn(&T) -> &String) -> Vec<T> {
+    records.sort_by(|a, b| id(a).cmp(id(b)));
+    records
+}
+
+/// Running total of the ratings of an entity
+#[derive(Default)]
+struct RatingTotal {
+    count: u64,
+    total: u64,
+    updated_at: DateTime<Utc>,
 }
 
 impl MemoryStorage {
@@ -34,9 +85,16 @@ impl MemoryStorage {
             providers: RwLock::new(HashMap::new()),
             catalogs: RwLock::new(HashMap::new()),
             orders: RwLock::new(HashMap::new()),
+            order_events: RwLock::new(HashMap::new()),
             fulfillments: RwLock::new(HashMap::new()),
+            tracking: RwLock::new(HashMap::new()),
             subscribers: RwLock::new(HashMap::new()),
+            subscriber_status_changes: RwLock::new(HashMap::new()),
             transactions: RwLock::new(HashMap::new()),
+            search_responses: RwLock::new(HashMap::new()),
+            processed_messages: RwLock::new(HashMap::new()),
+            order_ratings: RwLock::new(HashMap::new()),
+            rating_totals: RwLock::new(HashMap::new()),
         }
     }
 
@@ -127,7 +185,10 @@ impl Storage for MemoryStorage {
             .read()
             .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
 
-        Ok(providers.values().cloned().collect())
+        Ok(sorted_by_id(
+            providers.values().cloned().collect(),
+            |provider| &provider.id,
+        ))
     }
 
     // Catalog operations
@@ -197,22 +258,14 @@ impl Storage for MemoryStorage {
             .read()
             .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
 
-        // This is a simplified search implementation for in-memory storage
-        // In a real implementation, we would apply filters based on the search request
+        // Sort by provider ID so results are stable across calls
+        let mut candidates: Vec<(String, Catalog)> = catalogs
+            .iter()
+            .map(|(provider_id, catalog)| (provider_id.clone(), catalog.clone()))
+            .collect();
+        candidates.sort_by(|a, b| a.0.cmp(&b.0));
 
-        // For now, just return the first catalog that matches any criteria
-        // or an empty response if no catalogs are found
-        if catalogs.is_empty() {
-            return Err(StorageError::NotFound("No catalogs found".to_string()));
-        }
-
-        // For simplicity, just return the first catalog found
-        // In a real implementation, this would be more sophisticated
-        let first_catalog = catalogs.values().next().cloned().unwrap();
-
-        Ok(SearchResponse {
-            catalog: first_catalog,
-        })
+        search_catalogs(candidates, &request)
     }
 
     // Order operations
@@ -264,17 +317,40 @@ impl Storage for MemoryStorage {
         Ok(order_clone)
     }
 
+    async fn record_order_change(&self, order: Order) -> StorageResult<Order> {
+        let mut orders = self
+            .orders
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        let stored = orders.get(&order.id).ok_or_else(|| {
+            StorageError::NotFound(format!("Order with ID {} not found", order.id))
+        })?;
+        if stored.changes.len() + 1 != order.changes.len() {
+            return Err(StorageError::Conflict(format!(
+                "Order {} was changed concurrently",
+                order.id
+            )));
+        }
+
+        orders.insert(order.id.clone(), order.clone());
+        Ok(order)
+    }
+
     async fn list_orders_by_provider(&self, provider_id: &str) -> StorageResult<Vec<Order>> {
         let orders = self
             .orders
             .read()
             .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
 
-        Ok(orders
-            .values()
-            .filter(|order| order.provider.id == provider_id)
-            .cloned()
-            .collect())
+        Ok(sorted_by_id(
+            orders
+                .values()
+                .filter(|order| order.provider.id == provider_id)
+                .cloned()
+                .collect(),
+            |order| &order.id,
+        ))
     }
 
     async fn list_orders_by_customer(&self, customer_id: &str) -> StorageResult<Vec<Order>> {
@@ -285,11 +361,37 @@ impl Storage for MemoryStorage {
 
         // In a real implementation, we would filter by customer ID in the billing info
         // This is a simplified version that assumes customer ID is in the billing name field
-        Ok(orders
-            .values()
-            .filter(|order| order.billing.name == customer_id)
-            .cloned()
-            .collect())
+        Ok(sorted_by_id(
+            orders
+                .values()
+                .filter(|order| order.billing.name == customer_id)
+                .cloned()
+                .collect(),
+            |order| &order.id,
+        ))
+    }
+
+    // Order history
+    async fn append_order_event(&self, event: OrderEvent) -> StorageResult<()> {
+        let mut order_events = self
+            .order_events
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        order_events
+            .entry(event.order_id.clone())
+            .or_default()
+            .push(event);
+        Ok(())
+    }
+
+    async fn list_order_events(&self, order_id: &str) -> StorageResult<Vec<OrderEvent>> {
+        let order_events = self
+            .order_events
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        Ok(order_events.get(order_id).cloned().unwrap_or_default())
     }
 
     // Fulfillment operations
@@ -341,6 +443,22 @@ impl Storage for MemoryStorage {
         Ok(fulfillment_clone)
     }
 
+    async fn delete_fulfillment(&self, id: &str) -> StorageResult<()> {
+        let mut fulfillments = self
+            .fulfillments
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        if fulfillments.remove(id).is_none() {
+            return Err(StorageError::NotFound(format!(
+                "Fulfillment with ID {} not found",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
     async fn list_fulfillments_by_provider(
         &self,
         provider_id: &str,
@@ -350,11 +468,85 @@ impl Storage for MemoryStorage {
             .read()
             .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
 
-        Ok(fulfillments
-            .values()
-            .filter(|fulfillment| fulfillment.provider_id == provider_id)
-            .cloned()
-            .collect())
+        Ok(sorted_by_id(
+            fulfillments
+                .values()
+                .filter(|fulfillment| fulfillment.provider_id == provider_id)
+                .cloned()
+                .collect(),
+            |fulfillment| &fulfillment.id,
+        ))
+    }
+
+    async fn book_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        let mut fulfillments = self
+            .fulfillments
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        if fulfillments.contains_key(&fulfillment.id) {
+            return Err(StorageError::Duplicate(format!(
+                "Fulfillment with ID {} already exists",
+                fulfillment.id
+            )));
+        }
+        ensure_slot_free(
+            &fulfillment,
+            fulfillments
+                .values()
+                .filter(|other| other.provider_id == fulfillment.provider_id),
+        )?;
+
+        fulfillments.insert(fulfillment.id.clone(), fulfillment.clone());
+        Ok(fulfillment)
+    }
+
+    async fn rebook_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        let mut fulfillments = self
+            .fulfillments
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        if !fulfillments.contains_key(&fulfillment.id) {
+            return Err(StorageError::NotFound(format!(
+                "Fulfillment with ID {} not found",
+                fulfillment.id
+            )));
+        }
+        ensure_slot_free(
+            &fulfillment,
+            fulfillments
+                .values()
+                .filter(|other| other.provider_id == fulfillment.provider_id),
+        )?;
+
+        fulfillments.insert(fulfillment.id.clone(), fulfillment.clone());
+        Ok(fulfillment)
+    }
+
+    // Fulfillment tracking
+    async fn save_tracking(&self, tracking: Tracking) -> StorageResult<Tracking> {
+        let mut records = self
+            .tracking
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        records.insert(tracking.fulfillment_id.clone(), tracking.clone());
+        Ok(tracking)
+    }
+
+    async fn get_tracking(&self, fulfillment_id: &str) -> StorageResult<Tracking> {
+        let records = self
+            .tracking
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        records.get(fulfillment_id).cloned().ok_or_else(|| {
+            StorageError::NotFound(format!(
+                "Tracking of fulfillment {} not found",
+                fulfillment_id
+            ))
+        })
     }
 
     // Network registry operations
@@ -388,6 +580,24 @@ impl Storage for MemoryStorage {
             .ok_or_else(|| StorageError::NotFound(format!("Subscriber with ID {} not found", id)))
     }
 
+    async fn update_subscriber(&self, subscriber: Subscriber) -> StorageResult<Subscriber> {
+        let mut subscribers = self
+            .subscribers
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        if !subscribers.contains_key(&subscriber.id) {
+            return Err(StorageError::NotFound(format!(
+                "Subscriber with ID {} not found",
+                subscriber.id
+            )));
+        }
+
+        let subscriber_clone = subscriber.clone();
+        subscribers.insert(subscriber.id.clone(), subscriber);
+        Ok(subscriber_clone)
+    }
+
     async fn lookup_subscriber(&self, lookup: NetworkRegistryLookup) -> StorageResult<Subscriber> {
         let subscribers = self
             .subscribers
@@ -413,7 +623,38 @@ impl Storage for MemoryStorage {
             .read()
             .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
 
-        Ok(subscribers.values().cloned().collect())
+        Ok(sorted_by_id(
+            subscribers.values().cloned().collect(),
+            |subscriber| &subscriber.id,
+        ))
+    }
+
+    async fn append_subscriber_status_change(
+        &self,
+        change: SubscriberStatusChange,
+    ) -> StorageResult<()> {
+        let mut changes = self
+            .subscriber_status_changes
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        changes
+            .entry(change.subscriber_id.clone())
+            .or_default()
+            .push(change);
+        Ok(())
+    }
+
+    async fn list_subscriber_status_changes(
+        &self,
+        subscriber_id: &str,
+    ) -> StorageResult<Vec<SubscriberStatusChange>> {
+        let changes = self
+            .subscriber_status_changes
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        Ok(changes.get(subscriber_id).cloned().unwrap_or_default())
     }
 
     // Transaction tracking
@@ -441,4 +682,217 @@ impl Storage for MemoryStorage {
             StorageError::NotFound(format!("Transaction with ID {} not found", transaction_id))
         })
     }
+
+    // Search responses
+    async fn record_search_response(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+        provider_id: &str,
+        response: SearchResponse,
+    ) -> StorageResult<()> {
+        let mut search_responses = self
+            .search_responses
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        search_responses
+            .entry((transaction_id.to_string(), message_id.to_string()))
+            .or_default()
+            .insert(provider_id.to_string(), response);
+        Ok(())
+    }
+
+    async fn list_search_responses(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+    ) -> StorageResult<Vec<(String, SearchResponse)>> {
+        let search_responses = self
+            .search_responses
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        Ok(search_responses
+            .get(&(transaction_id.to_string(), message_id.to_string()))
+            .map(|responses| {
+                responses
+                    .iter()
+                    .map(|(provider_id, response)| (provider_id.clone(), response.clone()))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    // Ratings
+    async fn record_ratings(
+        &self,
+        order_id: &str,
+        ratings: Vec<Rating>,
+    ) -> StorageResult<Vec<AggregateRating>> {
+        let mut order_ratings = self
+            .order_ratings
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+        let mut rating_totals = self
+            .rating_totals
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        // Entities rated twice in the batch count as already rated
+        let rated_before = |index: usize, rating: &Rating| {
+            let key = rated_entity_key(rating.rating_category, &rating.id);
+            order_ratings.contains_key(&(order_id.to_string(), key))
+                || ratings[..index].iter().any(|other| {
+                    other.rating_category == rating.rating_category && other.id == rating.id
+                })
+        };
+        if let Some(rating) = ratings
+            .iter()
+            .enumerate()
+            .find_map(|(index, rating)| rated_before(index, rating).then_some(rating))
+        {
+            return Err(StorageError::Duplicate(format!(
+                "{} {} has already been rated for order {}",
+                rating.rating_category.code(),
+                rating.id,
+                order_id
+            )));
+        }
+
+        let now = Utc::now();
+        let mut aggregates = Vec::with_capacity(ratings.len());
+        for rating in ratings {
+            let key = rated_entity_key(rating.rating_category, &rating.id);
+            let totals = rating_totals.entry(key.clone()).or_default();
+            totals.count += 1;
+            totals.total += u64::from(rating.value);
+            totals.updated_at = now;
+
+            aggregates.push(AggregateRating::from_total(
+                rating.rating_category,
+                rating.id.clone(),
+                totals.count,
+                totals.total,
+                totals.updated_at,
+            ));
+            order_ratings.insert((order_id.to_string(), key), rating);
+        }
+        Ok(aggregates)
+    }
+
+    async fn get_rating_aggregate(
+        &self,
+        category: RatingCategory,
+        id: &str,
+    ) -> StorageResult<AggregateRating> {
+        let rating_totals = self
+            .rating_totals
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        rating_totals
+            .get(&rated_entity_key(category, id))
+            .map(|totals| {
+                AggregateRating::from_total(
+                    category,
+                    id,
+                    totals.count,
+                    totals.total,
+                    totals.updated_at,
+                )
+            })
+            .ok_or_else(|| {
+                StorageError::NotFound(format!("{} {} has not been rated", category.code(), id))
+            })
+    }
+
+    // Message deduplication
+    async fn create_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage> {
+        let mut processed_messages = self
+            .processed_messages
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        let key = message_key(&message.subscriber_id, &message.message_id, &message.action);
+        if let Some(existing) = processed_messages.get(&key) {
+            if existing.is_live(message.received_at) {
+                return Err(StorageError::Duplicate(format!(
+                    "Message {} for {} from {} already received",
+                    message.message_id, message.action, message.subscriber_id
+                )));
+            }
+        }
+
+        processed_messages.insert(key, message.clone());
+        Ok(message)
+    }
+
+    async fn get_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<ProcessedMessage> {
+        let processed_messages = self
+            .processed_messages
+            .read()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        processed_messages
+            .get(&message_key(subscriber_id, message_id, action))
+            .cloned()
+            .ok_or_else(|| {
+                StorageError::NotFound(format!(
+                    "Message {} for {} from {} not found",
+                    message_id, action, subscriber_id
+                ))
+            })
+    }
+
+    async fn update_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage> {
+        let mut processed_messages = self
+            .processed_messages
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        let key = message_key(&message.subscriber_id, &message.message_id, &message.action);
+        if !processed_messages.contains_key(&key) {
+            return Err(StorageError::NotFound(format!(
+                "Message {} for {} from {} not found",
+                message.message_id, message.action, message.subscriber_id
+            )));
+        }
+
+        processed_messages.insert(key, message.clone());
+        Ok(message)
+    }
+
+    async fn delete_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<()> {
+        let mut processed_messages = self
+            .processed_messages
+            .write()
+            .map_err(|e| StorageError::Internal(format!("Lock error: {}", e)))?;
+
+        processed_messages
+            .remove(&message_key(subscriber_id, message_id, action))
+            .map(|_| ())
+            .ok_or_else(|| {
+                StorageError::NotFound(format!(
+                    "Message {} for {} from {} not found",
+                    message_id, action, subscriber_id
+                ))
+            })
+    }
 }
@@ -1,9 +1,16 @@
 use super::*;
 use crate::models::provider::{Category, Descriptor, Provider};
+use crate::storage::conformance::storage_conformance_tests;
 use chrono::Utc;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+async fn conformance_storage() -> Option<Arc<dyn Storage>> {
+    Some(MemoryStorage::empty())
+}
+
+storage_conformance_tests!(conformance_storage);
+
 fn create_test_provider() -> Provider {
     Provider {
         id: Uuid::new_v4().to_string(),
@@ -24,6 +31,7 @@ fn create_test_provider() -> Provider {
             time: Some(Utc::now()),
             tags: Some(HashMap::new()),
         }],
+        support: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -1,11 +1,21 @@
+pub mod booking;
 pub mod memory;
+pub mod postgres;
+pub mod search;
+
+#[cfg(test)]
+pub(crate) mod conformance;
 
 use crate::models::{
     catalog::{Catalog, Item, SearchRequest, SearchResponse},
     fulfillment::Fulfillment,
-    network_registry::{NetworkRegistryLookup, Subscriber},
+    history::OrderEvent,
+    idempotency::ProcessedMessage,
+    network_registry::{NetworkRegistryLookup, Subscriber, SubscriberStatusChange},
     order::Order,
     provider::Provider,
+    rating::{AggregateRating, Rating, RatingCategory},
+    tracking::Tracking,
 };
 use async_trait::async_trait;
 use std::sync::Arc;
@@ -20,6 +30,9 @@ pub enum StorageError {
     #[error("Duplicate item: {0}")]
     Duplicate(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
 
@@ -31,6 +44,23 @@ pub enum StorageError {
 pub type StorageResult<T> = Result<T, StorageError>;
 
 /// Storage interface for persistence operations
+///
+/// Creates fail with `StorageError::Duplicate` when the key already exists, while
+/// reads, updates and deletes of missing records fail with `StorageError::NotFound`.
+/// List operations return records ordered by ID, except that order events and
+/// subscriber status changes are listed in the order they were appended. Processed messages are keyed by
+/// subscriber, message ID and action; creating one only fails while an
+/// existing record for the key is live, and expired records are replaced.
+/// Bookings fail with `StorageError::Conflict` when the slot overlaps another
+/// fulfillment of the provider; the check and the write are atomic across
+/// every instance sharing the backend. Each entity can be rated once per
+/// order: recording ratings fails with `StorageError::Duplicate` when one of
+/// them was already recorded for the order, in which case none are, and the
+/// entities' aggregates are updated atomically with the ratings.
+/// Recording an order change stores an order whose last change is new, and
+/// fails with `StorageError::Conflict` unless the stored order has all of its
+/// other changes, so concurrent updates cannot claim the same version.
+/// Every backend must pass the suite in `storage::conformance`.
 #[async_trait]
 pub trait Storage: Send + Sync + 'static {
     // Provider operations
@@ -50,24 +80,49 @@ pub trait Storage: Send + Sync + 'static {
     async fn create_order(&self, order: Order) -> StorageResult<Order>;
     async fn get_order(&self, id: &str) -> StorageResult<Order>;
     async fn update_order(&self, order: Order) -> StorageResult<Order>;
+    async fn record_order_change(&self, order: Order) -> StorageResult<Order>;
     async fn list_orders_by_provider(&self, provider_id: &str) -> StorageResult<Vec<Order>>;
     async fn list_orders_by_customer(&self, customer_id: &str) -> StorageResult<Vec<Order>>;
 
+    // Order history; events are append-only
+    async fn append_order_event(&self, event: OrderEvent) -> StorageResult<()>;
+    async fn list_order_events(&self, order_id: &str) -> StorageResult<Vec<OrderEvent>>;
+
     // Fulfillment operations
     async fn create_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment>;
     async fn get_fulfillment(&self, id: &str) -> StorageResult<Fulfillment>;
     async fn update_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment>;
+    async fn delete_fulfillment(&self, id: &str) -> StorageResult<()>;
     async fn list_fulfillments_by_provider(
         &self,
         provider_id: &str,
     ) -> StorageResult<Vec<Fulfillment>>;
 
+    // Slot bookings; cancelled fulfillments do not hold their slot
+    async fn book_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment>;
+    async fn rebook_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment>;
+
+    // Fulfillment tracking; saving replaces the fulfillment's previous record
+    async fn save_tracking(&self, tracking: Tracking) -> StorageResult<Tracking>;
+    async fn get_tracking(&self, fulfillment_id: &str) -> StorageResult<Tracking>;
+
     // Network registry operations
     async fn register_subscriber(&self, subscriber: Subscriber) -> StorageResult<Subscriber>;
     async fn get_subscriber(&self, id: &str) -> StorageResult<Subscriber>;
+    async fn update_subscriber(&self, subscriber: Subscriber) -> StorageResult<Subscriber>;
     async fn lookup_subscriber(&self, lookup: NetworkRegistryLookup) -> StorageResult<Subscriber>;
     async fn list_subscribers(&self) -> StorageResult<Vec<Subscriber>>;
 
+    // Subscriber status audit trail; changes are append-only
+    async fn append_subscriber_status_change(
+        &self,
+        change: SubscriberStatusChange,
+    ) -> StorageResult<()>;
+    async fn list_subscriber_status_changes(
+        &self,
+        subscriber_id: &str,
+    ) -> StorageResult<Vec<SubscriberStatusChange>>;
+
     // Transaction tracking
     async fn record_transaction(
         &self,
@@ -75,4 +130,53 @@ pub trait Storage: Send + Sync + 'static {
         data: serde_json::Value,
     ) -> StorageResult<()>;
     async fn get_transaction(&self, transaction_id: &str) -> StorageResult<serde_json::Value>;
+
+    // Search responses, one per provider and search message; recording a
+    // provider's response again replaces it. Listed by provider ID.
+    async fn record_search_response(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+        provider_id: &str,
+        response: SearchResponse,
+    ) -> StorageResult<()>;
+    async fn list_search_responses(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+    ) -> StorageResult<Vec<(String, SearchResponse)>>;
+
+    // Ratings; returns the updated aggregate of each rated entity
+    async fn record_ratings(
+        &self,
+        order_id: &str,
+        ratings: Vec<Rating>,
+    ) -> StorageResult<Vec<AggregateRating>>;
+    async fn get_rating_aggregate(
+        &self,
+        category: RatingCategory,
+        id: &str,
+    ) -> StorageResult<AggregateRating>;
+
+    // Message deduplication
+    async fn create_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage>;
+    async fn get_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<ProcessedMessage>;
+    async fn update_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage>;
+    async fn delete_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<()>;
 }
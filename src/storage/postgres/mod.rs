@@ -0,0 +1,938 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::migrate::Migrator;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::types::Json;
+
+use crate::config::DatabaseConfig;
+use crate::models::{
+    catalog::{Catalog, SearchRequest, SearchResponse},
+    fulfillment::Fulfillment,
+    history::OrderEvent,
+    idempotency::ProcessedMessage,
+    network_registry::{NetworkRegistryLookup, Subscriber, SubscriberStatusChange},
+    order::Order,
+    provider::Provider,
+    rating::{AggregateRating, Rating, RatingCategory},
+    tracking::Tracking,
+};
+
+use crate::storage::booking::ensure_slot_free;
+use crate::storage::search::search_catalogs;
+use crate::storage::{Storage, StorageError, StorageResult};
+
+#[cfg(test)]
+mod tests;
+
+/// Migrations embedded into the binary at compile time
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// PostgreSQL error code for foreign key violations
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+impl From<sqlx::Error> for StorageError {
+    fn from(err: sqlx::Error) -> Self {
+        match &err {
+            sqlx::Error::RowNotFound => StorageError::NotFound(err.to_string()),
+            sqlx::Error::Database(db_err)
+                if db_err.code().as_deref() == Some(FOREIGN_KEY_VIOLATION) =>
+            {
+                StorageError::NotFound(db_err.message().to_string())
+            }
+            _ => StorageError::Internal(format!("Database error: {}", err)),
+        }
+    }
+}
+
+/// PostgreSQL storage implementation backed by a sqlx connection pool
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect to the configured database and apply pending migrations
+    pub async fn connect(config: &DatabaseConfig) -> StorageResult<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .connect(&config.url)
+            .await?;
+
+        let storage = Self::from_pool(pool);
+        storage.run_migrations().await?;
+        Ok(storage)
+    }
+
+    /// Create a storage instance from an existing connection pool
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Apply all embedded migrations that have not been run yet
+    pub async fn run_migrations(&self) -> StorageResult<()> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| StorageError::Internal(format!("Migration error: {}", e)))
+    }
+
+    /// Insert or update a fulfillment once its slot is known to be free.
+    ///
+    /// A transaction-scoped advisory lock keyed by the provider serializes
+    /// bookings of the same provider across every instance, while bookings
+    /// of other providers proceed concurrently.
+    async fn write_booking(
+        &self,
+        fulfillment: Fulfillment,
+        insert: bool,
+    ) -> StorageResult<Fulfillment> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(&fulfillment.provider_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if !insert {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM fulfillments WHERE id = $1)")
+                    .bind(&fulfillment.id)
+                    .fetch_one(&mut *tx)
+                    .await?;
+            if !exists {
+                return Err(StorageError::NotFound(format!(
+                    "Fulfillment with ID {} not found",
+                    fulfillment.id
+                )));
+            }
+        }
+
+        let booked = sqlx::query_scalar::<_, Json<Fulfillment>>(
+            "SELECT data FROM fulfillments WHERE provider_id = $1",
+        )
+        .bind(&fulfillment.provider_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        ensure_slot_free(&fulfillment, booked.iter().map(|Json(other)| other))?;
+
+        let query = if insert {
+            "INSERT INTO fulfillments (id, provider_id, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO NOTHING"
+        } else {
+            "UPDATE fulfillments SET provider_id = $2, data = $3, updated_at = NOW() WHERE id = $1"
+        };
+        let result = sqlx::query(query)
+            .bind(&fulfillment.id)
+            .bind(&fulfillment.provider_id)
+            .bind(Json(&fulfillment))
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(if insert {
+                StorageError::Duplicate(format!(
+                    "Fulfillment with ID {} already exists",
+                    fulfillment.id
+                ))
+            } else {
+                StorageError::NotFound(format!("Fulfillment with ID {} not found", fulfillment.id))
+            });
+        }
+
+        tx.commit().await?;
+        Ok(fulfillment)
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    // Provider operations
+    async fn create_provider(&self, provider: Provider) -> StorageResult<Provider> {
+        let result = sqlx::query(
+            "INSERT INTO providers (id, data, created_at, updated_at) VALUES ($1, $2, $3, $4)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&provider.id)
+        .bind(Json(&provider))
+        .bind(provider.created_at)
+        .bind(provider.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Provider with ID {} already exists",
+                provider.id
+            )));
+        }
+
+        Ok(provider)
+    }
+
+    async fn get_provider(&self, id: &str) -> StorageResult<Provider> {
+        sqlx::query_scalar::<_, Json<Provider>>("SELECT data FROM providers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|Json(provider)| provider)
+            .ok_or_else(|| StorageError::NotFound(format!("Provider with ID {} not found", id)))
+    }
+
+    async fn update_provider(&self, provider: Provider) -> StorageResult<Provider> {
+        let result = sqlx::query("UPDATE providers SET data = $2, updated_at = $3 WHERE id = $1")
+            .bind(&provider.id)
+            .bind(Json(&provider))
+            .bind(provider.updated_at)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Provider with ID {} not found",
+                provider.id
+            )));
+        }
+
+        Ok(provider)
+    }
+
+    async fn delete_provider(&self, id: &str) -> StorageResult<()> {
+        let result = sqlx::query("DELETE FROM providers WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Provider with ID {} not found",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn list_providers(&self) -> StorageResult<Vec<Provider>> {
+        let providers = sqlx::query_scalar::<_, Json<Provider>>(
+            "SELECT data FROM providers ORDER BY id COLLATE \"C\"",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(providers
+            .into_iter()
+            .map(|Json(provider)| provider)
+            .collect())
+    }
+
+    // Catalog operations
+    async fn create_catalog(&self, provider_id: &str, catalog: Catalog) -> StorageResult<Catalog> {
+        // The foreign key on provider_id rejects catalogs for unknown providers
+        let result = sqlx::query(
+            "INSERT INTO catalogs (provider_id, data) VALUES ($1, $2)
+             ON CONFLICT (provider_id) DO NOTHING",
+        )
+        .bind(provider_id)
+        .bind(Json(&catalog))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| match StorageError::from(e) {
+            StorageError::NotFound(_) => {
+                StorageError::NotFound(format!("Provider with ID {} not found", provider_id))
+            }
+            other => other,
+        })?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Catalog for provider ID {} already exists",
+                provider_id
+            )));
+        }
+
+        Ok(catalog)
+    }
+
+    async fn get_catalog(&self, provider_id: &str) -> StorageResult<Catalog> {
+        sqlx::query_scalar::<_, Json<Catalog>>("SELECT data FROM catalogs WHERE provider_id = $1")
+            .bind(provider_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|Json(catalog)| catalog)
+            .ok_or_else(|| {
+                StorageError::NotFound(format!("Catalog for provider ID {} not found", provider_id))
+            })
+    }
+
+    async fn update_catalog(&self, provider_id: &str, catalog: Catalog) -> StorageResult<Catalog> {
+        let result =
+            sqlx::query("UPDATE catalogs SET data = $2, updated_at = NOW() WHERE provider_id = $1")
+                .bind(provider_id)
+                .bind(Json(&catalog))
+                .execute(&self.pool)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Catalog for provider ID {} not found",
+                provider_id
+            )));
+        }
+
+        Ok(catalog)
+    }
+
+    async fn search_catalog(&self, request: SearchRequest) -> StorageResult<SearchResponse> {
+        let candidates = sqlx::query_as::<_, (String, Json<Catalog>)>(
+            "SELECT provider_id, data FROM catalogs ORDER BY provider_id",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(provider_id, Json(catalog))| (provider_id, catalog))
+        .collect();
+
+        search_catalogs(candidates, &request)
+    }
+
+    // Order operations
+    async fn create_order(&self, order: Order) -> StorageResult<Order> {
+        let result = sqlx::query(
+            "INSERT INTO orders (id, provider_id, customer_id, data, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&order.id)
+        .bind(&order.provider.id)
+        .bind(&order.billing.name)
+        .bind(Json(&order))
+        .bind(order.created_at)
+        .bind(order.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Order with ID {} already exists",
+                order.id
+            )));
+        }
+
+        Ok(order)
+    }
+
+    async fn get_order(&self, id: &str) -> StorageResult<Order> {
+        sqlx::query_scalar::<_, Json<Order>>("SELECT data FROM orders WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|Json(order)| order)
+            .ok_or_else(|| StorageError::NotFound(format!("Order with ID {} not found", id)))
+    }
+
+    async fn update_order(&self, order: Order) -> StorageResult<Order> {
+        let result = sqlx::query(
+            "UPDATE orders SET provider_id = $2, customer_id = $3, data = $4, updated_at = $5
+             WHERE id = $1",
+        )
+        .bind(&order.id)
+        .bind(&order.provider.id)
+        .bind(&order.billing.name)
+        .bind(Json(&order))
+        .bind(order.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Order with ID {} not found",
+                order.id
+            )));
+        }
+
+        Ok(order)
+    }
+
+    async fn record_order_change(&self, order: Order) -> StorageResult<Order> {
+        let mut tx = self.pool.begin().await?;
+
+        let recorded: i32 = sqlx::query_scalar(
+            "SELECT jsonb_array_length(COALESCE(data->'changes', '[]'::jsonb))
+             FROM orders WHERE id = $1 FOR UPDATE",
+        )
+        .bind(&order.id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| StorageError::NotFound(format!("Order with ID {} not found", order.id)))?;
+        if recorded as usize + 1 != order.changes.len() {
+            return Err(StorageError::Conflict(format!(
+                "Order {} was changed concurrently",
+                order.id
+            )));
+        }
+
+        sqlx::query(
+            "UPDATE orders SET provider_id = $2, customer_id = $3, data = $4, updated_at = $5
+             WHERE id = $1",
+        )
+        .bind(&order.id)
+        .bind(&order.provider.id)
+        .bind(&order.billing.name)
+        .bind(Json(&order))
+        .bind(order.updated_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(order)
+    }
+
+    async fn list_orders_by_provider(&self, provider_id: &str) -> StorageResult<Vec<Order>> {
+        let orders = sqlx::query_scalar::<_, Json<Order>>(
+            "SELECT data FROM orders WHERE provider_id = $1 ORDER BY id COLLATE \"C\"",
+        )
+        .bind(provider_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|Json(order)| order).collect())
+    }
+
+    async fn list_orders_by_customer(&self, customer_id: &str) -> StorageResult<Vec<Order>> {
+        // Customer ID is taken from the billing name, matching the in-memory implementation
+        let orders = sqlx::query_scalar::<_, Json<Order>>(
+            "SELECT data FROM orders WHERE customer_id = $1 ORDER BY id COLLATE \"C\"",
+        )
+        .bind(customer_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orders.into_iter().map(|Json(order)| order).collect())
+    }
+
+    // Order history
+    async fn append_order_event(&self, event: OrderEvent) -> StorageResult<()> {
+        sqlx::query("INSERT INTO order_events (order_id, data, recorded_at) VALUES ($1, $2, $3)")
+            .bind(&event.order_id)
+            .bind(Json(&event))
+            .bind(event.recorded_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_order_events(&self, order_id: &str) -> StorageResult<Vec<OrderEvent>> {
+        let events = sqlx::query_scalar::<_, Json<OrderEvent>>(
+            "SELECT data FROM order_events WHERE order_id = $1 ORDER BY seq",
+        )
+        .bind(order_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(events.into_iter().map(|Json(event)| event).collect())
+    }
+
+    // Fulfillment operations
+    async fn create_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        let result = sqlx::query(
+            "INSERT INTO fulfillments (id, provider_id, data) VALUES ($1, $2, $3)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&fulfillment.id)
+        .bind(&fulfillment.provider_id)
+        .bind(Json(&fulfillment))
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Fulfillment with ID {} already exists",
+                fulfillment.id
+            )));
+        }
+
+        Ok(fulfillment)
+    }
+
+    async fn get_fulfillment(&self, id: &str) -> StorageResult<Fulfillment> {
+        sqlx::query_scalar::<_, Json<Fulfillment>>("SELECT data FROM fulfillments WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|Json(fulfillment)| fulfillment)
+            .ok_or_else(|| StorageError::NotFound(format!("Fulfillment with ID {} not found", id)))
+    }
+
+    async fn update_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        let result = sqlx::query(
+            "UPDATE fulfillments SET provider_id = $2, data = $3, updated_at = NOW() WHERE id = $1",
+        )
+        .bind(&fulfillment.id)
+        .bind(&fulfillment.provider_id)
+        .bind(Json(&fulfillment))
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Fulfillment with ID {} not found",
+                fulfillment.id
+            )));
+        }
+
+        Ok(fulfillment)
+    }
+
+    async fn delete_fulfillment(&self, id: &str) -> StorageResult<()> {
+        let result = sqlx::query("DELETE FROM fulfillments WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Fulfillment with ID {} not found",
+                id
+            )));
+        }
+
+        Ok(())
+    }
+
+    async fn list_fulfillments_by_provider(
+        &self,
+        provider_id: &str,
+    ) -> StorageResult<Vec<Fulfillment>> {
+        let fulfillments = sqlx::query_scalar::<_, Json<Fulfillment>>(
+            "SELECT data FROM fulfillments WHERE provider_id = $1 ORDER BY id COLLATE \"C\"",
+        )
+        .bind(provider_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(fulfillments
+            .into_iter()
+            .map(|Json(fulfillment)| fulfillment)
+            .collect())
+    }
+
+    async fn book_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        self.write_booking(fulfillment, true).await
+    }
+
+    async fn rebook_fulfillment(&self, fulfillment: Fulfillment) -> StorageResult<Fulfillment> {
+        self.write_booking(fulfillment, false).await
+    }
+
+    // Fulfillment tracking
+    async fn save_tracking(&self, tracking: Tracking) -> StorageResult<Tracking> {
+        sqlx::query(
+            "INSERT INTO fulfillment_tracking (fulfillment_id, data) VALUES ($1, $2)
+             ON CONFLICT (fulfillment_id) DO UPDATE SET data = EXCLUDED.data, updated_at = NOW()",
+        )
+        .bind(&tracking.fulfillment_id)
+        .bind(Json(&tracking))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(tracking)
+    }
+
+    async fn get_tracking(&self, fulfillment_id: &str) -> StorageResult<Tracking> {
+        sqlx::query_scalar::<_, Json<Tracking>>(
+            "SELECT data FROM fulfillment_tracking WHERE fulfillment_id = $1",
+        )
+        .bind(fulfillment_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|Json(tracking)| tracking)
+        .ok_or_else(|| {
+            StorageError::NotFound(format!(
+                "Tracking of fulfillment {} not found",
+                fulfillment_id
+            ))
+        })
+    }
+
+    // Network registry operations
+    async fn register_subscriber(&self, subscriber: Subscriber) -> StorageResult<Subscriber> {
+        let result = sqlx::query(
+            "INSERT INTO subscribers (id, type_field, domain, data, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&subscriber.id)
+        .bind(&subscriber.type_field)
+        .bind(&subscriber.domain)
+        .bind(Json(&subscriber))
+        .bind(subscriber.created_at)
+        .bind(subscriber.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Subscriber with ID {} already exists",
+                subscriber.id
+            )));
+        }
+
+        Ok(subscriber)
+    }
+
+    async fn get_subscriber(&self, id: &str) -> StorageResult<Subscriber> {
+        sqlx::query_scalar::<_, Json<Subscriber>>("SELECT data FROM subscribers WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|Json(subscriber)| subscriber)
+            .ok_or_else(|| StorageError::NotFound(format!("Subscriber with ID {} not found", id)))
+    }
+
+    async fn update_subscriber(&self, subscriber: Subscriber) -> StorageResult<Subscriber> {
+        let result = sqlx::query(
+            "UPDATE subscribers SET type_field = $2, domain = $3, data = $4, updated_at = $5
+             WHERE id = $1",
+        )
+        .bind(&subscriber.id)
+        .bind(&subscriber.type_field)
+        .bind(&subscriber.domain)
+        .bind(Json(&subscriber))
+        .bind(subscriber.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Subscriber with ID {} not found",
+                subscriber.id
+            )));
+        }
+
+        Ok(subscriber)
+    }
+
+    async fn lookup_subscriber(&self, lookup: NetworkRegistryLookup) -> StorageResult<Subscriber> {
+        // Simplified lookup that just checks the subscriber type and domain
+        sqlx::query_scalar::<_, Json<Subscriber>>(
+            "SELECT data FROM subscribers WHERE type_field = $1 AND domain = $2
+             ORDER BY created_at, id LIMIT 1",
+        )
+        .bind(&lookup.type_field)
+        .bind(&lookup.domain)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|Json(subscriber)| subscriber)
+        .ok_or_else(|| {
+            StorageError::NotFound(format!("No matching subscriber found for {:?}", lookup))
+        })
+    }
+
+    async fn list_subscribers(&self) -> StorageResult<Vec<Subscriber>> {
+        let subscribers = sqlx::query_scalar::<_, Json<Subscriber>>(
+            "SELECT data FROM subscribers ORDER BY id COLLATE \"C\"",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(subscribers
+            .into_iter()
+            .map(|Json(subscriber)| subscriber)
+            .collect())
+    }
+
+    async fn append_subscriber_status_change(
+        &self,
+        change: SubscriberStatusChange,
+    ) -> StorageResult<()> {
+        sqlx::query(
+            "INSERT INTO subscriber_status_changes (subscriber_id, data, changed_at)
+             VALUES ($1, $2, $3)",
+        )
+        .bind(&change.subscriber_id)
+        .bind(Json(&change))
+        .bind(change.changed_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_subscriber_status_changes(
+        &self,
+        subscriber_id: &str,
+    ) -> StorageResult<Vec<SubscriberStatusChange>> {
+        let changes = sqlx::query_scalar::<_, Json<SubscriberStatusChange>>(
+            "SELECT data FROM subscriber_status_changes WHERE subscriber_id = $1 ORDER BY seq",
+        )
+        .bind(subscriber_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes.into_iter().map(|Json(change)| change).collect())
+    }
+
+    // Transaction tracking
+    async fn record_transaction(
+        &self,
+        transaction_id: &str,
+        data: serde_json::Value,
+    ) -> StorageResult<()> {
+        sqlx::query(
+            "INSERT INTO transactions (transaction_id, data) VALUES ($1, $2)
+             ON CONFLICT (transaction_id) DO UPDATE SET data = EXCLUDED.data, updated_at = NOW()",
+        )
+        .bind(transaction_id)
+        .bind(data)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_transaction(&self, transaction_id: &str) -> StorageResult<serde_json::Value> {
+        sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT data FROM transactions WHERE transaction_id = $1",
+        )
+        .bind(transaction_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| {
+            StorageError::NotFound(format!("Transaction with ID {} not found", transaction_id))
+        })
+    }
+
+    // Search responses
+    async fn record_search_response(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+        provider_id: &str,
+        response: SearchResponse,
+    ) -> StorageResult<()> {
+        sqlx::query(
+            "INSERT INTO search_responses (transaction_id, message_id, provider_id, data)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT (transaction_id, message_id, provider_id)
+             DO UPDATE SET data = EXCLUDED.data, received_at = NOW()",
+        )
+        .bind(transaction_id)
+        .bind(message_id)
+        .bind(provider_id)
+        .bind(Json(&response))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_search_responses(
+        &self,
+        transaction_id: &str,
+        message_id: &str,
+    ) -> StorageResult<Vec<(String, SearchResponse)>> {
+        let rows = sqlx::query_as::<_, (String, Json<SearchResponse>)>(
+            "SELECT provider_id, data FROM search_responses
+             WHERE transaction_id = $1 AND message_id = $2
+             ORDER BY provider_id COLLATE \"C\"",
+        )
+        .bind(transaction_id)
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(provider_id, Json(response))| (provider_id, response))
+            .collect())
+    }
+
+    // Ratings
+    async fn record_ratings(
+        &self,
+        order_id: &str,
+        ratings: Vec<Rating>,
+    ) -> StorageResult<Vec<AggregateRating>> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut aggregates = Vec::with_capacity(ratings.len());
+        for rating in ratings {
+            let category = rating.rating_category.code();
+            let result = sqlx::query(
+                "INSERT INTO order_ratings (order_id, category, entity_id, data)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (order_id, category, entity_id) DO NOTHING",
+            )
+            .bind(order_id)
+            .bind(category)
+            .bind(&rating.id)
+            .bind(Json(&rating))
+            .execute(&mut *tx)
+            .await?;
+
+            // Dropping the transaction rolls back the ratings recorded so far
+            if result.rows_affected() == 0 {
+                return Err(StorageError::Duplicate(format!(
+                    "{} {} has already been rated for order {}",
+                    category, rating.id, order_id
+                )));
+            }
+
+            let (count, total, updated_at) = sqlx::query_as::<_, (i64, i64, DateTime<Utc>)>(
+                "INSERT INTO rating_aggregates (category, entity_id, count, total)
+                 VALUES ($1, $2, 1, $3)
+                 ON CONFLICT (category, entity_id) DO UPDATE
+                 SET count = rating_aggregates.count + 1,
+                     total = rating_aggregates.total + EXCLUDED.total,
+                     updated_at = NOW()
+                 RETURNING count, total, updated_at",
+            )
+            .bind(category)
+            .bind(&rating.id)
+            .bind(i64::from(rating.value))
+            .fetch_one(&mut *tx)
+            .await?;
+
+            aggregates.push(AggregateRating::from_total(
+                rating.rating_category,
+                rating.id,
+                count as u64,
+                total as u64,
+                updated_at,
+            ));
+        }
+
+        tx.commit().await?;
+        Ok(aggregates)
+    }
+
+    async fn get_rating_aggregate(
+        &self,
+        category: RatingCategory,
+        id: &str,
+    ) -> StorageResult<AggregateRating> {
+        sqlx::query_as::<_, (i64, i64, DateTime<Utc>)>(
+            "SELECT count, total, updated_at FROM rating_aggregates
+             WHERE category = $1 AND entity_id = $2",
+        )
+        .bind(category.code())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|(count, total, updated_at)| {
+            AggregateRating::from_total(category, id, count as u64, total as u64, updated_at)
+        })
+        .ok_or_else(|| {
+            StorageError::NotFound(format!("{} {} has not been rated", category.code(), id))
+        })
+    }
+
+    // Message deduplication
+    async fn create_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage> {
+        // Expired records are replaced in place
+        let result = sqlx::query(
+            "INSERT INTO processed_messages (subscriber_id, message_id, action, data, expires_at)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT (subscriber_id, message_id, action) DO UPDATE
+             SET data = EXCLUDED.data, expires_at = EXCLUDED.expires_at
+             WHERE processed_messages.expires_at <= $6",
+        )
+        .bind(&message.subscriber_id)
+        .bind(&message.message_id)
+        .bind(&message.action)
+        .bind(Json(&message))
+        .bind(message.expires_at)
+        .bind(message.received_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::Duplicate(format!(
+                "Message {} for {} from {} already received",
+                message.message_id, message.action, message.subscriber_id
+            )));
+        }
+
+        Ok(message)
+    }
+
+    async fn get_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<ProcessedMessage> {
+        sqlx::query_scalar::<_, Json<ProcessedMessage>>(
+            "SELECT data FROM processed_messages
+             WHERE subscriber_id = $1 AND message_id = $2 AND action = $3",
+        )
+        .bind(subscriber_id)
+        .bind(message_id)
+        .bind(action)
+        .fetch_optional(&self.pool)
+        .await?
+        .map(|Json(message)| message)
+        .ok_or_else(|| {
+            StorageError::NotFound(format!(
+                "Message {} for {} from {} not found",
+                message_id, action, subscriber_id
+            ))
+        })
+    }
+
+    async fn update_processed_message(
+        &self,
+        message: ProcessedMessage,
+    ) -> StorageResult<ProcessedMessage> {
+        let result = sqlx::query(
+            "UPDATE processed_messages SET data = $4, expires_at = $5
+             WHERE subscriber_id = $1 AND message_id = $2 AND action = $3",
+        )
+        .bind(&message.subscriber_id)
+        .bind(&message.message_id)
+        .bind(&message.action)
+        .bind(Json(&message))
+        .bind(message.expires_at)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Message {} for {} from {} not found",
+                message.message_id, message.action, message.subscriber_id
+            )));
+        }
+
+        Ok(message)
+    }
+
+    async fn delete_processed_message(
+        &self,
+        subscriber_id: &str,
+        message_id: &str,
+        action: &str,
+    ) -> StorageResult<()> {
+        let result = sqlx::query(
+            "DELETE FROM processed_messages
+             WHERE subscriber_id = $1 AND message_id = $2 AND action = $3",
+        )
+        .bind(subscriber_id)
+        .bind(message_id)
+        .bind(action)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(StorageError::NotFound(format!(
+                "Message {} for {} from {} not found",
+                message_id, action, subscriber_id
+            )));
+        }
+
+        Ok(())
+    }
+}
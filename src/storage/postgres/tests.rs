@@ -0,0 +1,84 @@
+use super::*;
+use crate::storage::conformance::{storage_conformance_tests, StorageFixture};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Environment variable naming the database used by the PostgreSQL tests
+const TEST_DATABASE_URL: &str = "UHI_TEST_DATABASE_URL";
+
+/// PostgreSQL storage isolated in its own schema, which is dropped with the
+/// fixture so test runs do not leave schemas behind
+struct SchemaFixture {
+    storage: Arc<dyn Storage>,
+    admin_pool: PgPool,
+    schema: String,
+}
+
+impl StorageFixture for SchemaFixture {
+    fn storage(&self) -> Arc<dyn Storage> {
+        Arc::clone(&self.storage)
+    }
+}
+
+impl Drop for SchemaFixture {
+    fn drop(&mut self) {
+        let drop_schema = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.schema);
+        let result = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current()
+                .block_on(sqlx::query(&drop_schema).execute(&self.admin_pool))
+        });
+        if let Err(err) = result {
+            eprintln!("Failed to drop test schema {}: {}", self.schema, err);
+        }
+    }
+}
+
+/// Connect to the test database with every test isolated in its own schema.
+///
+/// Returns `None` when `UHI_TEST_DATABASE_URL` is not set so the suite can
+/// run on machines without a local Postgres.
+async fn test_storage() -> Option<SchemaFixture> {
+    let url = match std::env::var(TEST_DATABASE_URL) {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!(
+                "{} not set, skipping PostgreSQL storage test",
+                TEST_DATABASE_URL
+            );
+            return None;
+        }
+    };
+
+    let schema = format!("uhi_test_{}", Uuid::new_v4().simple());
+    let admin_pool = PgPool::connect(&url).await.unwrap();
+    sqlx::query(&format!("CREATE SCHEMA {}", schema))
+        .execute(&admin_pool)
+        .await
+        .unwrap();
+
+    let search_path = format!("SET search_path TO {}", schema);
+    let pool = PgPoolOptions::new()
+        .max_connections(5)
+        .after_connect(move |conn, _meta| {
+            let search_path = search_path.clone();
+            Box::pin(async move {
+                sqlx::query(&search_path).execute(conn).await?;
+                Ok(())
+            })
+        })
+        .connect(&url)
+        .await
+        .unwrap();
+
+    // Own the fixture before migrating so a failed migration is cleaned up too
+    let storage = Arc::new(PostgresStorage::from_pool(pool));
+    let fixture = SchemaFixture {
+        storage: storage.clone(),
+        admin_pool,
+        schema,
+    };
+    storage.run_migrations().await.unwrap();
+    Some(fixture)
+}
+
+storage_conformance_tests!(test_storage);
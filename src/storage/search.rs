@@ -0,0 +1,599 @@
+//! Catalog search filtering shared by all storage backends.
+//!
+//! Backends load the candidate catalogs and hand them to `search_catalogs`,
+//! which applies the `SearchRequest` filters and merges the matching items of
+//! every provider into a single catalog.
+
+use crate::models::catalog::{Catalog, Item, SearchRequest, SearchResponse};
+use crate::models::provider::Location;
+use crate::storage::{StorageError, StorageResult};
+use chrono::Utc;
+use std::collections::HashMap;
+
+/// Radius used when a search location carries GPS coordinates (in kilometers)
+pub const DEFAULT_SEARCH_RADIUS_KM: f64 = 10.0;
+
+/// Tag added to every matched item, category and location to identify the
+/// provider it belongs to
+pub const PROVIDER_ID_TAG: &str = "provider_id";
+
+/// Filter the given provider catalogs and merge the matches into one catalog
+///
+/// # Parameters
+/// * `catalogs` - Provider ID and catalog pairs, in the order results should appear
+/// * `request` - Search criteria to apply
+///
+/// # Returns
+/// * A catalog holding only the matching items and the categories, locations and
+///   fulfillments they reference
+pub fn search_catalogs(
+    catalogs: Vec<(String, Catalog)>,
+    request: &SearchRequest,
+) -> StorageResult<SearchResponse> {
+    if catalogs.is_empty() {
+        return Err(StorageError::NotFound("No catalogs found".to_string()));
+    }
+
+    let mut merged: Option<Catalog> = None;
+
+    for (provider_id, catalog) in catalogs {
+        // Expired catalogs are stale and must not be served
+        if catalog.exp.is_some_and(|exp| exp < Utc::now()) {
+            continue;
+        }
+
+        let filtered = match filter_catalog(&provider_id, &catalog, request) {
+            Some(filtered) => filtered,
+            None => continue,
+        };
+
+        match merged.as_mut() {
+            None => merged = Some(filtered),
+            Some(merged) => merge_into(merged, filtered),
+        }
+    }
+
+    merged
+        .map(|catalog| SearchResponse { catalog })
+        .ok_or_else(|| {
+            StorageError::NotFound("No catalog items match the search criteria".to_string())
+        })
+}
+
+/// Reduce a catalog to the items matching the request, or `None` if nothing matches
+fn filter_catalog(
+    provider_id: &str,
+    catalog: &Catalog,
+    request: &SearchRequest,
+) -> Option<Catalog> {
+    // Payment filters apply to the whole catalog
+    if let Some(payment) = &request.payment {
+        if !catalog
+            .payments
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(payment))
+        {
+            return None;
+        }
+    }
+
+    let items: Vec<Item> = catalog
+        .items
+        .iter()
+        .filter(|item| matches_query(item, catalog, &request.query))
+        .filter(|item| {
+            request
+                .item
+                .as_ref()
+                .is_none_or(|criteria| matches_item(item, criteria))
+        })
+        .filter(|item| {
+            request
+                .fulfillment
+                .as_ref()
+                .is_none_or(|fulfillment| item.fulfillment_id.eq_ignore_ascii_case(fulfillment))
+        })
+        .filter(|item| {
+            request
+                .location
+                .as_ref()
+                .is_none_or(|location| matches_location(item, catalog, location))
+        })
+        .cloned()
+        .collect();
+
+    if items.is_empty() {
+        return None;
+    }
+
+    let categories = catalog
+        .categories
+        .iter()
+        .filter(|category| items.iter().any(|item| item.category_id == category.id))
+        .cloned()
+        .collect();
+
+    let locations = catalog
+        .locations
+        .iter()
+        .filter(|location| {
+            items
+                .iter()
+                .any(|item| item.location_id.as_deref() == Some(location.id.as_str()))
+        })
+        .cloned()
+        .collect();
+
+    let fulfillments = catalog
+        .fulfillments
+        .iter()
+        .filter(|fulfillment| {
+            items
+                .iter()
+                .any(|item| item.fulfillment_id == **fulfillment)
+        })
+        .cloned()
+        .collect();
+
+    let payments = match &request.payment {
+        Some(payment) => catalog
+            .payments
+            .iter()
+            .filter(|p| p.eq_ignore_ascii_case(payment))
+            .cloned()
+            .collect(),
+        None => catalog.payments.clone(),
+    };
+
+    let mut filtered = Catalog {
+        descriptor: catalog.descriptor.clone(),
+        categories,
+        fulfillments,
+        payments,
+        locations,
+        items,
+        exp: catalog.exp,
+    };
+    tag_provider(&mut filtered, provider_id);
+    Some(filtered)
+}
+
+/// Tag the items, categories and locations of a provider's catalog with the
+/// provider's ID
+pub(crate) fn tag_provider(catalog: &mut Catalog, provider_id: &str) {
+    let items = catalog.items.iter_mut().map(|item| &mut item.tags);
+    let categories = catalog.categories.iter_mut().map(|c| &mut c.tags);
+    let locations = catalog.locations.iter_mut().map(|l| &mut l.tags);
+
+    for tags in items.chain(categories).chain(locations) {
+        tags.get_or_insert_with(HashMap::new)
+            .insert(PROVIDER_ID_TAG.to_string(), provider_id.to_string());
+    }
+}
+
+/// ID of the provider a record was tagged with, empty if it is untagged
+pub(crate) fn provider_of(tags: &Option<HashMap<String, String>>) -> &str {
+    tags.as_ref()
+        .and_then(|tags| tags.get(PROVIDER_ID_TAG))
+        .map_or("", String::as_str)
+}
+
+/// Match the free-form query keys; values of one key are alternatives, keys are combined
+fn matches_query(item: &Item, catalog: &Catalog, query: &HashMap<String, Vec<String>>) -> bool {
+    query.iter().all(|(key, values)| {
+        if values.is_empty() {
+            return true;
+        }
+
+        match key.as_str() {
+            "name" => values
+                .iter()
+                .any(|value| contains_ignore_case(&item.descriptor.name, value)),
+            "category" => {
+                let category = catalog
+                    .categories
+                    .iter()
+                    .find(|category| category.id == item.category_id);
+                values.iter().any(|value| {
+                    item.category_id.eq_ignore_ascii_case(value)
+                        || category.is_some_and(|c| contains_ignore_case(&c.descriptor.name, value))
+                })
+            }
+            "tags" => values.iter().any(|value| matches_tag(item, value)),
+            // Unknown keys are not item filters and are left to the providers
+            _ => true,
+        }
+    })
+}
+
+/// Match a tag filter given as `key=value` or a bare key or value. Only `=`
+/// separates the key, so values may contain colons, e.g. `code=std:080`.
+fn matches_tag(item: &Item, filter: &str) -> bool {
+    let tags = match &item.tags {
+        Some(tags) => tags,
+        None => return false,
+    };
+
+    match filter.split_once('=') {
+        Some((key, value)) => tags
+            .get(key.trim())
+            .is_some_and(|tag| tag.eq_ignore_ascii_case(value.trim())),
+        None => tags.iter().any(|(key, value)| {
+            key.eq_ignore_ascii_case(filter) || value.eq_ignore_ascii_case(filter)
+        }),
+    }
+}
+
+/// Match the item descriptor criteria; empty fields are treated as wildcards
+fn matches_item(item: &Item, criteria: &Item) -> bool {
+    (criteria.id.is_empty() || item.id == criteria.id)
+        && (criteria.descriptor.name.is_empty()
+            || contains_ignore_case(&item.descriptor.name, &criteria.descriptor.name))
+        && (criteria.category_id.is_empty() || item.category_id == criteria.category_id)
+        && (criteria.fulfillment_id.is_empty()
+            || item
+                .fulfillment_id
+                .eq_ignore_ascii_case(&criteria.fulfillment_id))
+}
+
+/// Match the item's catalog location against the requested location
+fn matches_location(item: &Item, catalog: &Catalog, criteria: &Location) -> bool {
+    let location = match item
+        .location_id
+        .as_ref()
+        .and_then(|id| catalog.locations.iter().find(|location| location.id == *id))
+    {
+        Some(location) => location,
+        None => return false,
+    };
+
+    let optional_matches = |wanted: &Option<String>, actual: &Option<String>| match wanted {
+        Some(wanted) => actual
+            .as_ref()
+            .is_some_and(|actual| actual.eq_ignore_ascii_case(wanted)),
+        None => true,
+    };
+
+    if !criteria.id.is_empty() && criteria.id != location.id {
+        return false;
+    }
+
+    if !optional_matches(&criteria.city, &location.city)
+        || !optional_matches(&criteria.area_code, &location.area_code)
+        || !optional_matches(&criteria.country, &location.country)
+    {
+        return false;
+    }
+
+    match (parse_gps(&criteria.gps), parse_gps(&location.gps)) {
+        (Some(wanted), Some(actual)) => distance_km(wanted, actual) <= DEFAULT_SEARCH_RADIUS_KM,
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+/// Merge a provider-tagged catalog into the accumulated result, skipping
+/// duplicates. Categories and locations are only duplicates when they belong
+/// to the same provider, since IDs are only unique within a provider's catalog.
+pub(crate) fn merge_into(merged: &mut Catalog, catalog: Catalog) {
+    merged.items.extend(catalog.items);
+
+    for category in catalog.categories {
+        if !merged
+            .categories
+            .iter()
+            .any(|c| c.id == category.id && provider_of(&c.tags) == provider_of(&category.tags))
+        {
+            merged.categories.push(category);
+        }
+    }
+
+    for location in catalog.locations {
+        if !merged
+            .locations
+            .iter()
+            .any(|l| l.id == location.id && provider_of(&l.tags) == provider_of(&location.tags))
+        {
+            merged.locations.push(location);
+        }
+    }
+
+    for fulfillment in catalog.fulfillments {
+        if !merged.fulfillments.contains(&fulfillment) {
+            merged.fulfillments.push(fulfillment);
+        }
+    }
+
+    for payment in catalog.payments {
+        if !merged.payments.contains(&payment) {
+            merged.payments.push(payment);
+        }
+    }
+
+    // The merged catalog is only as fresh as its stalest source
+    merged.exp = match (merged.exp, catalog.exp) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    };
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Parse "latitude,longitude" coordinates, returning `None` for empty or invalid input
+pub(crate) fn parse_gps(gps: &str) -> Option<(f64, f64)> {
+    let (lat, lng) = gps.split_once(',')?;
+    let lat = lat.trim().parse::<f64>().ok()?;
+    let lng = lng.trim().parse::<f64>().ok()?;
+
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) {
+        Some((lat, lng))
+    } else {
+        None
+    }
+}
+
+/// Great-circle distance between two coordinates using the Haversine formula
+fn distance_km((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    const EARTH_RADIUS: f64 = 6371.0;
+
+    let dlat = (lat2 - lat1).to_radians();
+    let dlng = (lng2 - lng1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlng / 2.0).sin().powi(2);
+
+    EARTH_RADIUS * 2.0 * a.sqrt().atan2((1.0 - a).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::catalog::Price;
+    use crate::models::provider::{Category, Descriptor};
+
+    fn descriptor(name: &str) -> Descriptor {
+        Descriptor {
+            name: name.to_string(),
+            short_desc: None,
+            long_desc: None,
+            images: None,
+        }
+    }
+
+    fn location(id: &str, city: &str, gps: &str) -> Location {
+        Location {
+            id: id.to_string(),
+            descriptor: descriptor(id),
+            gps: gps.to_string(),
+            address: None,
+            city: Some(city.to_string()),
+            state: None,
+            country: Some("IND".to_string()),
+            area_code: None,
+            tags: None,
+        }
+    }
+
+    fn item(id: &str, name: &str, category_id: &str, fulfillment: &str, location_id: &str) -> Item {
+        Item {
+            id: id.to_string(),
+            parent_item_id: None,
+            descriptor: descriptor(name),
+            price: Price {
+                currency: "INR".to_string(),
+                value: "500".to_string(),
+                maximum_value: None,
+            },
+            category_id: category_id.to_string(),
+            fulfillment_id: fulfillment.to_string(),
+            location_id: Some(location_id.to_string()),
+            time: None,
+            recommended: None,
+            tags: Some(HashMap::from([(
+                "language".to_string(),
+                "Kannada".to_string(),
+            )])),
+        }
+    }
+
+    fn test_catalog() -> Catalog {
+        Catalog {
+            descriptor: descriptor("City Hospital"),
+            categories: vec![
+                Category {
+                    id: "cardio".to_string(),
+                    descriptor: descriptor("Cardiology"),
+                    time: None,
+                    tags: None,
+                },
+                Category {
+                    id: "derma".to_string(),
+                    descriptor: descriptor("Dermatology"),
+                    time: None,
+                    tags: None,
+                },
+            ],
+            fulfillments: vec!["teleconsultation".to_string(), "physical".to_string()],
+            payments: vec!["ON-ORDER".to_string()],
+            locations: vec![
+                location("blr", "Bangalore", "12.9716,77.5946"),
+                location("mys", "Mysore", "12.2958,76.6394"),
+            ],
+            items: vec![
+                item(
+                    "ecg",
+                    "ECG Consultation",
+                    "cardio",
+                    "teleconsultation",
+                    "blr",
+                ),
+                item("skin", "Skin Checkup", "derma", "physical", "mys"),
+            ],
+            exp: None,
+        }
+    }
+
+    fn request(query: &[(&str, &str)]) -> SearchRequest {
+        SearchRequest {
+            query: query
+                .iter()
+                .map(|(key, value)| (key.to_string(), vec![value.to_string()]))
+                .collect(),
+            item: None,
+            fulfillment: None,
+            payment: None,
+            location: None,
+        }
+    }
+
+    fn search(request: &SearchRequest) -> StorageResult<SearchResponse> {
+        search_catalogs(vec![("provider-1".to_string(), test_catalog())], request)
+    }
+
+    #[test]
+    fn test_filter_by_name_keeps_referenced_categories_and_locations() {
+        let response = search(&request(&[("name", "ecg")])).unwrap();
+        let catalog = response.catalog;
+
+        assert_eq!(catalog.items.len(), 1);
+        assert_eq!(catalog.items[0].id, "ecg");
+        assert_eq!(catalog.categories.len(), 1);
+        assert_eq!(catalog.categories[0].id, "cardio");
+        assert_eq!(catalog.locations.len(), 1);
+        assert_eq!(catalog.locations[0].id, "blr");
+        assert_eq!(catalog.fulfillments, vec!["teleconsultation".to_string()]);
+        assert_eq!(
+            catalog.items[0].tags.as_ref().unwrap().get(PROVIDER_ID_TAG),
+            Some(&"provider-1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_filter_by_category_name_and_tags() {
+        let by_category = search(&request(&[("category", "dermatology")])).unwrap();
+        assert_eq!(by_category.catalog.items[0].id, "skin");
+
+        let by_tag = search(&request(&[("tags", "language=kannada")])).unwrap();
+        assert_eq!(by_tag.catalog.items.len(), 2);
+
+        let result = search(&request(&[("tags", "language=hindi")]));
+        assert!(matches!(result, Err(StorageError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_tag_values_may_contain_colons() {
+        let mut tagged = item("ecg", "ECG", "cardio", "physical", "mys");
+        tagged
+            .tags
+            .get_or_insert_with(HashMap::new)
+            .insert("code".to_string(), "std:080".to_string());
+
+        assert!(matches_tag(&tagged, "code=std:080"));
+        assert!(matches_tag(&tagged, "std:080"));
+        assert!(!matches_tag(&tagged, "code=std"));
+    }
+
+    #[test]
+    fn test_filter_by_fulfillment_and_payment() {
+        let mut req = request(&[]);
+        req.fulfillment = Some("physical".to_string());
+        let response = search(&req).unwrap();
+        assert_eq!(response.catalog.items.len(), 1);
+        assert_eq!(response.catalog.items[0].id, "skin");
+
+        req.payment = Some("ON-DELIVERY".to_string());
+        assert!(search(&req).is_err());
+    }
+
+    #[test]
+    fn test_filter_by_item_descriptor() {
+        let mut req = request(&[]);
+        req.item = Some(item("", "checkup", "", "", ""));
+        let response = search(&req).unwrap();
+        assert_eq!(response.catalog.items.len(), 1);
+        assert_eq!(response.catalog.items[0].id, "skin");
+    }
+
+    #[test]
+    fn test_filter_by_location_city_and_gps() {
+        let mut req = request(&[]);
+        req.location = Some(Location {
+            city: Some("mysore".to_string()),
+            country: None,
+            ..location("", "", "")
+        });
+        let response = search(&req).unwrap();
+        assert_eq!(response.catalog.items[0].id, "skin");
+
+        // A few kilometers away from the Bangalore clinic
+        req.location = Some(Location {
+            city: None,
+            country: None,
+            ..location("", "", "12.9352,77.6245")
+        });
+        let response = search(&req).unwrap();
+        assert_eq!(response.catalog.items.len(), 1);
+        assert_eq!(response.catalog.items[0].id, "ecg");
+    }
+
+    #[test]
+    fn test_merges_matches_across_providers() {
+        let mut other = test_catalog();
+        other.descriptor = descriptor("Other Clinic");
+        other.items.truncate(1);
+        other.items[0].id = "ecg-2".to_string();
+
+        let response = search_catalogs(
+            vec![
+                ("provider-1".to_string(), test_catalog()),
+                ("provider-2".to_string(), other),
+            ],
+            &request(&[("category", "cardio")]),
+        )
+        .unwrap();
+
+        let providers: Vec<_> = response
+            .catalog
+            .items
+            .iter()
+            .map(|item| item.tags.as_ref().unwrap()[PROVIDER_ID_TAG].clone())
+            .collect();
+        assert_eq!(providers, vec!["provider-1", "provider-2"]);
+
+        // Both providers use the same IDs, which only identify records within
+        // a provider's catalog
+        let categories: Vec<_> = response
+            .catalog
+            .categories
+            .iter()
+            .map(|category| (provider_of(&category.tags), category.id.as_str()))
+            .collect();
+        assert_eq!(
+            categories,
+            vec![("provider-1", "cardio"), ("provider-2", "cardio")]
+        );
+        let locations: Vec<_> = response
+            .catalog
+            .locations
+            .iter()
+            .map(|location| (provider_of(&location.tags), location.id.as_str()))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![("provider-1", "blr"), ("provider-2", "blr")]
+        );
+    }
+
+    #[test]
+    fn test_expired_catalogs_are_skipped() {
+        let mut catalog = test_catalog();
+        catalog.exp = Some(Utc::now() - chrono::Duration::hours(1));
+
+        let result = search_catalogs(
+            vec![("provider-1".to_string(), catalog)],
+            &request(&[("name", "ecg")]),
+        );
+        assert!(matches!(result, Err(StorageError::NotFound(_))));
+    }
+}